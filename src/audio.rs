@@ -0,0 +1,87 @@
+use macroquad::audio::{load_sound, play_sound, PlaySoundParams, Sound};
+
+use crate::timer::Timer;
+
+const CUE_COOLDOWN: f32 = 3.;
+// an event this far ahead, or nearer, plays at full volume
+const URGENT_TIME: f32 = 5.;
+const MIN_CUE_VOLUME: f32 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cue {
+  CloseApproach,
+  Collision,
+  SoiChange,
+}
+
+impl Cue {
+  fn file(&self) -> &'static str {
+    match self {
+      Self::CloseApproach => "close_approach.wav",
+      Self::Collision => "collision.wav",
+      Self::SoiChange => "soi_change.wav",
+    }
+  }
+}
+
+// alert sounds played at most once per cooldown for each cue; cues without a
+// loadable sound file stay silent
+pub struct AudioCues {
+  sounds: Vec<(Cue, Sound)>,
+  cooldowns: Vec<(Cue, Timer)>,
+}
+
+impl AudioCues {
+  pub async fn load() -> Self {
+    let mut sounds = vec![];
+    for cue in [Cue::CloseApproach, Cue::Collision, Cue::SoiChange] {
+      if let Ok(sound) = load_sound(cue.file()).await {
+        sounds.push((cue, sound));
+      }
+    }
+    Self { sounds, cooldowns: vec![] }
+  }
+
+  pub fn update(&mut self, dt: f32) {
+    for (_, timer) in &mut self.cooldowns {
+      timer.update(dt);
+    }
+    self.cooldowns.retain(|(_, timer)| !timer.is_just_over());
+  }
+
+  // louder the sooner the event is due; returns whether the cue went through the debounce
+  pub fn trigger(&mut self, cue: Cue, time_to_event: f32) -> bool {
+    if self.cooldowns.iter().any(|(c, _)| *c == cue) {
+      return false;
+    }
+    self.cooldowns.push((cue, Timer::new(CUE_COOLDOWN)));
+    let volume = (URGENT_TIME / time_to_event.max(f32::EPSILON)).clamp(MIN_CUE_VOLUME, 1.);
+    if let Some((_, sound)) = self.sounds.iter().find(|(c, _)| *c == cue) {
+      play_sound(*sound, PlaySoundParams { looped: false, volume });
+    }
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn a_repeated_cue_plays_once_per_cooldown() {
+    let mut cues = AudioCues { sounds: vec![], cooldowns: vec![] };
+    let dt = 0.1;
+    let mut played = vec![];
+    // the same alert raised every frame for two cooldowns and a bit
+    for frame in 0..((CUE_COOLDOWN * 2.2 / dt) as usize) {
+      if cues.trigger(Cue::CloseApproach, 1.) {
+        played.push(frame as f32 * dt);
+      }
+      cues.update(dt);
+    }
+    assert_eq!(played.len(), 3);
+    assert!(played.windows(2).all(|w| w[1] - w[0] >= CUE_COOLDOWN));
+    // other cues have their own cooldown
+    assert!(cues.trigger(Cue::Collision, 1.));
+  }
+}
@@ -0,0 +1,350 @@
+use macroquad::prelude::*;
+
+use crate::orbit::{circularization_dv, escape_dv, kepler_position, lagrange_point, lambert_2d, orbit_summary, osculating_elements, TrojanPoint};
+use crate::params::SimParams;
+use crate::{CelestialBody, CelestialBodyReference, Ship, ShipState};
+
+const ASCENT_ORBIT_RADIUS_FACTOR: f32 = 1.5;
+const LIFTOFF_ALTITUDE_FACTOR: f32 = 0.05;
+const PITCH_OVER_RADIAL_WEIGHT: f32 = 0.3;
+const CIRCULARIZE_ALIGNMENT: f32 = 10.;
+const CIRCULARIZE_TOLERANCE: f32 = 0.5;
+const ESCAPE_ALIGNMENT: f32 = 10.;
+const DOCKING_ALIGNMENT: f32 = 10.;
+const DOCKING_TOLERANCE: f32 = 0.5;
+const COURSE_CORRECTION_THRESHOLD: f32 = 2.;
+const HOLD_DEADBAND: f32 = 1.;
+// fraction of the body's hill radius around the L-point that counts as arrived
+const L_POINT_ARRIVAL_FACTOR: f32 = 0.5;
+const L_POINT_CLOSING_TIME: f32 = 50.;
+
+// heading kept by the attitude hold; the throttle stays with the pilot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttitudeHold {
+  Prograde,
+  Retrograde,
+  Target,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AscentPhase {
+  Liftoff,
+  PitchOver,
+  Coast,
+  Circularize,
+}
+
+pub struct AscentAutopilot {
+  body: CelestialBodyReference,
+  target_radius: f32,
+  pub phase: AscentPhase,
+}
+
+impl AscentAutopilot {
+  pub fn new(ship: &Ship) -> Option<Self> {
+    match &ship.state {
+      ShipState::Landed(cb, _, _) => Some(Self {
+        body: cb.clone(),
+        target_radius: cb.borrow().radius * ASCENT_ORBIT_RADIUS_FACTOR,
+        phase: AscentPhase::Liftoff,
+      }),
+      _ => None,
+    }
+  }
+
+  // returns a report once the ascent is finished or aborted
+  pub fn update(&mut self, ship: &mut Ship, params: &SimParams, dt: f32) -> Option<String> {
+    let body = self.body.borrow();
+    let mu = params.config.g * body.mov.mass;
+    let rel_pos = ship.mov.pos - body.mov.pos;
+    let rel_vel = ship.mov.vel - body.mov.vel;
+    let radial = rel_pos.normalize_or_zero();
+    let summary = || orbit_summary(rel_pos, rel_vel, mu, &body.name, params.day_length);
+
+    if let ShipState::Destroyed = ship.state {
+      return Some(format!("Ascent aborted, ship destroyed near {}", body.name));
+    }
+    if ship.fuel <= 0. {
+      return Some(format!("Ascent cut off, out of fuel. {}", summary()));
+    }
+
+    match self.phase {
+      AscentPhase::Liftoff => {
+        ship.point_toward(radial, params, dt);
+        ship.throttle_up(params, dt);
+        if rel_pos.length() - body.radius > body.radius * LIFTOFF_ALTITUDE_FACTOR {
+          self.phase = AscentPhase::PitchOver;
+        }
+      },
+      AscentPhase::PitchOver => {
+        let horizontal = rel_pos.perp().normalize_or_zero();
+        ship.point_toward(horizontal + radial * PITCH_OVER_RADIAL_WEIGHT, params, dt);
+        ship.throttle_up(params, dt);
+        let apoapsis = osculating_elements(rel_pos, rel_vel, mu).apoapsis.unwrap_or(f32::INFINITY);
+        if apoapsis >= self.target_radius {
+          self.phase = AscentPhase::Coast;
+        }
+      },
+      AscentPhase::Coast => {
+        ship.point_toward(circularization_dv(rel_pos, rel_vel, mu), params, dt);
+        if rel_pos.dot(rel_vel) <= 0. || rel_pos.length() >= self.target_radius {
+          self.phase = AscentPhase::Circularize;
+        }
+      },
+      AscentPhase::Circularize => {
+        let dv = circularization_dv(rel_pos, rel_vel, mu);
+        if dv.length() < CIRCULARIZE_TOLERANCE {
+          return Some(format!("Ascent complete. {}", summary()));
+        }
+        ship.point_toward(dv, params, dt);
+        let heading = vec2(ship.mov.rot.cos(), ship.mov.rot.sin());
+        if heading.angle_between(dv).abs() < CIRCULARIZE_ALIGNMENT.to_radians() {
+          ship.throttle_up(params, dt);
+        }
+      },
+    }
+    None
+  }
+}
+
+// prograde burn around the dominant body until the orbit stops being bound
+pub struct EscapeAutopilot {
+  body: CelestialBodyReference,
+  pub remaining: f32,
+}
+
+impl EscapeAutopilot {
+  pub fn new(ship: &Ship, g: f32) -> Result<Self, String> {
+    let ShipState::InSpace = ship.state else {
+      return Err("Escape planner needs the ship in flight".to_owned());
+    };
+    let body = ship.dominant_body().ok_or("No body to escape from")?;
+    let dv = {
+      let b = body.borrow();
+      escape_dv(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, g * b.mov.mass)
+    };
+    if dv > ship.remaining_dv() {
+      return Err(format!("Escape from {} needs {:.1} delta-v, short by {:.1}", body.borrow().name, dv, dv - ship.remaining_dv()));
+    }
+    Ok(Self { body, remaining: dv })
+  }
+
+  // returns a report once the escape burn is finished or aborted
+  pub fn update(&mut self, ship: &mut Ship, params: &SimParams, dt: f32) -> Option<String> {
+    let body = self.body.borrow();
+    let mu = params.config.g * body.mov.mass;
+    let rel_pos = ship.mov.pos - body.mov.pos;
+    let rel_vel = ship.mov.vel - body.mov.vel;
+
+    if let ShipState::Destroyed = ship.state {
+      return Some(format!("Escape aborted, ship destroyed near {}", body.name));
+    }
+    self.remaining = escape_dv(rel_pos, rel_vel, mu);
+    if self.remaining <= 0. {
+      return Some(format!("Escape burn complete. {}", orbit_summary(rel_pos, rel_vel, mu, &body.name, params.day_length)));
+    }
+    if ship.fuel <= 0. {
+      return Some(format!("Escape cut off, out of fuel, {:.1} delta-v short", self.remaining));
+    }
+    ship.point_toward(rel_vel, params, dt);
+    let heading = vec2(ship.mov.rot.cos(), ship.mov.rot.sin());
+    if heading.angle_between(rel_vel).abs() < ESCAPE_ALIGNMENT.to_radians() {
+      ship.throttle_up(params, dt);
+    }
+    None
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DockingPhase {
+  Transfer,
+  Coast,
+  Match,
+  Hold,
+}
+
+// Lambert transfer around the star to a planet's L4 or L5 point, re-solved every step for the
+// remaining flight time, then matching the co-rotating point's velocity and holding there
+pub struct LPointAutopilot {
+  body: CelestialBodyReference,
+  star: CelestialBodyReference,
+  pub point: TrojanPoint,
+  arrival_at: f64,
+  pub phase: DockingPhase,
+  pub delta_v: f32,
+  pub distance: f32,
+}
+
+// velocity change that puts the ship on a transfer reaching the L-point after tof
+fn l_point_transfer_dv(ship: &Ship, body: &CelestialBody, star: &CelestialBody, point: TrojanPoint, tof: f32, g: f32) -> Option<Vec2> {
+  let mu = g * star.mov.mass;
+  let (body_pos, body_vel) = (body.mov.pos - star.mov.pos, body.mov.vel - star.mov.vel);
+  let (arrival, _) = lagrange_point(kepler_position(body_pos, body_vel, mu, tof), body_vel, point);
+  let v1 = lambert_2d(ship.mov.pos - star.mov.pos, arrival, tof, mu)?;
+  Some(v1 - (ship.mov.vel - star.mov.vel))
+}
+
+fn burn_toward(ship: &mut Ship, dv: Vec2, params: &SimParams, dt: f32) {
+  ship.point_toward(dv, params, dt);
+  let heading = vec2(ship.mov.rot.cos(), ship.mov.rot.sin());
+  if heading.angle_between(dv).abs() < DOCKING_ALIGNMENT.to_radians() {
+    ship.throttle_up(params, dt);
+  }
+}
+
+impl LPointAutopilot {
+  pub fn new(ship: &Ship, body: &CelestialBodyReference, star: &CelestialBodyReference, point: TrojanPoint, tof: f32, now: f64, g: f32) -> Result<Self, String> {
+    let ShipState::InSpace = ship.state else {
+      return Err("L-point docking needs the ship in flight".to_owned());
+    };
+    let name = body.borrow().name.clone();
+    let dv = l_point_transfer_dv(ship, &body.borrow(), &star.borrow(), point, tof, g)
+      .ok_or(format!("No transfer to {} {:?} in that time", name, point))?
+      .length();
+    if dv > ship.remaining_dv() {
+      return Err(format!("Transfer to {} {:?} needs {:.1} delta-v, short by {:.1}", name, point, dv, dv - ship.remaining_dv()));
+    }
+    Ok(Self {
+      body: body.clone(),
+      star: star.clone(),
+      point,
+      arrival_at: now + tof as f64,
+      phase: DockingPhase::Transfer,
+      delta_v: dv,
+      distance: f32::INFINITY,
+    })
+  }
+
+  pub fn time_to_arrival(&self, now: f64) -> f32 {
+    (self.arrival_at - now).max(0.) as f32
+  }
+
+  // returns a report once the docking is aborted; holding goes on until switched off
+  pub fn update(&mut self, ship: &mut Ship, params: &SimParams, now: f64, dt: f32) -> Option<String> {
+    let (body, star) = (self.body.borrow(), self.star.borrow());
+    if let ShipState::Destroyed = ship.state {
+      return Some(format!("Docking aborted, ship destroyed on the way to {} {:?}", body.name, self.point));
+    }
+    if ship.fuel <= 0. {
+      return Some(format!("Docking cut off, out of fuel {:.0} from {} {:?}", self.distance, body.name, self.point));
+    }
+
+    let (l_pos, l_vel) = lagrange_point(body.mov.pos - star.mov.pos, body.mov.vel - star.mov.vel, self.point);
+    let offset = ship.mov.pos - (star.mov.pos + l_pos);
+    self.distance = offset.length();
+    let arrived = self.distance < body.hill_radius * L_POINT_ARRIVAL_FACTOR;
+
+    match self.phase {
+      DockingPhase::Transfer | DockingPhase::Coast if arrived => {
+        self.phase = DockingPhase::Match;
+      },
+      DockingPhase::Transfer | DockingPhase::Coast => {
+        let tof = self.time_to_arrival(now);
+        let Some(dv) = l_point_transfer_dv(ship, &body, &star, self.point, tof, params.config.g).filter(|_| tof > 0.) else {
+          return Some(format!("Missed {} {:?} by {:.0}", body.name, self.point, self.distance));
+        };
+        self.delta_v = dv.length();
+        if self.phase == DockingPhase::Transfer {
+          if self.delta_v < DOCKING_TOLERANCE {
+            self.phase = DockingPhase::Coast;
+          } else {
+            burn_toward(ship, dv, params, dt);
+          }
+        } else if self.delta_v > COURSE_CORRECTION_THRESHOLD {
+          self.phase = DockingPhase::Transfer;
+        }
+      },
+      DockingPhase::Match | DockingPhase::Hold => {
+        // drift back towards the point while matching its motion
+        let dv = (star.mov.vel + l_vel) - offset / L_POINT_CLOSING_TIME - ship.mov.vel;
+        self.delta_v = dv.length();
+        let deadband = if self.phase == DockingPhase::Match { DOCKING_TOLERANCE } else { HOLD_DEADBAND };
+        if self.delta_v < deadband {
+          self.phase = DockingPhase::Hold;
+        } else {
+          burn_toward(ship, dv, params, dt);
+        }
+      },
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::orbit::transfer_arc;
+  use crate::params::ShipConfig;
+  use crate::physics::get_initial_position_and_velocity;
+  use crate::simulation::Simulation;
+  use crate::{wrap_object, CelestialBodyType, GameObjectReference};
+
+  fn star_and_planet(params: &SimParams) -> (CelestialBodyReference, CelestialBodyReference) {
+    let star = CelestialBody::new(Vec2::ZERO, 30000000., 7000., CelestialBodyType::Star, ORANGE, "Star".to_owned(), params);
+    let planet = CelestialBody::from_parent(&star, params.config.au, 0., 30000., 500., CelestialBodyType::Planet, BLUE, "Planet".to_owned(), params);
+    (wrap_object(star), wrap_object(planet))
+  }
+
+  // a ship on a circular orbit around the star at radius, at the given angle in degrees
+  fn orbiting_ship(star: &CelestialBody, radius: f32, angle: f32, params: &SimParams) -> Ship {
+    let (pos, vel) = get_initial_position_and_velocity(star.mov.mass, radius, angle, &params.config);
+    Ship::new(pos, vel, &ShipConfig::default(), "Ship".to_owned(), WHITE)
+  }
+
+  fn hohmann_time(r1: f32, r2: f32, mu: f32) -> f32 {
+    std::f32::consts::PI * ((r1 + r2) / 2.).powi(3).sqrt() / mu.sqrt()
+  }
+
+  #[test]
+  fn l4_leads_the_planet_by_sixty_degrees() {
+    let params = SimParams::default();
+    let (star, planet) = star_and_planet(&params);
+    let (p, s) = (planet.borrow(), star.borrow());
+    let (l4, _) = lagrange_point(p.mov.pos - s.mov.pos, p.mov.vel - s.mov.vel, TrojanPoint::L4);
+    assert!((l4.length() - params.config.au).abs() < 1e-2 * params.config.au);
+    assert!((l4.angle_between(p.mov.pos).abs() - 60_f32.to_radians()).abs() < 1e-3);
+  }
+
+  #[test]
+  fn transfer_across_the_star_to_an_l_point_is_planned() {
+    let params = SimParams::default();
+    let (star, planet) = star_and_planet(&params);
+    let mu = params.config.g * star.borrow().mov.mass;
+    let tof = hohmann_time(params.config.au / 2., params.config.au, mu);
+    // put the ship exactly opposite where L4 will be at arrival, the half-turn Lambert case
+    let (p, s) = (planet.borrow(), star.borrow());
+    let (arrival, _) = lagrange_point(kepler_position(p.mov.pos - s.mov.pos, p.mov.vel - s.mov.vel, mu, tof), p.mov.vel - s.mov.vel, TrojanPoint::L4);
+    let angle = (-arrival).y.atan2((-arrival).x).to_degrees();
+    let mut ship = orbiting_ship(&s, params.config.au / 2., angle, &params);
+
+    let dv = l_point_transfer_dv(&ship, &p, &s, TrojanPoint::L4, tof, params.config.g).unwrap();
+    let reached = *transfer_arc(ship.mov.pos - s.mov.pos, ship.mov.vel + dv - s.mov.vel, tof, mu, 2000).last().unwrap();
+    assert!(reached.distance(arrival) < 1e-2 * params.config.au);
+    drop((p, s));
+    ship.fuel = f32::MAX / 2.;
+    assert!(LPointAutopilot::new(&ship, &planet, &star, TrojanPoint::L4, tof, 0., params.config.g).is_ok());
+  }
+
+  #[test]
+  fn ascent_from_a_small_airless_body_reaches_a_bound_orbit() {
+    let params = SimParams::default();
+    let moon = wrap_object(CelestialBody::new(Vec2::ZERO, 2000., 200., CelestialBodyType::Star, GRAY, "Moon".to_owned(), &params));
+    let ship = wrap_object(Ship::new(vec2(200., 0.), Vec2::ZERO, &ShipConfig::default(), "Ship".to_owned(), WHITE));
+    ship.borrow_mut().land(moon.clone(), &params.config);
+    let game_objects: Vec<GameObjectReference> = vec![moon.clone(), ship.clone()];
+    let mut sim = Simulation::from_world(1, (moon.clone(), vec![moon.clone()], vec![moon.clone()], vec![], vec![ship.clone()], ship.clone(), game_objects, 0));
+
+    let mut ascent = AscentAutopilot::new(&ship.borrow()).unwrap();
+    let dt = params.step_dt();
+    let report = (0..200000).find_map(|_| {
+      let report = ascent.update(&mut ship.borrow_mut(), &params, dt);
+      sim.step(&params, dt);
+      report
+    });
+    assert!(report.unwrap().starts_with("Ascent complete"));
+    let s = ship.borrow();
+    let elements = osculating_elements(s.mov.pos - moon.borrow().mov.pos, s.mov.vel - moon.borrow().mov.vel, params.config.g * 2000.);
+    assert!(matches!(s.state, ShipState::InSpace));
+    assert!(elements.eccentricity < 1. && elements.periapsis > 200.);
+  }
+}
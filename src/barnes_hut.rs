@@ -0,0 +1,193 @@
+use macroquad::prelude::*;
+
+use crate::physics::gravity_acc;
+use crate::{CelestialBodyReference, MIN_PARALLEL_BODIES};
+
+// coincident bodies would split forever, past this depth they share a leaf
+const MAX_DEPTH: usize = 24;
+
+struct Node {
+  centre: Vec2,
+  half: f32,
+  mass: f32,
+  // sum of position times mass, divided by mass for the centre of mass
+  weighted_pos: Vec2,
+  children: Option<[usize; 4]>,
+  bodies: Vec<usize>,
+}
+
+impl Node {
+  fn new(centre: Vec2, half: f32) -> Self {
+    Self { centre, half, mass: 0., weighted_pos: Vec2::ZERO, children: None, bodies: vec![] }
+  }
+
+  fn quadrant(&self, pos: Vec2) -> usize {
+    (pos.x >= self.centre.x) as usize + 2 * (pos.y >= self.centre.y) as usize
+  }
+
+  fn contains(&self, pos: Vec2) -> bool {
+    (pos - self.centre).abs().max_element() <= self.half
+  }
+}
+
+// quadtree over a snapshot of the bodies; a node whose size is below theta times its distance
+// acts as a single mass at its centre of mass, so theta 0 reproduces the pairwise sum
+pub struct BarnesHut {
+  nodes: Vec<Node>,
+  bodies: Vec<(Vec2, f32)>,
+}
+
+impl BarnesHut {
+  pub fn new(celestial_bodies: &[CelestialBodyReference]) -> Self {
+    let bodies: Vec<(Vec2, f32)> = celestial_bodies.iter().map(|cb| (cb.borrow().mov.pos, cb.borrow().mov.mass)).collect();
+    let (min, max) = bodies.iter().fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), (p, _)| (min.min(*p), max.max(*p)));
+    let half = if bodies.is_empty() { 0. } else { (max - min).max_element() / 2. };
+    let mut tree = Self { nodes: vec![Node::new((min + max) / 2., half)], bodies };
+    for i in 0..tree.bodies.len() {
+      tree.insert(0, i, 0);
+    }
+    tree
+  }
+
+  fn insert(&mut self, node: usize, body: usize, depth: usize) {
+    let (pos, mass) = self.bodies[body];
+    self.nodes[node].mass += mass;
+    self.nodes[node].weighted_pos += pos * mass;
+    if let Some(children) = self.nodes[node].children {
+      let child = children[self.nodes[node].quadrant(pos)];
+      self.insert(child, body, depth + 1);
+      return;
+    }
+    if self.nodes[node].bodies.is_empty() || depth >= MAX_DEPTH {
+      self.nodes[node].bodies.push(body);
+      return;
+    }
+
+    let (centre, half) = (self.nodes[node].centre, self.nodes[node].half / 2.);
+    let first = self.nodes.len();
+    for (dx, dy) in [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)] {
+      self.nodes.push(Node::new(centre + vec2(dx, dy) * half, half));
+    }
+    self.nodes[node].children = Some([first, first + 1, first + 2, first + 3]);
+    for resident in std::mem::take(&mut self.nodes[node].bodies) {
+      let child = first + self.nodes[node].quadrant(self.bodies[resident].0);
+      self.insert(child, resident, depth + 1);
+    }
+    let child = first + self.nodes[node].quadrant(pos);
+    self.insert(child, body, depth + 1);
+  }
+
+  pub fn acceleration(&self, body: usize, g: f32, exponent: f32, softening: f32, theta: f32) -> Vec2 {
+    let pos = self.bodies[body].0;
+    let mut acc = Vec2::ZERO;
+    let mut stack = vec![0];
+    while let Some(n) = stack.pop() {
+      let node = &self.nodes[n];
+      if node.mass <= 0. {
+        continue;
+      }
+      match node.children {
+        None => {
+          for &other in node.bodies.iter().filter(|&&other| other != body) {
+            let (other_pos, other_mass) = self.bodies[other];
+            acc += gravity_acc(pos, 1., other_pos, other_mass, g, exponent, softening).0;
+          }
+        },
+        Some(children) => {
+          let centre_of_mass = node.weighted_pos / node.mass;
+          if !node.contains(pos) && node.half * 2. < theta * pos.distance(centre_of_mass) {
+            acc += gravity_acc(pos, 1., centre_of_mass, node.mass, g, exponent, softening).0;
+          } else {
+            stack.extend(children);
+          }
+        },
+      }
+    }
+    acc
+  }
+
+  // in the order of the bodies the tree was built from
+  pub fn accelerations(&self, g: f32, exponent: f32, softening: f32, theta: f32) -> Vec<Vec2> {
+    (0..self.bodies.len()).map(|i| self.acceleration(i, g, exponent, softening, theta)).collect()
+  }
+
+  // the same accelerations with the bodies split over scoped threads; the tree is only read, so
+  // every thread walks it for its own slice. Small belts and the web build stay serial
+  pub fn accelerations_threaded(&self, g: f32, exponent: f32, softening: f32, theta: f32, threads: usize) -> Vec<Vec2> {
+    if threads <= 1 || self.bodies.len() < MIN_PARALLEL_BODIES || cfg!(target_arch = "wasm32") {
+      return self.accelerations(g, exponent, softening, theta);
+    }
+    let (count, chunk) = (self.bodies.len(), self.bodies.len().div_ceil(threads));
+    std::thread::scope(|scope| {
+      let handles: Vec<_> = (0..count).step_by(chunk)
+        .map(|start| scope.spawn(move || {
+          (start..(start + chunk).min(count)).map(|i| self.acceleration(i, g, exponent, softening, theta)).collect::<Vec<_>>()
+        }))
+        .collect();
+      handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::SimConfig;
+  use crate::params::SimParams;
+  use crate::rng::SeededRng;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  fn asteroid(pos: Vec2, mass: f32) -> CelestialBodyReference {
+    wrap_object(CelestialBody::new(pos, mass, 20., CelestialBodyType::Asteroid, GRAY, "Ast".to_owned(), &SimParams::default()))
+  }
+
+  #[test]
+  fn theta_zero_matches_the_pairwise_sum() {
+    let mut rng = SeededRng::new(11);
+    let belt: Vec<CelestialBodyReference> = (0..40)
+      .map(|_| asteroid(vec2(rng.gen_range(-1000., 1000.), rng.gen_range(-1000., 1000.)), rng.gen_range(50., 150.)))
+      .collect();
+    let g = SimConfig::default().g;
+    for (exponent, softening) in [(2., 0.), (3., 5.)] {
+      let tree = BarnesHut::new(&belt);
+      for (i, acc) in tree.accelerations(g, exponent, softening, 0.).into_iter().enumerate() {
+        let a = belt[i].borrow();
+        let (mut expected, mut magnitude) = (Vec2::ZERO, 0.);
+        for b in belt.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, b)| b.borrow()) {
+          let pull = gravity_acc(a.mov.pos, a.mov.mass, b.mov.pos, b.mov.mass, g, exponent, softening).0;
+          expected += pull;
+          magnitude += pull.length();
+        }
+        assert!(acc.distance(expected) <= 1e-5 * magnitude, "{}: {} vs {}", i, acc, expected);
+      }
+    }
+  }
+
+  #[test]
+  fn coincident_bodies_stop_at_the_depth_cap() {
+    let (pos, far) = (vec2(100., 100.), vec2(-500., 300.));
+    let belt = [asteroid(pos, 100.), asteroid(pos, 100.), asteroid(far, 100.)];
+    let tree = BarnesHut::new(&belt);
+    assert!(tree.nodes.len() <= 1 + 4 * MAX_DEPTH);
+    let g = SimConfig::default().g;
+    let accelerations = tree.accelerations(g, 2., 1., 0.5);
+    assert!(accelerations.iter().all(|acc| acc.is_finite()));
+    let expected = gravity_acc(pos, 1., far, 100., g, 2., 1.).0;
+    assert_eq!(accelerations[0], expected);
+    assert_eq!(accelerations[1], expected);
+  }
+
+  #[test]
+  fn threaded_accelerations_match_the_serial_ones() {
+    let mut rng = SeededRng::new(5);
+    let belt: Vec<CelestialBodyReference> = (0..MIN_PARALLEL_BODIES + 50)
+      .map(|_| asteroid(vec2(rng.gen_range(-5000., 5000.), rng.gen_range(-5000., 5000.)), rng.gen_range(50., 150.)))
+      .collect();
+    let tree = BarnesHut::new(&belt);
+    let g = SimConfig::default().g;
+    let serial = tree.accelerations(g, 2., 1., 0.5);
+    for threads in [2, 3, 8] {
+      assert_eq!(tree.accelerations_threaded(g, 2., 1., 0.5, threads), serial);
+    }
+  }
+}
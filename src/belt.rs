@@ -0,0 +1,132 @@
+use macroquad::prelude::*;
+use std::time::Instant;
+
+use crate::physics::gravity_acc;
+use crate::CelestialBodyReference;
+
+const PAIRS_PER_BUDGET_CHECK: usize = 64;
+
+// Time-sliced mutual gravity for the asteroid belt. All steps of a frame share one
+// millisecond budget: each processes pairs from `pair_cursor` until the frame's
+// budget runs out, and the rest carries over to the next frame. A pair only gets a kick once per full sweep, so its
+// impulse is scaled by how much sim time the previous sweep took; forces are
+// therefore up to one sweep stale, which is acceptable for the weak
+// asteroid-asteroid pull but would not be for the major bodies.
+pub struct AsteroidPairScheduler {
+  pair_cursor: (usize, usize),
+  sweep_time: f32,
+  last_sweep_time: f32,
+  frame_spent_ms: f64,
+}
+
+impl AsteroidPairScheduler {
+  pub fn new() -> Self {
+    Self { pair_cursor: (0, 1), sweep_time: 0., last_sweep_time: 0., frame_spent_ms: 0. }
+  }
+
+  // hands out a fresh budget, called once per frame before its steps
+  pub fn start_frame(&mut self) {
+    self.frame_spent_ms = 0.;
+  }
+
+  pub fn step(&mut self, asteroids: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32, dt: f32, budget_ms: f64) {
+    let n = asteroids.len();
+    self.sweep_time += dt;
+    let pair_dt = if self.last_sweep_time > 0. { self.last_sweep_time } else { dt };
+    if self.frame_spent_ms > budget_ms {
+      return;
+    }
+    let start = Instant::now();
+    let spent_before = self.frame_spent_ms;
+    let mut processed: usize = 0;
+
+    loop {
+      let (i, j) = self.pair_cursor;
+      if i + 1 >= n || j >= n {
+        self.pair_cursor = (0, 1);
+        self.last_sweep_time = self.sweep_time;
+        self.sweep_time = 0.;
+        self.frame_spent_ms = spent_before + start.elapsed().as_secs_f64() * 1000.;
+        return;
+      }
+
+      {
+        let mut go_a = asteroids[i].borrow_mut();
+        let mut go_b = asteroids[j].borrow_mut();
+        let (acca, accb) = gravity_acc(go_a.mov.pos, go_a.mov.mass, go_b.mov.pos, go_b.mov.mass, g, exponent, softening);
+        go_a.mov.vel += acca * pair_dt;
+        go_b.mov.vel += accb * pair_dt;
+      }
+
+      self.pair_cursor = if j + 1 < n { (i, j + 1) } else { (i + 1, i + 2) };
+      processed += 1;
+      if processed.is_multiple_of(PAIRS_PER_BUDGET_CHECK) {
+        self.frame_spent_ms = spent_before + start.elapsed().as_secs_f64() * 1000.;
+        if self.frame_spent_ms > budget_ms {
+          return;
+        }
+      }
+    }
+  }
+}
+
+// keeps every n-th asteroid ordered by angle around the belt centre, so the thinned belt
+// stays evenly spread instead of losing whole sectors
+pub fn thin_belt(asteroids: &[CelestialBodyReference], max: usize) -> Vec<CelestialBodyReference> {
+  if asteroids.len() <= max {
+    return asteroids.to_vec();
+  }
+  let centre = asteroids.iter().fold(Vec2::ZERO, |acc, a| acc + a.borrow().mov.pos) / asteroids.len() as f32;
+  let angle = |a: &CelestialBodyReference| {
+    let rel = a.borrow().mov.pos - centre;
+    rel.y.atan2(rel.x)
+  };
+  let mut by_angle = asteroids.to_vec();
+  by_angle.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+  (0..max).map(|i| by_angle[i * by_angle.len() / max].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::SimParams;
+  use crate::config::SimConfig;
+  use crate::physics::rotate_vec2_by_rad;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  fn ring(n: usize) -> Vec<CelestialBodyReference> {
+    (0..n).map(|i| {
+      let pos = rotate_vec2_by_rad(&vec2(1000., 0.), i as f32 / n as f32 * std::f32::consts::TAU);
+      wrap_object(CelestialBody::new(pos, 100., 20., CelestialBodyType::Asteroid, GRAY, format!("Ast {}", i), &SimParams::default()))
+    }).collect()
+  }
+
+  #[test]
+  fn every_pair_is_reached_over_enough_frames() {
+    let asteroids = ring(30);
+    let mut scheduler = AsteroidPairScheduler::new();
+    let mut frames = 0;
+    while scheduler.last_sweep_time == 0. && frames < 1000 {
+      scheduler.start_frame();
+      scheduler.step(&asteroids, SimConfig::default().g, 2., 0., 0.01, 0.);
+      frames += 1;
+    }
+    assert!(scheduler.last_sweep_time > 0.);
+    assert!(frames > 1);
+    assert!(asteroids.iter().all(|a| a.borrow().mov.vel != Vec2::ZERO));
+  }
+
+  #[test]
+  fn steps_of_one_frame_share_the_budget() {
+    let asteroids = ring(30);
+    let mut scheduler = AsteroidPairScheduler::new();
+    scheduler.start_frame();
+    scheduler.step(&asteroids, SimConfig::default().g, 2., 0., 0.01, 0.);
+    let cursor = scheduler.pair_cursor;
+    scheduler.step(&asteroids, SimConfig::default().g, 2., 0., 0.01, 0.);
+    assert_eq!(scheduler.pair_cursor, cursor);
+    scheduler.start_frame();
+    scheduler.step(&asteroids, SimConfig::default().g, 2., 0., 0.01, 0.);
+    assert_ne!(scheduler.pair_cursor, cursor);
+  }
+}
@@ -0,0 +1,187 @@
+use macroquad::prelude::*;
+use std::rc::Rc;
+
+use crate::CelestialBodyReference;
+
+const CHASE_SMOOTH_TIME: f32 = 0.3;
+const CHASE_LOOKAHEAD: f32 = 0.5;
+const PAN_DAMPING: f32 = 4.;
+const PAN_VELOCITY_SMOOTHING: f32 = 0.5;
+const PAN_REST_SPEED: f32 = 1e-3;
+
+// what the camera centres on before the pan offset; the ship is whichever one is active
+#[derive(Clone)]
+pub enum FocusTarget {
+  Ship,
+  Body(CelestialBodyReference),
+  Free(Vec2),
+}
+
+impl FocusTarget {
+  pub fn body(&self) -> Option<&CelestialBodyReference> {
+    match self {
+      Self::Body(body) => Some(body),
+      Self::Ship | Self::Free(_) => None,
+    }
+  }
+
+  // the body after the focused one, back to the ship after the last
+  pub fn next_body(&self, bodies: &[CelestialBodyReference]) -> Self {
+    let next = match self.body().and_then(|focused| bodies.iter().position(|b| Rc::ptr_eq(b, focused))) {
+      Some(i) => bodies.get(i + 1),
+      None => bodies.first(),
+    };
+    next.map_or(Self::Ship, |body| Self::Body(body.clone()))
+  }
+}
+
+// critically damped spring following a point slightly ahead of the ship
+pub struct SmoothCamera {
+  pub pos: Vec2,
+  pub vel: Vec2,
+}
+
+impl SmoothCamera {
+  pub fn new(pos: Vec2, vel: Vec2) -> Self {
+    Self { pos, vel }
+  }
+
+  pub fn update(&mut self, ship_pos: Vec2, ship_vel: Vec2, dt: f32) {
+    let target = ship_pos + ship_vel * CHASE_LOOKAHEAD;
+    // closed form approximation of the spring step, stable for any dt
+    let omega = 2. / CHASE_SMOOTH_TIME;
+    let x = omega * dt;
+    let decay = 1. / (1. + x + 0.48 * x * x + 0.235 * x * x * x);
+    let change = self.pos - target;
+    let temp = (self.vel + omega * change) * dt;
+    self.vel = (self.vel - omega * temp) * decay;
+    self.pos = target + (change + temp) * decay;
+  }
+}
+
+// camera offset from its focus target; a released drag keeps drifting and slows down
+pub struct Pan {
+  pub offset: Vec2,
+  pub vel: Vec2,
+  last_mouse: Option<Vec2>,
+}
+
+impl Pan {
+  pub fn new() -> Self {
+    Self { offset: Vec2::ZERO, vel: Vec2::ZERO, last_mouse: None }
+  }
+
+  pub fn reset(&mut self) {
+    *self = Self::new();
+  }
+
+  pub fn update(&mut self, mouse: Vec2, dragging: bool, scale: f32, dt: f32) {
+    if dragging {
+      if let Some(last) = self.last_mouse {
+        let delta = (last - mouse) * scale;
+        self.offset += delta;
+        if dt > 0. {
+          self.vel = self.vel.lerp(delta / dt, PAN_VELOCITY_SMOOTHING);
+        }
+      }
+      self.last_mouse = Some(mouse);
+      return;
+    }
+    self.last_mouse = None;
+    self.offset += self.vel * dt;
+    self.vel *= (-PAN_DAMPING * dt).exp();
+    if self.vel.length() < PAN_REST_SPEED * scale {
+      self.vel = Vec2::ZERO;
+    }
+  }
+
+  // shifts the view so the world point cursor_offset away from the screen centre stays put
+  // while the scale changes
+  pub fn zoom_about(&mut self, cursor_offset: Vec2, old_scale: f32, new_scale: f32) {
+    self.offset += cursor_offset * (old_scale - new_scale);
+  }
+}
+
+// camera settings put aside while the momentary system overview is shown
+pub struct Overview {
+  scale: f32,
+  target_scale: f32,
+  pan_offset: Vec2,
+  pan_vel: Vec2,
+}
+
+impl Overview {
+  pub fn begin(scale: f32, target_scale: f32, pan: &mut Pan) -> Self {
+    let saved = Self { scale, target_scale, pan_offset: pan.offset, pan_vel: pan.vel };
+    pan.reset();
+    saved
+  }
+
+  // puts the pan back and returns the scale and target scale to restore
+  pub fn end(self, pan: &mut Pan) -> (f32, f32) {
+    pan.reset();
+    pan.offset = self.pan_offset;
+    pan.vel = self.pan_vel;
+    (self.scale, self.target_scale)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn chase_camera_settles_on_a_resting_ship_without_overshoot() {
+    let ship = vec2(1000., -500.);
+    for dt in [1. / 144., 1. / 60., 0.1, 0.5] {
+      let mut camera = SmoothCamera::new(Vec2::ZERO, Vec2::ZERO);
+      let mut last = camera.pos.distance(ship);
+      for _ in 0..(5. / dt) as usize {
+        camera.update(ship, Vec2::ZERO, dt);
+        // critically damped: always on the near side, never further than the step before
+        assert!((camera.pos - ship).dot(ship) <= 0.);
+        let distance = camera.pos.distance(ship);
+        assert!(distance <= last);
+        last = distance;
+      }
+      assert!(last < 1., "{} left at dt {}", last, dt);
+    }
+  }
+
+  #[test]
+  fn pan_drift_dies_out_and_a_drag_takes_over() {
+    let dt = 1. / 60.;
+    let mut pan = Pan::new();
+    pan.update(vec2(100., 100.), true, 1., dt);
+    pan.update(vec2(90., 100.), true, 1., dt);
+    assert!(pan.vel.x > 0.);
+    // released, it keeps drifting the same way until it comes to rest
+    let released = pan.offset;
+    let rest = (0..600).position(|_| {
+      pan.update(vec2(90., 100.), false, 1., dt);
+      pan.vel == Vec2::ZERO
+    });
+    assert!(rest.is_some());
+    assert!(pan.offset.x > released.x);
+
+    // a new drag follows the mouse exactly, whatever the drift was
+    pan.vel = vec2(-500., 0.);
+    let start = pan.offset;
+    pan.update(vec2(50., 50.), true, 2., dt);
+    pan.update(vec2(40., 50.), true, 2., dt);
+    assert_eq!(pan.offset, start + vec2(20., 0.));
+  }
+
+  #[test]
+  fn an_overview_puts_the_camera_back_as_it_was() {
+    let mut pan = Pan::new();
+    pan.offset = vec2(120., -40.);
+    pan.vel = vec2(3., 4.);
+    let saved = Overview::begin(2.5, 3., &mut pan);
+    assert_eq!((pan.offset, pan.vel), (Vec2::ZERO, Vec2::ZERO));
+    // the overview pans about on its own meanwhile
+    pan.offset = vec2(999., 999.);
+    assert_eq!(saved.end(&mut pan), (2.5, 3.));
+    assert_eq!((pan.offset, pan.vel), (vec2(120., -40.), vec2(3., 4.)));
+  }
+}
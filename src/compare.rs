@@ -0,0 +1,98 @@
+use macroquad::prelude::*;
+
+use crate::divergence::path_deviation;
+use crate::integrator::{integrate, IntegratorKind};
+use crate::physics::gravity_acc;
+use crate::{CelestialBodyReference, Ship};
+
+const COMPARE_SAMPLE_EVERY: usize = 10;
+const COMPARE_MAX_SAMPLES: usize = 2000;
+
+struct IntegratorGhost {
+  kind: IntegratorKind,
+  pos: Vec2,
+  vel: Vec2,
+  path: Vec<Vec2>,
+}
+
+// two thrustless copies of the ship started from the same state and stepped with different
+// integrators through the live bodies' gravity; the live ship is never touched
+pub struct IntegratorComparison {
+  ghosts: [IntegratorGhost; 2],
+  steps: usize,
+}
+
+impl IntegratorComparison {
+  pub fn new(ship: &Ship, kinds: [IntegratorKind; 2]) -> Self {
+    let ghost = |kind| IntegratorGhost { kind, pos: ship.mov.pos, vel: ship.mov.vel, path: vec![ship.mov.pos] };
+    Self { ghosts: kinds.map(ghost), steps: 0 }
+  }
+
+  pub fn step(&mut self, celestial_bodies: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32, dt: f32) {
+    let bodies: Vec<(Vec2, f32)> = celestial_bodies.iter().map(|cb| (cb.borrow().mov.pos, cb.borrow().mov.mass)).collect();
+    let acc = |pos: Vec2| bodies.iter().fold(Vec2::ZERO, |a, (p, m)| a + gravity_acc(pos, 1., *p, *m, g, exponent, softening).0);
+    self.steps += 1;
+    for ghost in &mut self.ghosts {
+      (ghost.pos, ghost.vel) = integrate(ghost.kind, ghost.pos, ghost.vel, dt, acc);
+      if self.steps.is_multiple_of(COMPARE_SAMPLE_EVERY) && ghost.path.len() < COMPARE_MAX_SAMPLES {
+        ghost.path.push(ghost.pos);
+      }
+    }
+  }
+
+  pub fn kinds(&self) -> (IntegratorKind, IntegratorKind) {
+    (self.ghosts[0].kind, self.ghosts[1].kind)
+  }
+
+  pub fn separation(&self) -> f32 {
+    self.ghosts[0].pos.distance(self.ghosts[1].pos)
+  }
+
+  pub fn max_deviation(&self) -> f32 {
+    path_deviation(&self.ghosts[0].path, &self.ghosts[1].path)
+  }
+
+  pub fn draw(&self, focus: Vec2, scale: f32) {
+    for (ghost, color) in self.ghosts.iter().zip([Color::new(0., 1., 1., 0.6), Color::new(1., 0., 1., 0.6)]) {
+      for w in ghost.path.windows(2) {
+        let (a, b) = ((w[0] - focus) / scale, (w[1] - focus) / scale);
+        draw_line(a.x, a.y, b.x, b.y, 1., color);
+      }
+      let p = (ghost.pos - focus) / scale;
+      draw_circle_lines(p.x, p.y, 4., 1., color);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::{ShipConfig, SimParams};
+  use crate::physics::get_initial_position_and_velocity;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  fn compare(kinds: [IntegratorKind; 2], steps: usize) -> Vec<f32> {
+    let params = SimParams::default();
+    let star = wrap_object(CelestialBody::new(Vec2::ZERO, 30000000., 7000., CelestialBodyType::Star, ORANGE, "Star".to_owned(), &params));
+    let (pos, vel) = get_initial_position_and_velocity(30000000., 20000., 0., &params.config);
+    let ship = Ship::new(pos, vel, &ShipConfig::default(), "Ship".to_owned(), WHITE);
+    let mut c = IntegratorComparison::new(&ship, kinds);
+    (0..steps).map(|_| {
+      c.step(std::slice::from_ref(&star), params.config.g, 2., 0., params.step_dt());
+      c.max_deviation().max(c.separation())
+    }).collect()
+  }
+
+  #[test]
+  fn the_same_integrator_never_diverges() {
+    let divergence = compare([IntegratorKind::VelocityVerlet; 2], 2000);
+    assert!(divergence.iter().all(|d| *d == 0.));
+  }
+
+  #[test]
+  fn different_integrators_drift_apart() {
+    let divergence = compare([IntegratorKind::Euler, IntegratorKind::VelocityVerlet], 2000);
+    assert!(divergence[99] > 0.);
+    assert!(divergence[1999] > 10. * divergence[99]);
+  }
+}
@@ -0,0 +1,29 @@
+const G: f32 = 50.;
+const AU: f32 = 150000.;
+const TERMINAL_VELOCITY: f32 = 30.;
+const HILL_RADIUS_COEFFICIENT: f32 = 3.;
+const MAX_LANDING_ANGLE: f32 = 30.;
+
+// world constants read when a system is generated; changing them takes effect on the next reseed
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimConfig {
+  pub g: f32,
+  pub au: f32,
+  pub max_landing_speed: f32,
+  // a major body's sphere of influence in multiples of its Hill radius
+  pub hill_radius_coefficient: f32,
+  // degrees off the surface normal a touchdown still counts as a landing
+  pub max_landing_angle: f32,
+}
+
+impl Default for SimConfig {
+  fn default() -> Self {
+    Self {
+      g: G,
+      au: AU,
+      max_landing_speed: TERMINAL_VELOCITY,
+      hill_radius_coefficient: HILL_RADIUS_COEFFICIENT,
+      max_landing_angle: MAX_LANDING_ANGLE,
+    }
+  }
+}
@@ -0,0 +1,211 @@
+use macroquad::prelude::*;
+
+use crate::params::{pair_gravity_scale, SimParams};
+use crate::CelestialBodyReference;
+
+pub fn kinetic_energy(celestial_bodies: &[CelestialBodyReference]) -> f64 {
+  celestial_bodies.iter()
+    .map(|cb| {
+      let cb = cb.borrow();
+      0.5 * cb.mov.mass as f64 * cb.mov.vel.length_squared() as f64
+    })
+    .sum()
+}
+
+// pairwise potential of the same force law gravity_acc applies, so the total stays flat for any
+// exponent and softening; an exponent of 1 has a logarithmic potential instead of a power
+pub fn pair_potential(distance: f32, mass_product: f64, params: &SimParams) -> f64 {
+  let softened = distance as f64 * distance as f64 + params.softening as f64 * params.softening as f64;
+  let exponent = params.gravity_exponent as f64;
+  if exponent == 1. {
+    params.config.g as f64 * mass_product * 0.5 * softened.ln()
+  } else {
+    -params.config.g as f64 * mass_product / ((exponent - 1.) * softened.powf((exponent - 1.) / 2.))
+  }
+}
+
+pub fn potential_energy(celestial_bodies: &[CelestialBodyReference], params: &SimParams) -> f64 {
+  let mut energy = 0.;
+  for (i, a) in celestial_bodies.iter().enumerate() {
+    let a = a.borrow();
+    for b in &celestial_bodies[i + 1..] {
+      let b = b.borrow();
+      let scale = pair_gravity_scale(&params.gravity_scales, a.id, b.id) as f64;
+      energy += scale * pair_potential(a.mov.pos.distance(b.mov.pos), a.mov.mass as f64 * b.mov.mass as f64, params);
+    }
+  }
+  energy
+}
+
+pub fn system_momentum(celestial_bodies: &[CelestialBodyReference]) -> Vec2 {
+  celestial_bodies.iter().fold(Vec2::ZERO, |p, cb| {
+    let cb = cb.borrow();
+    p + cb.mov.vel * cb.mov.mass
+  })
+}
+
+// totals over the major bodies, measured once when a system starts and again every frame
+pub struct ConservationTotals {
+  pub kinetic: f64,
+  pub potential: f64,
+  pub momentum: Vec2,
+  // sum of every body's own momentum; the total is near zero for a system at rest around
+  // its barycentre, so drift is measured against this instead
+  pub momentum_scale: f32,
+}
+
+impl ConservationTotals {
+  pub fn measure(celestial_bodies: &[CelestialBodyReference], params: &SimParams) -> Self {
+    Self {
+      kinetic: kinetic_energy(celestial_bodies),
+      potential: potential_energy(celestial_bodies, params),
+      momentum: system_momentum(celestial_bodies),
+      momentum_scale: celestial_bodies.iter().map(|cb| cb.borrow().mov.mass * cb.borrow().mov.vel.length()).sum(),
+    }
+  }
+
+  pub fn energy(&self) -> f64 {
+    self.kinetic + self.potential
+  }
+
+  // percent change in total energy and momentum against a baseline; an empty or
+  // motionless baseline reports no drift rather than dividing by zero
+  pub fn drift_from(&self, baseline: &Self) -> (f64, f32) {
+    let energy_drift = if baseline.energy() == 0. { 0. } else { (self.energy() - baseline.energy()) / baseline.energy().abs() * 100. };
+    let momentum_drift = if baseline.momentum_scale == 0. { 0. } else { (self.momentum - baseline.momentum).length() / baseline.momentum_scale * 100. };
+    (energy_drift, momentum_drift)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::integrator::IntegratorKind;
+  use crate::physics::gravity_acc;
+  use crate::rng::SeededRng;
+  use crate::simulation::{SimEvent, Simulation};
+  use crate::ShipState;
+
+  // runs the system without thrust and checks the major bodies' totals before and after;
+  // tolerances are relative to the starting values
+  fn assert_conserved(sim: &mut Simulation, params: &SimParams, steps: usize, energy_tol: f64, momentum_tol: f32) {
+    let bodies = sim.major_celestial_bodies.clone();
+    let before = ConservationTotals::measure(&bodies, params);
+    for _ in 0..steps {
+      sim.step(params, params.step_dt());
+    }
+    let (energy_drift, momentum_drift) = ConservationTotals::measure(&bodies, params).drift_from(&before);
+    let (energy_drift, momentum_drift) = (energy_drift.abs() / 100., momentum_drift / 100.);
+    assert!(energy_drift <= energy_tol, "energy drift {:.2e} over {} steps", energy_drift, steps);
+    assert!(momentum_drift <= momentum_tol, "momentum drift {:.2e} over {} steps", momentum_drift, steps);
+  }
+
+  #[test]
+  fn verlet_conserves_the_generated_system() {
+    let params = SimParams::default();
+    assert_conserved(&mut Simulation::new(1, &params), &params, 2000, 1e-3, 1e-3);
+  }
+
+  #[test]
+  fn potential_slope_matches_the_applied_force() {
+    for (exponent, softening) in [(1., 0.), (2., 0.), (3., 0.), (2., 20.)] {
+      let params = SimParams { gravity_exponent: exponent, softening, ..SimParams::default() };
+      let (r, h) = (300., 0.01);
+      let (_, acc) = gravity_acc(Vec2::ZERO, 1000., vec2(r, 0.), 1., params.config.g, exponent, softening);
+      let slope = (pair_potential(r + h, 1000., &params) - pair_potential(r - h, 1000., &params)) / (2. * h as f64);
+      assert!((slope + acc.x as f64).abs() < 1e-3 * slope.abs(), "exponent {} softening {}: slope {} force {}", exponent, softening, slope, acc.x);
+    }
+  }
+
+  // drives the active ship with random turn and throttle inputs next to its spawn body and
+  // checks the collision state machine after every step; a seed always replays the same inputs
+  fn fuzz_ship(seed: u64, params: &SimParams, steps: usize) -> Result<String, String> {
+    let mut sim = Simulation::new(seed, params);
+    let mut rng = SeededRng::new(seed);
+    let dt = params.step_dt();
+    let (mut landings, mut crashes) = (0, 0);
+    for step in 0..steps {
+      let was_destroyed = matches!(sim.ship.borrow().state, ShipState::Destroyed);
+      {
+        let mut ship = sim.ship.borrow_mut();
+        match rng.gen_range(0., 4.) as usize {
+          0 => ship.turn_left(params, dt),
+          1 => ship.turn_right(params, dt),
+          _ => {},
+        }
+        ship.throttle(rng.gen_range(0., 1.), params, dt);
+      }
+      for event in sim.step(params, dt) {
+        match event {
+          SimEvent::Landed { .. } => landings += 1,
+          SimEvent::Destroyed { .. } => crashes += 1,
+          _ => {},
+        }
+      }
+
+      let ship = sim.ship.borrow();
+      let fail = |what: &str| Err(format!("seed {} step {}: {}", seed, step, what));
+      if !(ship.mov.pos.is_finite() && ship.mov.vel.is_finite() && ship.mov.rot.is_finite()) {
+        return fail("non-finite ship state");
+      }
+      if ship.fuel < 0. {
+        return fail("negative fuel");
+      }
+      match &ship.state {
+        ShipState::Destroyed => {},
+        _ if was_destroyed => return fail("destroyed ship came back without a respawn"),
+        ShipState::Landed(cb, _, _) => {
+          let cb = cb.borrow();
+          if ship.mov.pos.distance(cb.mov.pos) > cb.radius + ship.landing_standoff + 1e-3 * cb.radius {
+            return fail("landed ship is off the surface");
+          }
+        },
+        ShipState::InSpace => {},
+      }
+    }
+    Ok(format!("seed {}: {} steps, {} landings, {} crashes", seed, steps, landings, crashes))
+  }
+
+  #[test]
+  fn random_piloting_keeps_the_ship_state_consistent() {
+    let params = SimParams::default();
+    for seed in 0..4 {
+      if let Err(report) = fuzz_ship(seed, &params, 3000) {
+        panic!("{}", report);
+      }
+    }
+  }
+
+  // steps a fresh copy of the system forward, reverses every velocity and steps back the same
+  // number of times; returns how far the major bodies end up from where they started
+  fn reversibility_error(seed: u64, params: &SimParams, steps: usize) -> f32 {
+    let mut sim = Simulation::new(seed, params);
+    let start: Vec<Vec2> = sim.major_celestial_bodies.iter().map(|cb| cb.borrow().mov.pos).collect();
+    for _ in 0..steps {
+      sim.step(params, params.step_dt());
+    }
+    for cb in &sim.all_celestial_bodies {
+      let mut cb = cb.borrow_mut();
+      cb.mov.vel = -cb.mov.vel;
+    }
+    for s in &sim.ships {
+      let mut s = s.borrow_mut();
+      s.mov.vel = -s.mov.vel;
+    }
+    for _ in 0..steps {
+      sim.step(params, params.step_dt());
+    }
+    sim.major_celestial_bodies.iter()
+      .zip(start)
+      .map(|(cb, pos)| cb.borrow().mov.pos.distance(pos))
+      .fold(0., f32::max)
+  }
+
+  #[test]
+  fn verlet_retraces_its_steps_closer_than_euler() {
+    let verlet = SimParams { integrator: IntegratorKind::VelocityVerlet, ..SimParams::default() };
+    let euler = SimParams { integrator: IntegratorKind::SemiImplicitEuler, ..SimParams::default() };
+    let (verlet_error, euler_error) = (reversibility_error(1, &verlet, 1000), reversibility_error(1, &euler, 1000));
+    assert!(verlet_error < euler_error, "verlet {} euler {}", verlet_error, euler_error);
+  }
+}
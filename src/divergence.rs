@@ -0,0 +1,65 @@
+use macroquad::prelude::*;
+
+pub fn path_deviation(predicted: &[Vec2], actual: &[Vec2]) -> f32 {
+  predicted.iter()
+    .zip(actual)
+    .map(|(p, a)| p.distance(*a))
+    .fold(0., f32::max)
+}
+
+// records the realized ship path at the same cadence the prediction was sampled
+pub struct PredictionCheck {
+  pub predicted: Vec<Vec2>,
+  pub actual: Vec<Vec2>,
+  steps_per_sample: usize,
+  steps: usize,
+}
+
+impl PredictionCheck {
+  pub fn new(predicted: Vec<Vec2>, steps_per_sample: usize) -> Self {
+    let actual = predicted.first().copied().into_iter().collect();
+    Self { predicted, actual, steps_per_sample, steps: 0 }
+  }
+
+  pub fn is_done(&self) -> bool {
+    self.actual.len() >= self.predicted.len()
+  }
+
+  pub fn record(&mut self, pos: Vec2) {
+    if self.is_done() {
+      return;
+    }
+    self.steps += 1;
+    if self.steps.is_multiple_of(self.steps_per_sample) {
+      self.actual.push(pos);
+    }
+  }
+
+  pub fn max_deviation(&self) -> f32 {
+    path_deviation(&self.predicted, &self.actual)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn deviation_of_identical_and_offset_paths() {
+    let path: Vec<Vec2> = (0..10).map(|i| vec2(i as f32, (i * i) as f32)).collect();
+    assert_eq!(path_deviation(&path, &path), 0.);
+    let offset: Vec<Vec2> = path.iter().enumerate().map(|(i, p)| *p + vec2(0., i as f32 / 2.)).collect();
+    assert_eq!(path_deviation(&path, &offset), 4.5);
+  }
+
+  #[test]
+  fn check_samples_at_the_prediction_cadence() {
+    let mut check = PredictionCheck::new(vec![Vec2::ZERO, vec2(1., 0.), vec2(2., 0.)], 2);
+    for i in 1..=4 {
+      check.record(vec2(i as f32 / 2., 1.));
+    }
+    assert!(check.is_done());
+    assert_eq!(check.actual, vec![Vec2::ZERO, vec2(1., 1.), vec2(2., 1.)]);
+    assert_eq!(check.max_deviation(), 1.);
+  }
+}
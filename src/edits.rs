@@ -0,0 +1,99 @@
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::simulation::{add_body, remove_body, Simulation};
+use crate::CelestialBodyReference;
+
+const UNDO_LIMIT: usize = 32;
+
+// a removed body is kept as the same reference, so undoing its removal brings back the body
+// older edits point at
+pub enum Edit {
+  Added(CelestialBodyReference),
+  Removed(CelestialBodyReference),
+  VelocityChanged(CelestialBodyReference, Vec2),
+}
+
+pub struct EditHistory {
+  edits: VecDeque<Edit>,
+}
+
+impl EditHistory {
+  pub fn new() -> Self {
+    Self { edits: VecDeque::new() }
+  }
+
+  pub fn push(&mut self, edit: Edit) {
+    if self.edits.len() == UNDO_LIMIT {
+      self.edits.pop_front();
+    }
+    self.edits.push_back(edit);
+  }
+
+  // applies the inverse of the last edit, returns the name of the affected body
+  pub fn undo(&mut self, sim: &mut Simulation) -> Option<String> {
+    let name = match self.edits.pop_back()? {
+      Edit::Added(body) => {
+        remove_body(sim, &body);
+        body.borrow().name.clone()
+      },
+      Edit::Removed(body) => {
+        add_body(sim, body.clone());
+        body.borrow().name.clone()
+      },
+      Edit::VelocityChanged(body, old_vel) => {
+        if sim.all_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, &body)) {
+          body.borrow_mut().mov.vel = old_vel;
+        }
+        body.borrow().name.clone()
+      },
+    };
+    Some(name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::SimParams;
+  use crate::simulation::stress_scenario;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  fn contains(sim: &Simulation, body: &CelestialBodyReference) -> bool {
+    sim.all_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, body))
+  }
+
+  #[test]
+  fn undoing_a_placement_removes_the_body() {
+    let params = SimParams::default();
+    let mut sim = stress_scenario(3, &params);
+    let body = wrap_object(CelestialBody::from_parent(&sim.cb_parent.borrow(), 20000., 45., 100., 20., CelestialBodyType::Asteroid, GRAY, "Placed".to_owned(), &params));
+    add_body(&mut sim, body.clone());
+    let mut edits = EditHistory::new();
+    edits.push(Edit::Added(body.clone()));
+    assert_eq!(edits.undo(&mut sim), Some("Placed".to_owned()));
+    assert!(!contains(&sim, &body));
+    assert_eq!(sim.all_celestial_bodies.len(), 3);
+    assert_eq!(edits.undo(&mut sim), None);
+  }
+
+  #[test]
+  fn undoing_a_deletion_restores_the_body_older_edits_point_at() {
+    let params = SimParams::default();
+    let mut sim = stress_scenario(3, &params);
+    let body = sim.minor_celestial_bodies[0].clone();
+    let old_vel = body.borrow().mov.vel;
+    let mut edits = EditHistory::new();
+    edits.push(Edit::VelocityChanged(body.clone(), old_vel));
+    body.borrow_mut().mov.vel = old_vel * 2.;
+    edits.push(Edit::Removed(body.clone()));
+    remove_body(&mut sim, &body);
+    assert!(!contains(&sim, &body));
+    edits.undo(&mut sim);
+    assert!(contains(&sim, &body));
+    assert!(sim.minor_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, &body)));
+    edits.undo(&mut sim);
+    assert_eq!(body.borrow().mov.vel, old_vel);
+  }
+}
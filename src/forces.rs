@@ -0,0 +1,94 @@
+use macroquad::prelude::*;
+
+use crate::CelestialBodyReference;
+
+const SOLAR_PRESSURE: f32 = 10.;
+const DRAG_COEFICIENT: f32 = 0.01;
+
+pub trait ForceField {
+  fn acceleration(&self, pos: Vec2, vel: Vec2, mass: f32) -> Vec2;
+}
+
+// pushes outward from the source, falling off with inverse square of distance;
+// strength is the acceleration felt by a unit mass at the source surface
+pub struct RadialPressure {
+  source: CelestialBodyReference,
+  strength: f32,
+}
+
+impl RadialPressure {
+  pub fn new(source: CelestialBodyReference, strength: f32) -> Self {
+    Self { source, strength }
+  }
+}
+
+impl ForceField for RadialPressure {
+  fn acceleration(&self, pos: Vec2, _vel: Vec2, mass: f32) -> Vec2 {
+    let source = self.source.borrow();
+    let distance_vector = pos - source.mov.pos;
+    let distance_length = distance_vector.length_squared();
+    if distance_length == 0. {
+      return Vec2::ZERO;
+    }
+    distance_vector.normalize() * self.strength * source.radius.powi(2) / distance_length / mass
+  }
+}
+
+pub struct UniformDrag {
+  coeficient: f32,
+}
+
+impl UniformDrag {
+  pub fn new(coeficient: f32) -> Self {
+    Self { coeficient }
+  }
+}
+
+impl ForceField for UniformDrag {
+  fn acceleration(&self, _pos: Vec2, vel: Vec2, _mass: f32) -> Vec2 {
+    -vel * self.coeficient
+  }
+}
+
+pub fn default_force_fields(star: &CelestialBodyReference) -> Vec<Box<dyn ForceField>> {
+  vec![
+    Box::new(RadialPressure::new(star.clone(), SOLAR_PRESSURE)),
+    Box::new(UniformDrag::new(DRAG_COEFICIENT)),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::SimParams;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  fn star(pos: Vec2, radius: f32) -> CelestialBodyReference {
+    wrap_object(CelestialBody::new(pos, 1000., radius, CelestialBodyType::Star, ORANGE, "Star".to_owned(), &SimParams::default()))
+  }
+
+  #[test]
+  fn radial_pressure_pushes_outward_with_the_inverse_square() {
+    let pressure = RadialPressure::new(star(vec2(100., 0.), 10.), 5.);
+    // at the surface a unit mass feels the full strength
+    assert!(pressure.acceleration(vec2(110., 0.), Vec2::ZERO, 1.).distance(vec2(5., 0.)) < 1e-5);
+    // twice as far a quarter, and a heavier mass less
+    assert!(pressure.acceleration(vec2(100., -20.), vec2(3., 3.), 1.).distance(vec2(0., -1.25)) < 1e-5);
+    assert!(pressure.acceleration(vec2(100., -20.), Vec2::ZERO, 4.).distance(vec2(0., -0.3125)) < 1e-5);
+    assert_eq!(pressure.acceleration(vec2(100., 0.), Vec2::ZERO, 1.), Vec2::ZERO);
+  }
+
+  #[test]
+  fn uniform_drag_opposes_the_velocity() {
+    let drag = UniformDrag::new(0.5);
+    assert_eq!(drag.acceleration(vec2(1000., 1000.), vec2(4., -2.), 7.), vec2(-2., 1.));
+    assert_eq!(drag.acceleration(Vec2::ZERO, Vec2::ZERO, 1.), Vec2::ZERO);
+  }
+
+  #[test]
+  fn default_fields_sum_pressure_and_drag() {
+    let fields = default_force_fields(&star(Vec2::ZERO, 10.));
+    let total = fields.iter().fold(Vec2::ZERO, |acc, f| acc + f.acceleration(vec2(0., 10.), vec2(100., 0.), 1.));
+    assert!(total.distance(vec2(-100. * DRAG_COEFICIENT, SOLAR_PRESSURE)) < 1e-5);
+  }
+}
@@ -17,15 +17,50 @@ const PLANET_NAMES: [&str; 63] = [
   "Autonoe",  "Megaclite",  "S/2003"
 ];
 
+// hands out each distinct name from PLANET_NAMES once, in table order, until reset
 pub struct NamesGen {
   available_names: Vec<String>
 }
 
 impl NamesGen {
   pub fn new() -> Self {
-    Self {
+    let mut names = Self {
       available_names: vec![]
+    };
+    names.reset();
+    names
+  }
+
+  pub fn take(&mut self) -> Option<String> {
+    self.available_names.pop()
+  }
+
+  // kept reversed so take pops from the end
+  pub fn reset(&mut self) {
+    self.available_names.clear();
+    for name in PLANET_NAMES {
+      if !self.available_names.iter().any(|n| n == name) {
+        self.available_names.push(name.to_owned());
+      }
     }
+    self.available_names.reverse();
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashSet;
+
+  #[test]
+  fn take_never_repeats_a_name_until_reset() {
+    let mut names = NamesGen::new();
+    let mut seen = HashSet::new();
+    while let Some(name) = names.take() {
+      assert!(seen.insert(name.clone()), "{} handed out twice", name);
+    }
+    assert_eq!(seen.len(), PLANET_NAMES.iter().collect::<HashSet<_>>().len());
+    names.reset();
+    assert_eq!(names.take().as_deref(), Some(PLANET_NAMES[0]));
+  }
+}
@@ -0,0 +1,118 @@
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::orbit::osculating_elements;
+use crate::physics::rotate_vec2_by_rad;
+use crate::{CelestialBodyReference, Ship};
+
+const GHOST_COUNT: usize = 8;
+const GHOST_SEGMENTS: usize = 90;
+
+struct OrbitSnapshot {
+  eccentricity: f32,
+  semi_major_axis: f32,
+  argument_of_periapsis: f32,
+}
+
+// one snapshot of the osculating orbit per orbital period, so drift shows up as a fan
+pub struct OrbitGhosts {
+  body: Option<CelestialBodyReference>,
+  snapshots: VecDeque<OrbitSnapshot>,
+  since_last: f32,
+}
+
+impl OrbitGhosts {
+  pub fn new() -> Self {
+    Self { body: None, snapshots: VecDeque::new(), since_last: 0. }
+  }
+
+  pub fn update(&mut self, ship: &Ship, g: f32, sim_dt: f32) {
+    let Some(body) = ship.dominant_body() else {
+      self.body = None;
+      self.snapshots.clear();
+      return;
+    };
+    if !self.body.as_ref().is_some_and(|b| Rc::ptr_eq(b, &body)) {
+      self.body = Some(body.clone());
+      self.snapshots.clear();
+      self.since_last = f32::INFINITY;
+    }
+
+    let b = body.borrow();
+    let elements = osculating_elements(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, g * b.mov.mass);
+    let Some(period) = elements.period else {
+      self.snapshots.clear();
+      return;
+    };
+    self.since_last += sim_dt;
+    if self.since_last < period {
+      return;
+    }
+    self.since_last = 0.;
+    if self.snapshots.len() == GHOST_COUNT {
+      self.snapshots.pop_front();
+    }
+    self.snapshots.push_back(OrbitSnapshot {
+      eccentricity: elements.eccentricity,
+      semi_major_axis: elements.semi_major_axis,
+      argument_of_periapsis: elements.argument_of_periapsis,
+    });
+  }
+
+  pub fn draw(&self, focus: Vec2, scale: f32, color: Color) {
+    let Some(body) = &self.body else { return };
+    let centre = body.borrow().mov.pos;
+    for (i, s) in self.snapshots.iter().enumerate() {
+      let alpha = 0.6 * (i + 1) as f32 / self.snapshots.len() as f32;
+      let p = s.semi_major_axis * (1. - s.eccentricity * s.eccentricity);
+      let points: Vec<Vec2> = (0..=GHOST_SEGMENTS)
+        .map(|k| {
+          let nu = k as f32 / GHOST_SEGMENTS as f32 * 360_f32.to_radians();
+          let r = p / (1. + s.eccentricity * nu.cos());
+          (centre + rotate_vec2_by_rad(&vec2(r, 0.), nu + s.argument_of_periapsis) - focus) / scale
+        })
+        .collect();
+      for w in points.windows(2) {
+        draw_line(w[0].x, w[0].y, w[1].x, w[1].y, 1., Color::new(color.r, color.g, color.b, alpha));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::SimConfig;
+  use crate::integrator::IntegratorKind;
+  use crate::params::{ShipConfig, SimParams};
+  use crate::physics::orbital_state;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType, GameObject};
+
+  // periapsis directions of the snapshots taken over three orbits; any law but the inverse
+  // square makes the orbit precess
+  fn drift(exponent: f32) -> Vec<f32> {
+    let g = SimConfig::default().g;
+    let star = wrap_object(CelestialBody::new(Vec2::ZERO, 1000000., 100., CelestialBodyType::Star, ORANGE, "Star".to_owned(), &SimParams::default()));
+    let (pos, vel) = orbital_state(1000000., 1000., 0.3, 0., g);
+    let mut ship = Ship::new(pos, vel, &ShipConfig::default(), "Ship".to_owned(), WHITE);
+    let mut ghosts = OrbitGhosts::new();
+    let bodies = [star];
+    let dt = 0.01;
+    // same pull at the periapsis as the inverse square law, only the fall-off differs
+    let g_law = g * 1000_f32.powf(exponent - 2.);
+    while ghosts.snapshots.len() < 4 {
+      ship.apply_gravity(&bodies, g_law, exponent, 0.);
+      ship.update(IntegratorKind::SemiImplicitEuler, dt);
+      ghosts.update(&ship, g, dt);
+    }
+    ghosts.snapshots.iter().map(|s| s.argument_of_periapsis).collect()
+  }
+
+  #[test]
+  fn a_precessing_orbit_leaves_rotated_snapshots() {
+    let turns = |angles: Vec<f32>| angles.windows(2).map(|w| crate::normalize_angle(w[1] - w[0]).abs()).collect::<Vec<f32>>();
+    assert!(turns(drift(2.2)).iter().all(|turn| *turn > 5_f32.to_radians()));
+    assert!(turns(drift(2.)).iter().all(|turn| *turn < 0.5_f32.to_radians()));
+  }
+}
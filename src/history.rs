@@ -0,0 +1,102 @@
+const SEED_HISTORY_SIZE: usize = 16;
+
+pub struct SeedHistory {
+  seeds: Vec<u64>,
+  cursor: usize,
+}
+
+impl SeedHistory {
+  pub fn new(seed: u64) -> Self {
+    Self { seeds: vec![seed], cursor: 0 }
+  }
+
+  pub fn current(&self) -> u64 {
+    self.seeds[self.cursor]
+  }
+
+  // one past every seed seen, including any forward history push is about to drop
+  pub fn advance(&mut self) -> u64 {
+    let seed = self.seeds.iter().max().map_or(0, |s| s + 1);
+    self.push(seed)
+  }
+
+  // like a browser, a new seed after going back replaces the forward history
+  pub fn push(&mut self, seed: u64) -> u64 {
+    self.seeds.truncate(self.cursor + 1);
+    self.seeds.push(seed);
+    if self.seeds.len() > SEED_HISTORY_SIZE {
+      self.seeds.remove(0);
+    }
+    self.cursor = self.seeds.len() - 1;
+    seed
+  }
+
+  pub fn back(&mut self) -> Option<u64> {
+    if self.cursor == 0 {
+      return None;
+    }
+    self.cursor -= 1;
+    Some(self.current())
+  }
+
+  pub fn forward(&mut self) -> Option<u64> {
+    if self.cursor + 1 >= self.seeds.len() {
+      return None;
+    }
+    self.cursor += 1;
+    Some(self.current())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn back_and_forward_walk_the_history() {
+    let mut history = SeedHistory::new(5);
+    assert_eq!(history.back(), None);
+    assert_eq!(history.advance(), 6);
+    assert_eq!(history.advance(), 7);
+    assert_eq!(history.back(), Some(6));
+    assert_eq!(history.back(), Some(5));
+    assert_eq!(history.back(), None);
+    assert_eq!(history.forward(), Some(6));
+    assert_eq!(history.forward(), Some(7));
+    assert_eq!(history.forward(), None);
+    assert_eq!(history.current(), 7);
+  }
+
+  #[test]
+  fn a_new_seed_after_going_back_drops_the_forward_history() {
+    let mut history = SeedHistory::new(1);
+    history.push(2);
+    history.push(3);
+    history.back();
+    history.back();
+    assert_eq!(history.push(10), 10);
+    assert_eq!(history.forward(), None);
+    assert_eq!(history.back(), Some(1));
+    assert_eq!(history.back(), None);
+    // a generated seed is past the forward history it drops
+    history.forward();
+    history.push(11);
+    history.back();
+    assert_eq!(history.advance(), 12);
+    assert_eq!(history.back(), Some(10));
+  }
+
+  #[test]
+  fn only_the_latest_seeds_are_kept() {
+    let mut history = SeedHistory::new(0);
+    for _ in 0..SEED_HISTORY_SIZE * 2 {
+      history.advance();
+    }
+    let mut steps_back = 0;
+    while history.back().is_some() {
+      steps_back += 1;
+    }
+    assert_eq!(steps_back, SEED_HISTORY_SIZE - 1);
+    assert_eq!(history.current(), SEED_HISTORY_SIZE as u64 + 1);
+  }
+}
@@ -0,0 +1,43 @@
+use macroquad::prelude::*;
+
+const HUD_MARGIN: f32 = 5.;
+const RIGHT_COLUMN_WIDTH: f32 = 256.;
+
+// corners of the screen in the centred screen camera, recomputed each frame so the HUD
+// follows the window when it is resized
+pub struct HudAnchors {
+  pub left: f32,
+  pub right: f32,
+  pub right_column: f32,
+  pub top: f32,
+  pub bottom: f32,
+}
+
+impl HudAnchors {
+  pub fn new(width: f32, height: f32) -> Self {
+    Self {
+      left: -width / 2. + HUD_MARGIN,
+      right: width / 2.,
+      right_column: width / 2. - RIGHT_COLUMN_WIDTH,
+      top: -height / 2.,
+      bottom: height / 2.,
+    }
+  }
+}
+
+pub fn screen_camera(width: f32, height: f32) -> Camera2D {
+  Camera2D::from_display_rect(Rect::new(-width / 2., -height / 2., width, height))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn anchors_follow_the_window_size() {
+    let small = HudAnchors::new(800., 600.);
+    assert_eq!((small.left, small.right, small.right_column, small.top, small.bottom), (-395., 400., 144., -300., 300.));
+    let wide = HudAnchors::new(1920., 1080.);
+    assert_eq!((wide.left, wide.right, wide.right_column, wide.top, wide.bottom), (-955., 960., 704., -540., 540.));
+  }
+}
@@ -0,0 +1,120 @@
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntegratorKind {
+  Euler,
+  SemiImplicitEuler,
+  VelocityVerlet,
+  Rk4,
+}
+
+impl IntegratorKind {
+  pub fn next(&self) -> Self {
+    match self {
+      Self::Euler => Self::SemiImplicitEuler,
+      Self::SemiImplicitEuler => Self::VelocityVerlet,
+      Self::VelocityVerlet => Self::Rk4,
+      Self::Rk4 => Self::Euler,
+    }
+  }
+
+  // how many times a step gathers the accelerations: advance takes the first, complete the rest
+  pub fn stages(&self) -> usize {
+    match self {
+      Self::Euler | Self::SemiImplicitEuler => 1,
+      Self::VelocityVerlet => 2,
+      Self::Rk4 => 4,
+    }
+  }
+}
+
+// what a multi-stage integrator carries between the stages of one step
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StageState {
+  start: (Vec2, Vec2),
+  sum: (Vec2, Vec2),
+  stage: usize,
+}
+
+// moves a point mass by dt given the acceleration at its current position; velocity Verlet
+// still owes the half kick from the acceleration at the new position and RK4 its last three
+// stages, see complete
+pub fn advance(kind: IntegratorKind, state: &mut StageState, pos: Vec2, vel: Vec2, acc: Vec2, dt: f32) -> (Vec2, Vec2) {
+  match kind {
+    IntegratorKind::Euler => (pos + vel * dt, vel + acc * dt),
+    IntegratorKind::SemiImplicitEuler => {
+      let vel = vel + acc * dt;
+      (pos + vel * dt, vel)
+    },
+    IntegratorKind::VelocityVerlet => (pos + vel * dt + acc * dt * dt / 2., vel + acc * dt / 2.),
+    IntegratorKind::Rk4 => {
+      *state = StageState { start: (pos, vel), sum: (vel, acc), stage: 1 };
+      (pos + vel * dt / 2., vel + acc * dt / 2.)
+    },
+  }
+}
+
+// acc is the acceleration at the position the previous stage moved to; RK4 returns the next
+// stage's trial state until its last stage, which lands on the weighted mean of all four
+pub fn complete(kind: IntegratorKind, state: &mut StageState, pos: Vec2, vel: Vec2, acc: Vec2, dt: f32) -> (Vec2, Vec2) {
+  match kind {
+    IntegratorKind::VelocityVerlet => (pos, vel + acc * dt / 2.),
+    IntegratorKind::Rk4 => {
+      let (pos0, vel0) = state.start;
+      let weight = if state.stage == 3 { 1. } else { 2. };
+      state.sum = (state.sum.0 + vel * weight, state.sum.1 + acc * weight);
+      state.stage += 1;
+      match state.stage {
+        2 => (pos0 + vel * dt / 2., vel0 + acc * dt / 2.),
+        3 => (pos0 + vel * dt, vel0 + acc * dt),
+        _ => (pos0 + state.sum.0 * dt / 6., vel0 + state.sum.1 * dt / 6.),
+      }
+    },
+    IntegratorKind::Euler | IntegratorKind::SemiImplicitEuler => (pos, vel),
+  }
+}
+
+// advances a point mass by dt in the acceleration field `acc`
+pub fn integrate(kind: IntegratorKind, pos: Vec2, vel: Vec2, dt: f32, acc: impl Fn(Vec2) -> Vec2) -> (Vec2, Vec2) {
+  let mut state = StageState::default();
+  let (mut pos, mut vel) = advance(kind, &mut state, pos, vel, acc(pos), dt);
+  for _ in 1..kind.stages() {
+    (pos, vel) = complete(kind, &mut state, pos, vel, acc(pos), dt);
+  }
+  (pos, vel)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::SimConfig;
+  use crate::physics::{gravity_acc, orbital_state};
+
+  // a test mass on a circular orbit around a fixed central mass, integrated for one period
+  fn orbit_closure_error(kind: IntegratorKind) -> f32 {
+    let (mass, radius, dt, g) = (1000., 100., 0.01, SimConfig::default().g);
+    let (start, vel) = orbital_state(mass, radius, 0., 0., g);
+    let period = std::f32::consts::TAU * radius / vel.length();
+    let acc = |p: Vec2| gravity_acc(p, 1., Vec2::ZERO, mass, g, 2., 0.).0;
+    let (mut pos, mut vel) = (start, vel);
+    for _ in 0..(period / dt).round() as usize {
+      (pos, vel) = integrate(kind, pos, vel, dt, acc);
+    }
+    pos.distance(start) / radius
+  }
+
+  #[test]
+  fn verlet_closes_a_circular_orbit() {
+    assert!(orbit_closure_error(IntegratorKind::VelocityVerlet) < 1e-3);
+  }
+
+  #[test]
+  fn verlet_closes_tighter_than_euler() {
+    assert!(orbit_closure_error(IntegratorKind::VelocityVerlet) < orbit_closure_error(IntegratorKind::Euler) / 10.);
+  }
+
+  #[test]
+  fn rk4_closes_tighter_than_verlet() {
+    assert!(orbit_closure_error(IntegratorKind::Rk4) < orbit_closure_error(IntegratorKind::VelocityVerlet));
+  }
+}
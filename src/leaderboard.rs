@@ -0,0 +1,93 @@
+use std::fs;
+use std::io;
+
+use crate::library::parse_objects;
+
+// 100 for an upright touchdown at rest, falling linearly to 0 at the crash limits;
+// speed counts for more than attitude
+pub fn landing_score(speed: f32, max_speed: f32, angle_error: f32, max_angle: f32) -> u32 {
+  let softness = (1. - speed / max_speed).clamp(0., 1.);
+  let alignment = (1. - angle_error / max_angle).clamp(0., 1.);
+  (100. * (0.7 * softness + 0.3 * alignment)).round() as u32
+}
+
+// best landing score per seed, kept on disk
+pub struct Leaderboard {
+  path: String,
+  best: Vec<(u64, u32)>,
+}
+
+impl Leaderboard {
+  // a missing or unreadable file starts an empty board
+  pub fn open(path: &str) -> Self {
+    let best = fs::read_to_string(path).ok()
+      .and_then(|json| parse_objects(&json))
+      .map(|objects| objects.iter().filter_map(|fields| {
+        let field = |key: &str| fields.iter().find(|(k, _)| k == key).and_then(|(_, v)| v.parse::<u64>().ok());
+        Some((field("seed")?, field("score")? as u32))
+      }).collect())
+      .unwrap_or_default();
+    Self { path: path.to_owned(), best }
+  }
+
+  pub fn best(&self, seed: u64) -> Option<u32> {
+    self.best.iter().find(|(s, _)| *s == seed).map(|(_, score)| *score)
+  }
+
+  // true when the score beats the seed's record and was saved
+  pub fn record(&mut self, seed: u64, score: u32) -> io::Result<bool> {
+    if self.best(seed).is_some_and(|best| best >= score) {
+      return Ok(false);
+    }
+    self.best.retain(|(s, _)| *s != seed);
+    self.best.push((seed, score));
+    self.save()?;
+    Ok(true)
+  }
+
+  fn save(&self) -> io::Result<()> {
+    let objects: Vec<String> = self.best.iter().map(|(seed, score)| format!("  {{\"seed\": {}, \"score\": {}}}", seed, score)).collect();
+    fs::write(&self.path, format!("[\n{}\n]\n", objects.join(",\n")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scratch_board(name: &str) -> (String, Leaderboard) {
+    let path = std::env::temp_dir().join(format!("solsys_{}_{}.json", name, std::process::id()));
+    let path = path.to_str().unwrap().to_owned();
+    let _ = fs::remove_file(&path);
+    let board = Leaderboard::open(&path);
+    (path, board)
+  }
+
+  #[test]
+  fn only_a_better_landing_replaces_the_record() {
+    let (path, mut board) = scratch_board("leaderboard_ranking");
+    assert_eq!(board.best(1), None);
+    assert!(board.record(1, 60).unwrap());
+    assert!(board.record(1, 85).unwrap());
+    assert!(!board.record(1, 40).unwrap());
+    // a tie keeps the record that was there first
+    assert!(!board.record(1, 85).unwrap());
+    assert!(board.record(2, 10).unwrap());
+    assert_eq!((board.best(1), board.best(2)), (Some(85), Some(10)));
+
+    let reopened = Leaderboard::open(&path);
+    assert_eq!((reopened.best(1), reopened.best(2), reopened.best(3)), (Some(85), Some(10), None));
+    assert_eq!(reopened.best.len(), 2);
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn landing_score_rounds_and_stays_within_0_and_100() {
+    assert_eq!(landing_score(0., 30., 0., 0.5), 100);
+    assert_eq!(landing_score(15., 30., 0.25, 0.5), 50);
+    // past the crash limits both parts bottom out at 0 instead of going negative
+    assert_eq!(landing_score(90., 30., 2., 0.5), 0);
+    assert_eq!(landing_score(90., 30., 0., 0.5), 30);
+    assert_eq!(landing_score(1., 30., 0., 0.5), 98);
+  }
+}
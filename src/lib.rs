@@ -0,0 +1,6 @@
+// the headless physics core, no rendering or input
+pub mod config;
+pub mod integrator;
+pub mod movable;
+pub mod physics;
+pub mod timer;
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+use crate::params::SimParams;
+
+// everything needed to regenerate a system
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SystemConfig {
+  pub seed: u64,
+  pub max_asteroids: usize,
+  pub gravity_exponent: f32,
+  pub day_length: f32,
+}
+
+impl SystemConfig {
+  pub fn new(seed: u64, params: &SimParams) -> Self {
+    Self {
+      seed,
+      max_asteroids: params.max_asteroids,
+      gravity_exponent: params.gravity_exponent,
+      day_length: params.day_length,
+    }
+  }
+
+  pub fn apply(&self, params: &SimParams) -> SimParams {
+    SimParams {
+      max_asteroids: self.max_asteroids,
+      gravity_exponent: self.gravity_exponent,
+      day_length: self.day_length,
+      ..params.clone()
+    }
+  }
+
+  pub fn encode(&self, name: &str) -> String {
+    format!(
+      "{{\"name\": \"{}\", \"seed\": {}, \"max_asteroids\": {}, \"gravity_exponent\": {}, \"day_length\": {}}}",
+      name.replace('\\', "\\\\").replace('"', "\\\""), self.seed, self.max_asteroids, self.gravity_exponent, self.day_length
+    )
+  }
+
+  pub fn decode(fields: &[(String, String)]) -> Option<(String, Self)> {
+    let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    Some((
+      field("name")?.to_owned(),
+      Self {
+        seed: field("seed")?.parse().ok()?,
+        max_asteroids: field("max_asteroids")?.parse().ok()?,
+        gravity_exponent: field("gravity_exponent")?.parse().ok()?,
+        day_length: field("day_length")?.parse().ok()?,
+      },
+    ))
+  }
+}
+
+// reads a JSON array of flat objects with string or number values
+pub fn parse_objects(json: &str) -> Option<Vec<Vec<(String, String)>>> {
+  let mut objects = vec![];
+  let mut it = json.chars().peekable();
+  let skip_ws = |it: &mut std::iter::Peekable<std::str::Chars>| {
+    while it.peek().is_some_and(|c| c.is_whitespace()) {
+      it.next();
+    }
+  };
+  let read_string = |it: &mut std::iter::Peekable<std::str::Chars>| -> Option<String> {
+    let mut s = String::new();
+    loop {
+      match it.next()? {
+        '"' => return Some(s),
+        '\\' => s.push(it.next()?),
+        c => s.push(c),
+      }
+    }
+  };
+
+  skip_ws(&mut it);
+  if it.next()? != '[' {
+    return None;
+  }
+  loop {
+    skip_ws(&mut it);
+    match it.next()? {
+      ']' => return Some(objects),
+      ',' => continue,
+      '{' => {},
+      _ => return None,
+    }
+    let mut fields = vec![];
+    loop {
+      skip_ws(&mut it);
+      match it.next()? {
+        '}' => break,
+        ',' => continue,
+        '"' => {},
+        _ => return None,
+      }
+      let key = read_string(&mut it)?;
+      skip_ws(&mut it);
+      if it.next()? != ':' {
+        return None;
+      }
+      skip_ws(&mut it);
+      let value = if it.peek() == Some(&'"') {
+        it.next();
+        read_string(&mut it)?
+      } else {
+        let mut v = String::new();
+        while it.peek().is_some_and(|c| !matches!(c, ',' | '}') && !c.is_whitespace()) {
+          v.push(it.next()?);
+        }
+        v
+      };
+      fields.push((key, value));
+    }
+    objects.push(fields);
+  }
+}
+
+pub struct SystemLibrary {
+  path: String,
+  entries: Vec<(String, SystemConfig)>,
+}
+
+impl SystemLibrary {
+  // a missing or unreadable file starts an empty library
+  pub fn open(path: &str) -> Self {
+    let entries = fs::read_to_string(path).ok()
+      .and_then(|json| parse_objects(&json))
+      .map(|objects| objects.iter().filter_map(|fields| SystemConfig::decode(fields)).collect())
+      .unwrap_or_default();
+    Self { path: path.to_owned(), entries }
+  }
+
+  pub fn add(&mut self, name: &str, config: SystemConfig) -> io::Result<()> {
+    self.entries.retain(|(n, _)| n != name);
+    self.entries.push((name.to_owned(), config));
+    self.save()
+  }
+
+  pub fn list(&self) -> Vec<&str> {
+    self.entries.iter().map(|(n, _)| n.as_str()).collect()
+  }
+
+  pub fn load(&self, name: &str) -> Option<&SystemConfig> {
+    self.entries.iter().find(|(n, _)| n == name).map(|(_, c)| c)
+  }
+
+  fn save(&self) -> io::Result<()> {
+    let objects: Vec<String> = self.entries.iter().map(|(name, config)| format!("  {}", config.encode(name))).collect();
+    fs::write(&self.path, format!("[\n{}\n]\n", objects.join(",\n")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn saved_systems_load_back_unchanged() {
+    let path = std::env::temp_dir().join(format!("solsys_library_{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let _ = fs::remove_file(path);
+    let params = SimParams { max_asteroids: 200, gravity_exponent: 3., day_length: 12., ..SimParams::default() };
+    let (plain, tweaked) = (SystemConfig::new(42, &SimParams::default()), SystemConfig::new(7, &params));
+
+    let mut library = SystemLibrary::open(path);
+    assert!(library.list().is_empty());
+    library.add("Home", plain.clone()).unwrap();
+    library.add("A \"quoted\" \\ name", tweaked.clone()).unwrap();
+    // a name saved again replaces its entry
+    library.add("Home", plain.clone()).unwrap();
+
+    let reopened = SystemLibrary::open(path);
+    assert_eq!(reopened.list(), ["A \"quoted\" \\ name", "Home"]);
+    assert_eq!(reopened.load("Home"), Some(&plain));
+    assert_eq!(reopened.load("A \"quoted\" \\ name"), Some(&tweaked));
+    assert_eq!(reopened.load("Missing"), None);
+    let applied = tweaked.apply(&SimParams::default());
+    assert_eq!((applied.max_asteroids, applied.gravity_exponent, applied.day_length), (200, 3., 12.));
+    fs::remove_file(path).unwrap();
+  }
+}
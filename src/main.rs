@@ -1,102 +1,309 @@
 use macroquad::prelude::*;
-use macroquad::rand::{srand, ChooseRandom};
 use macroquad::telemetry::ZoneGuard;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::mem::{replace};
+use std::path::Path;
 use std::rc::{Rc};
-
+use std::sync::atomic::{AtomicUsize, Ordering};
+use solsys::{config, integrator, movable, physics, timer};
+
+use audio::{AudioCues, Cue};
+use autopilot::{AscentAutopilot, AttitudeHold, EscapeAutopilot, LPointAutopilot};
+use belt::thin_belt;
+use camera::{FocusTarget, Overview, Pan, SmoothCamera};
+use compare::IntegratorComparison;
+use config::SimConfig;
+use diagnostics::ConservationTotals;
+use divergence::PredictionCheck;
+use edits::{Edit, EditHistory};
+use forces::{ForceField, default_force_fields};
+use generators::NamesGen;
+use ghosts::OrbitGhosts;
+use history::SeedHistory;
+use hud::{screen_camera, HudAnchors};
+use integrator::IntegratorKind;
+use leaderboard::{landing_score, Leaderboard};
+use library::{SystemConfig, SystemLibrary};
+use maneuver::PlannedBurn;
+use minimap::draw_minimap;
 use movable::Movable;
+use panels::{load_panels, save_panels, Panel};
+use orbit::{TrojanPoint, closest_intercept, elements_report, elements_with_apsides, state_from_elements, intercept_miss, lambert_2d, orbit_summary, orbits_can_intersect, osculating_elements, position_on_rail, time_to_soi_exit, transfer_arc};
+use params::{BODY_TAGS, next_pair_scale, pair_gravity_scale, toggle_tag, DetailLevel, DisplayRadii, PairScale, ShipConfig, SimParams, ThrottleResponse, TurnModel};
+use pause::{focus_event, FocusPause};
+use physics::{calculate_hill_radius, collides_after, get_elliptical_position_and_velocity, get_initial_position_and_velocity, gravity_acc, normalize_angle, point_in_circle, rotate_vec2_by_rad};
+use potential::PotentialHeatmap;
+use prompt::{typed_chars, PromptOutcome, TextPrompt};
+use replay::{Replay, TrajectoryRecorder};
+use report::{export_report, system_report};
+use rng::SeededRng;
+use savegame::{load_state, newest_autosave, restore_state, save_state, AutoSave};
+use simulation::{add_body, add_ship, calendar, remove_body, stress_scenario, SimEvent, Simulation, state_hash};
+use stepper::StepAccumulator;
+use svg::{export_svg, Viewport};
+use timeline::Timeline;
 use timer::Timer;
 
-mod timer;
-mod movable;
+mod audio;
+mod autopilot;
+mod barnes_hut;
+mod belt;
+mod camera;
+mod compare;
+mod diagnostics;
+mod divergence;
+mod edits;
+mod forces;
+mod generators;
+mod ghosts;
+mod history;
+mod hud;
+mod leaderboard;
+mod library;
+mod maneuver;
+mod minimap;
+mod orbit;
+mod panels;
+mod params;
+mod pause;
+mod potential;
+mod prompt;
+mod replay;
+mod report;
+mod rng;
+mod savegame;
+mod simulation;
+mod stepper;
+mod svg;
+mod timeline;
 
 type GameObjectReference = Rc<RefCell<dyn GameObject>>;
 type CelestialBodyReference = Rc<RefCell<CelestialBody>>;
 type ShipReference = Rc<RefCell<Ship>>;
 type TrialElement = (Vec2, Color, Timer);
+type World = (CelestialBodyReference, Vec<CelestialBodyReference>, Vec<CelestialBodyReference>, Vec<CelestialBodyReference>, Vec<ShipReference>, ShipReference, Vec<GameObjectReference>, usize);
 
-const G: f32 = 50.;
-const AU: f32 = 150000.;
 const SHIP_SIZE: f32 = 10.;
+const SHIP_DRY_MASS: f32 = 1.;
+// acceleration shown as 1 g
+const STANDARD_GRAVITY: f32 = 9.81;
+const CARGO_UNIT: f32 = 0.5;
+const SKID_FRICTION: f32 = 0.5;
+// skids only below this multiple of the landing speed, and while the normal speed stays
+// under this fraction of the tangential one
+const SKID_SPEED_FACTOR: f32 = 4.;
+const SKID_NORMAL_RATIO: f32 = 0.3;
+#[cfg(debug_assertions)]
+const TELEPORT_ORBIT_RADIUS_FACTOR: f32 = 2.;
+const MAX_CARGO: f32 = 2.;
 const SHIP_ACCELERATION: f32 = 10.;
 const SHIP_ROT_SPEED: f32 = 90.;
+const SHIP_FUEL: f32 = 1000.;
+// rear corners of the hull triangle sit on the ground
+const LANDING_STANDOFF: f32 = SHIP_SIZE * 0.5;
+const THROTTLE_SPOOL_TIME: f32 = 1.5;
+const SHIP_ROT_ACCELERATION: f32 = 60.;
+const KILL_ROTATION_ACCELERATION: f32 = 360.;
+// below this speed the velocity's direction is noise, so prograde and retrograde keep the heading
+const HOLD_MIN_SPEED: f32 = 0.1;
+const RCS_FUEL_PER_DEGREE: f32 = 0.05;
 const INFO_FONT_SIZE: f32 = 18.;
+const HOME_ARROW_LENGTH: f32 = 40.;
+const TRAIL_SUBDIVISIONS: usize = 4;
+// seconds an impact's spark stays on screen, and the screen radius it grows to
+const SPARK_TIME: f32 = 0.6;
+const SPARK_RADIUS: f32 = 20.;
 const TRAIL_CLEANUP_IIME: f32 = 300.;
 const PHYSICS_STEP: f32 = 0.02;
 const SIMULATION_STEP: f32 = 0.5;
-const MAJOR_CB_HILL_RADIUS_COEFICIENT: f32 = 3.;
 const DAY_TIME: f32 = 24.;
-const TERMINAL_VELOCITY: f32 = 30.;
+const DAYS_PER_YEAR: u32 = 100;
+// 360 belt sectors with at most 4 asteroids each
+const MAX_ASTEROIDS: usize = 1440;
+const TRACER_MASS_THRESHOLD: f32 = 1000.;
+const STRESS_BODY_COUNT: usize = 2000;
+const STRESS_BODIES_PER_RING: usize = 64;
+const STRESS_RING_SPACING: f32 = 2000.;
+const TRANSFER_ARC_SAMPLES: usize = 100;
+const EVENT_LOG_SIZE: usize = 5;
+const PREDICTION_SAMPLE_EVERY: usize = 5;
+const ENVELOPE_RUNS: usize = 5;
+const ENVELOPE_STEP_SPREAD: f32 = 0.4;
+const ENVELOPE_SAMPLES: usize = 40;
+const STAR_GLOW_RINGS: usize = 8;
+const STAR_GLOW_RADIUS_FACTOR: f32 = 3.;
+const STAR_GLOW_ALPHA: f32 = 0.3;
+const ATMOSPHERE_ALPHA: f32 = 0.2;
+const TERMINATOR_SEGMENTS: usize = 16;
+const DEPTH_SIZE_FACTOR: f32 = 0.3;
+const DEPTH_PARALLAX: f32 = 0.02;
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 5000.;
+const OVERVIEW_MARGIN: f32 = 1.1;
+const ORBIT_SCALE_MARGIN: f32 = 1.2;
+const LOW_PREDICTION_CONFIDENCE: f32 = 0.5;
+const APSIS_DASH_LENGTH: f32 = 8.;
+const APSIS_HANDLE_RADIUS: f32 = 6.;
+const ZOOM_EASE_RATE: f32 = 12.;
+const HEATMAP_REFRESH_TIME: f32 = 1.;
+const PLACED_BODY_MASS: f32 = 100.;
+const PLACED_BODY_RADIUS: f32 = 30.;
+const FLING_TIME: f32 = 100.;
+const BURN_PREVIEW_TIME: f32 = 10.;
+const REPLAY_SCRUB_RATE: f32 = 4.;
+const REWIND_STEP: f64 = 10.;
+const COMPOSE_PAN_SPEED: f32 = 400.;
+const LIBRARY_PATH: &str = "systems.json";
+const PANELS_PATH: &str = "panels.json";
+const MINIMAP_SIZE: f32 = 200.;
+const LEADERBOARD_PATH: &str = "landings.json";
+const AUTOSAVE_DIR: &str = ".";
+const QUICKSAVE_PATH: &str = "quicksave.json";
+// below this many asteroids the belt pass stays on the main thread
+const MIN_PARALLEL_BODIES: usize = 256;
+const SHIP_PALETTE: [Color; 6] = [WHITE, SKYBLUE, PINK, LIME, GOLD, VIOLET];
+const THROTTLE_KEYS: [KeyCode; 10] = [
+  KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+  KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+];
 
 
 fn wrap_object<T>(obj: T) -> Rc<RefCell<T>> {
   Rc::new(RefCell::new(obj))
 }
 
-fn rotate_vec2_by_rad(v: &Vec2, rad: f32) -> Vec2 {
-  let c = rad.cos();
-  let s = rad.sin();
-  vec2(c*v.x - s*v.y, s*v.x + c*v.y)
+// the star's starting position, the middle of the default window; a function since the locked
+// glam has no const Vec2 constructor
+fn system_center() -> Vec2 {
+  vec2(660., 380.)
+}
+
+// plain data kernel over (pos, mass) snapshots, accelerations towards the parent out; each
+// entry is independent, so a slice of the belt gives the same values as the whole
+fn compute_belt_accelerations(snapshots: &[(Vec2, f32)], parent: (Vec2, f32), g: f32, exponent: f32, softening: f32) -> Vec<Vec2> {
+  snapshots.iter()
+    .map(|(pos, mass)| gravity_acc(*pos, *mass, parent.0, parent.1, g, exponent, softening).0)
+    .collect()
 }
 
-fn gravity_vel(a_pos: Vec2, a_mass: f32, b_pos: Vec2, b_mass: f32, dt: f32) -> (Vec2, Vec2) {
-  let distance_vector = a_pos - b_pos;
-  let force_vec = distance_vector.normalize();
-  let distance_length = distance_vector.length_squared();
+// splits the snapshots over scoped threads, rayon is not a dependency of this crate; small
+// belts and the web build stay serial so they don't pay the spawn cost every step
+fn compute_belt_accelerations_threaded(snapshots: &[(Vec2, f32)], parent: (Vec2, f32), g: f32, exponent: f32, softening: f32, threads: usize) -> Vec<Vec2> {
+  if threads <= 1 || snapshots.len() < MIN_PARALLEL_BODIES || cfg!(target_arch = "wasm32") {
+    return compute_belt_accelerations(snapshots, parent, g, exponent, softening);
+  }
+  let chunk = snapshots.len().div_ceil(threads);
+  std::thread::scope(|scope| {
+    let handles: Vec<_> = snapshots.chunks(chunk)
+      .map(|c| scope.spawn(move || compute_belt_accelerations(c, parent, g, exponent, softening)))
+      .collect();
+    handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+  })
+}
 
-  (
-    -force_vec * b_mass * G / distance_length * dt,
-    force_vec * a_mass * G / distance_length * dt,
-  )
+fn apply_gravity_asteroids(asteroids: &[CelestialBodyReference], parent: &CelestialBodyReference, g: f32, exponent: f32, softening: f32, scales: &[PairScale], threads: usize) {
+  let snapshots: Vec<(Vec2, f32)> = asteroids.iter().map(|a| (a.borrow().mov.pos, a.borrow().mov.mass)).collect();
+  let parent_id = parent.borrow().id;
+  let parent = (parent.borrow().mov.pos, parent.borrow().mov.mass);
+  let accelerations = compute_belt_accelerations_threaded(&snapshots, parent, g, exponent, softening, threads);
+  for (a, acc) in asteroids.iter().zip(accelerations) {
+    let mut a = a.borrow_mut();
+    let scale = if scales.is_empty() { 1. } else { pair_gravity_scale(scales, a.id, parent_id) };
+    a.mov.acc += acc * scale;
+  }
 }
 
-fn apply_gravity_asteroids(asteroids: &[CelestialBodyReference], parent: &CelestialBodyReference, dt: f32) {
-  for a in asteroids {
-    let mut go_a = a.borrow_mut();
-    let go_b = parent.borrow();
-    let (vela, _) = gravity_vel(go_a.mov.pos, go_a.mov.mass, go_b.mov.pos, go_b.mov.mass, dt);
-    go_a.mov.vel += vela;
+// tracers feel the massive bodies but exert nothing back
+fn apply_gravity_to_tracers(tracers: &[CelestialBodyReference], massive: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32, scales: &[PairScale]) {
+  for t in tracers {
+    let mut go_a = t.borrow_mut();
+    for m in massive {
+      let go_b = m.borrow();
+      let (acca, _) = gravity_acc(go_a.mov.pos, go_a.mov.mass, go_b.mov.pos, go_b.mov.mass, g, exponent, softening);
+      let scale = if scales.is_empty() { 1. } else { pair_gravity_scale(scales, go_a.id, go_b.id) };
+      go_a.mov.acc += acca * scale;
+    }
   }
 }
 
-fn apply_gravity_to_celestial_bodies(celestial_bodies: &[CelestialBodyReference], dt: f32) {
+fn partition_by_mass(celestial_bodies: &[CelestialBodyReference], threshold: f32) -> (Vec<CelestialBodyReference>, Vec<CelestialBodyReference>) {
+  celestial_bodies.iter().cloned().partition(|cb| cb.borrow().mov.mass >= threshold)
+}
+
+fn apply_gravity_to_celestial_bodies(celestial_bodies: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32, scales: &[PairScale]) {
+  let mut accelerations = vec![Vec2::ZERO; celestial_bodies.len()];
   for i in 0..celestial_bodies.len() {
-    let mut go_a = celestial_bodies[i].borrow_mut();
+    let go_a = celestial_bodies[i].borrow();
     for j in (i+1)..celestial_bodies.len() {
-      let mut go_b = celestial_bodies[j].borrow_mut();
-      let (vela, velb) = gravity_vel(go_a.mov.pos, go_a.mov.mass, go_b.mov.pos, go_b.mov.mass, dt);
-      go_a.mov.vel += vela;
-      go_b.mov.vel += velb;
+      let go_b = celestial_bodies[j].borrow();
+      let (mut acca, mut accb) = gravity_acc(go_a.mov.pos, go_a.mov.mass, go_b.mov.pos, go_b.mov.mass, g, exponent, softening);
+      // the lookup only runs once an override exists
+      if !scales.is_empty() {
+        let scale = pair_gravity_scale(scales, go_a.id, go_b.id);
+        acca *= scale;
+        accb *= scale;
+      }
+      accelerations[i] += acca;
+      accelerations[j] += accb;
     }
   }
+  for (cb, acc) in celestial_bodies.iter().zip(accelerations) {
+    cb.borrow_mut().mov.acc += acc;
+  }
+}
+
+fn apply_gravity_to_ships(ships: &[ShipReference], celestial_bodies: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32) {
+  for s in ships {
+    s.borrow_mut().apply_gravity(celestial_bodies, g, exponent, softening);
+  }
 }
 
-fn apply_gravity_to_ships(ships: &[ShipReference], celestial_bodies: &[CelestialBodyReference], dt: f32) {
+fn apply_force_fields_to_ships(ships: &[ShipReference], force_fields: &[Box<dyn ForceField>]) {
   for s in ships {
-    s.borrow_mut().apply_gravity(celestial_bodies, dt);
+    s.borrow_mut().apply_force_fields(force_fields);
   }
 }
 
-fn get_initial_position_and_velocity(parent_mass: f32, distance: f32, angle: f32) -> (Vec2, Vec2) {
-  let delta_vector = rotate_vec2_by_rad(&vec2(distance, 0.), angle.to_radians());
-  let speed = (parent_mass / distance * G).sqrt();
-  (delta_vector, delta_vector.perp().normalize() * speed)
+fn illumination(body_pos: Vec2, star_pos: Vec2, star_luminosity: f32) -> f32 {
+  star_luminosity / body_pos.distance_squared(star_pos)
 }
 
-fn point_in_circle(point: &Vec2, circle: &Vec2, radius: f32) -> bool {
-  circle.distance_squared(*point) < (radius).powi(2)
+// concentric rings fading outwards, drawn under the star's disc
+fn draw_star_glow(pos: Vec2, radius: f32, color: Color) {
+  for i in (1..=STAR_GLOW_RINGS).rev() {
+    let t = i as f32 / STAR_GLOW_RINGS as f32;
+    draw_circle(pos.x, pos.y, radius * (1. + (STAR_GLOW_RADIUS_FACTOR - 1.) * t), Color::new(color.r, color.g, color.b, STAR_GLOW_ALPHA * (1. - t)));
+  }
 }
 
-fn calculate_hill_radius(parent_pos: Vec2, parent_mass: f32, child_pos: Vec2, child_mass: f32) -> f32 {
-  let a = (child_pos - parent_pos).length();
-  a * (child_mass / (3. * parent_mass)).cbrt()
+fn draw_terminator(body: &CelestialBody, star: &CelestialBody, luminosity: f32, focus: Vec2, scale: f32, radii: &DisplayRadii) {
+  let act_pos = (body.mov.pos - focus) / scale;
+  let radius = body.display_radius(scale, radii);
+  let away = (body.mov.pos - star.mov.pos).normalize_or_zero();
+  let start = away.y.atan2(away.x) - 90_f32.to_radians();
+  let step = 180_f32.to_radians() / TERMINATOR_SEGMENTS as f32;
+  let dim = 1. - illumination(body.mov.pos, star.mov.pos, luminosity).min(1.);
+
+  draw_circle(act_pos.x, act_pos.y, radius, Color::new(0., 0., 0., dim * 0.5));
+  for i in 0..TERMINATOR_SEGMENTS {
+    let a = start + step * i as f32;
+    draw_triangle(
+      act_pos,
+      act_pos + vec2(a.cos(), a.sin()) * radius,
+      act_pos + vec2((a + step).cos(), (a + step).sin()) * radius,
+      Color::new(0., 0., 0., 0.6),
+    );
+  }
 }
 
 trait GameObject {
-  fn update(&mut self, dt: f32);
-  fn draw(&self, focus: Vec2, scale: f32);
+  fn update(&mut self, integrator: IntegratorKind, dt: f32);
+  fn complete_step(&mut self, integrator: IntegratorKind, dt: f32);
+  fn draw(&self, focus: Vec2, scale: f32, detail: DetailLevel, params: &SimParams, alpha: f32);
+  // false once the object should be dropped from the simulation
+  fn is_alive(&self) -> bool;
 }
 
 #[derive(Clone)]
@@ -107,85 +314,147 @@ enum CelestialBodyType {
   Asteroid,
 }
 
-impl CelestialBodyType {
-  pub fn min_display_radius(&self) -> f32 {
-    match self {
-      Self::Star => 15.,
-      Self::Planet => 5.,
-      Self::Moon => 3.,
-      Self::Asteroid => 1.,
-    }
-  }
-}
-
 #[derive(Clone)]
 struct CelestialBody {
+  // unique for the run and kept by copies, unlike names or positions in the body lists
+  id: usize,
   mov: Movable,
   radius: f32,
   cb_type: CelestialBodyType,
   hill_radius: f32,
+  hill_radius_coefficient: f32,
   color: Color,
   name: String,
+  depth: f32,
+  max_landing_speed: f32,
+  atmosphere_height: Option<f32>,
+  tags: Vec<String>,
+  last_good: (Vec2, Vec2),
+}
+
+fn next_body_id() -> usize {
+  static NEXT_BODY_ID: AtomicUsize = AtomicUsize::new(0);
+  NEXT_BODY_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+fn depth_scale(depth: f32) -> f32 {
+  1. + depth * DEPTH_SIZE_FACTOR
 }
 
 impl CelestialBody {
-  pub fn new(pos: Vec2, mass: f32, radius: f32, cb_type: CelestialBodyType, color: Color, name: String) -> Self {
+  pub fn new(pos: Vec2, mass: f32, radius: f32, cb_type: CelestialBodyType, color: Color, name: String, params: &SimParams) -> Self {
     Self {
+      id: next_body_id(),
       mov: Movable::new(pos, Vec2::ZERO, mass, 0.),
       radius,
       cb_type,
       hill_radius: f32::INFINITY,
+      hill_radius_coefficient: params.config.hill_radius_coefficient,
       color,
       name,
+      depth: 0.,
+      max_landing_speed: params.config.max_landing_speed,
+      atmosphere_height: None,
+      tags: vec![],
+      last_good: (pos, Vec2::ZERO),
     }
   }
 
-  pub fn from_parent(parent: &CelestialBody, distance: f32, angle: f32, mass: f32, radius: f32, cb_type: CelestialBodyType, color: Color, name: String) -> Self {
-    let (pos, vel) = get_initial_position_and_velocity(parent.mov.mass, distance, angle);
+  #[allow(clippy::too_many_arguments)]
+  pub fn from_parent(parent: &CelestialBody, distance: f32, angle: f32, mass: f32, radius: f32, cb_type: CelestialBodyType, color: Color, name: String, params: &SimParams) -> Self {
+    let (pos, vel) = get_initial_position_and_velocity(parent.mov.mass, distance, angle, &params.config);
     let mov = Movable::new(parent.mov.pos + pos, parent.mov.vel + vel, mass, 0.);
-    let hill_radius = calculate_hill_radius(parent.mov.pos, parent.mov.mass, mov.pos + pos, mov.mass);
+    let hill_radius = calculate_hill_radius(parent.mov.pos, parent.mov.mass, mov.pos, mov.mass);
 
     Self {
-      mov,
+      id: next_body_id(),
       radius,
       cb_type,
       hill_radius,
+      hill_radius_coefficient: params.config.hill_radius_coefficient,
       color,
       name,
+      depth: 0.,
+      max_landing_speed: params.config.max_landing_speed,
+      atmosphere_height: None,
+      tags: vec![],
+      last_good: (mov.pos, mov.vel),
+      mov,
     }
   }
 
-  pub fn pos_in_hill_radius(&self, pos: &Vec2) -> bool {
-    let hr = match self.cb_type {
+  pub fn display_radius(&self, scale: f32, radii: &DisplayRadii) -> f32 {
+    (self.radius / scale).max(radii.for_type(&self.cb_type))
+  }
+
+  // outer edge of the atmosphere shading on screen, None for airless bodies
+  pub fn atmosphere_radius(&self, scale: f32) -> Option<f32> {
+    self.atmosphere_height.map(|height| (self.radius + height) / scale * depth_scale(self.depth))
+  }
+
+  pub fn draws_glow(&self, params: &SimParams) -> bool {
+    matches!(self.cb_type, CelestialBodyType::Star) && params.star_glow
+  }
+
+  pub fn soi_radius(&self) -> f32 {
+    match self.cb_type {
       CelestialBodyType::Asteroid => self.hill_radius,
-      _ => self.hill_radius * MAJOR_CB_HILL_RADIUS_COEFICIENT
-    };
-    point_in_circle(pos, &self.mov.pos, hr)
+      _ => self.hill_radius * self.hill_radius_coefficient
+    }
+  }
+
+  pub fn pos_in_hill_radius(&self, pos: &Vec2) -> bool {
+    point_in_circle(pos, &self.mov.pos, self.soi_radius())
   }
 }
 
 impl GameObject for CelestialBody {
-  fn update(&mut self, dt: f32) {
-    self.mov.update(dt);
+  fn update(&mut self, integrator: IntegratorKind, dt: f32) {
+    self.mov.update(integrator, dt);
   }
 
-  fn draw(&self, focus: Vec2, scale: f32) {
-    let act_pos = (self.mov.pos - focus) / scale;
-    let radius = (self.radius / scale).max(self.cb_type.min_display_radius());
-    draw_circle(act_pos.x, act_pos.y, radius, self.color);
-    match self.cb_type {
-      CelestialBodyType::Asteroid => {},
-      _ => {
-        // draw_circle_lines(act_pos.x, act_pos.y, self.hill_radius / scale, 1., self.color);
-        draw_text(&format!("{}", self.name), act_pos.x - radius / 2., act_pos.y - radius - INFO_FONT_SIZE + 4., INFO_FONT_SIZE, self.color);
+  fn complete_step(&mut self, integrator: IntegratorKind, dt: f32) {
+    self.mov.complete_step(integrator, dt);
+  }
+
+  // bodies leave through remove_body, which also clears them from the other collections
+  fn is_alive(&self) -> bool {
+    true
+  }
+
+  fn draw(&self, focus: Vec2, scale: f32, detail: DetailLevel, params: &SimParams, alpha: f32) {
+    let Some(fade) = params.tag_filter.alpha(&self.tags) else { return };
+    let color = Color::new(self.color.r, self.color.g, self.color.b, self.color.a * fade);
+    let act_pos = (self.mov.render_pos(alpha) - focus) / scale * (1. + self.depth * DEPTH_PARALLAX);
+    let radius = self.display_radius(scale, &params.min_display_radius) * depth_scale(self.depth);
+    if self.draws_glow(params) {
+      draw_star_glow(act_pos, radius, color);
+    }
+    match (&self.cb_type, detail) {
+      (CelestialBodyType::Asteroid, DetailLevel::Low) => draw_rectangle(act_pos.x, act_pos.y, 1., 1., color),
+      _ => draw_circle(act_pos.x, act_pos.y, radius, color),
+    }
+    if !matches!(self.cb_type, CelestialBodyType::Asteroid | CelestialBodyType::Star) {
+      // the collision surface, smaller than the disc whenever the minimum display radius kicks in
+      let surface = self.radius / scale * depth_scale(self.depth);
+      if surface < radius {
+        draw_circle(act_pos.x, act_pos.y, surface, Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, color.a));
       }
     }
+    if let Some(shell) = self.atmosphere_radius(scale) {
+      draw_circle(act_pos.x, act_pos.y, shell, Color::new(color.r, color.g, color.b, ATMOSPHERE_ALPHA * fade));
+    }
+    if detail.shows_labels() && !matches!(self.cb_type, CelestialBodyType::Asteroid) {
+      // draw_circle_lines(act_pos.x, act_pos.y, self.hill_radius / scale, 1., self.color);
+      draw_text(&self.name, act_pos.x - radius / 2., act_pos.y - radius - INFO_FONT_SIZE + 4., INFO_FONT_SIZE, color);
+    }
   }
 }
 
 #[derive(Clone)]
 enum ShipState {
-  Landed(CelestialBodyReference, Vec2),
+  // body, accumulated takeoff velocity, resting offset from the body's centre
+  Landed(CelestialBodyReference, Vec2, Vec2),
   InSpace,
   Destroyed,
 }
@@ -196,7 +465,7 @@ impl Debug for ShipState {
       ShipState::InSpace => {
         write!(f, "In space")
       },
-      ShipState::Landed(cb, tv) => {
+      ShipState::Landed(cb, tv, _) => {
         write!(f, "Landed on {}, takeoff v: [{:.2}][{:.2}]", cb.borrow().name, tv.x, tv.y)
       },
       ShipState::Destroyed => {
@@ -213,18 +482,54 @@ struct Ship {
   store: ShipState,
   fuel: f32,
   max_fuel: f32,
+  thrust: f32,
+  turn_rate: f32,
+  throttle_level: f32,
+  total_dv_used: f32,
+  // fuel per second drawn by the main engine on the latest throttle call
+  fuel_flow: f32,
+  cargo_mass: f32,
+  cargo_origin: Option<CelestialBodyReference>,
+  last_vel: Vec2,
+  // the latest finite position and velocity, restored by sanitize
+  last_good: (Vec2, Vec2),
+  last_landing_score: u32,
+  g_force: f32,
+  peak_g_force: f32,
+  landing_standoff: f32,
+  collision_radius: f32,
+  frozen: bool,
   in_hill_radius_of: Vec<CelestialBodyReference>,
+  name: String,
+  color: Color,
 }
 
 impl Ship {
-  pub fn new(pos: Vec2, vel: Vec2, fuel: f32) -> Self {
+  pub fn new(pos: Vec2, vel: Vec2, config: &ShipConfig, name: String, color: Color) -> Self {
     Self {
-      mov: Movable::new(pos, vel, 1., 0.),
+      mov: Movable::new(pos, vel, SHIP_DRY_MASS, 0.),
       state: ShipState::InSpace,
       store: ShipState::InSpace,
-      fuel,
-      max_fuel: fuel,
-      in_hill_radius_of: vec![]
+      fuel: config.fuel,
+      max_fuel: config.fuel,
+      thrust: config.thrust,
+      turn_rate: config.turn_rate,
+      throttle_level: 0.,
+      total_dv_used: 0.,
+      fuel_flow: 0.,
+      cargo_mass: 0.,
+      cargo_origin: None,
+      last_vel: vel,
+      last_good: (pos, vel),
+      last_landing_score: 0,
+      g_force: 0.,
+      peak_g_force: 0.,
+      landing_standoff: config.landing_standoff,
+      collision_radius: SHIP_SIZE / 2.,
+      frozen: false,
+      in_hill_radius_of: vec![],
+      name,
+      color,
     }
   }
 
@@ -239,43 +544,219 @@ impl Ship {
     self.mov.load();
   }
 
-  pub fn throttle_up(&mut self, dt: f32) {
-    if self.fuel <= 0. {
+  pub fn throttle_up(&mut self, params: &SimParams, dt: f32) {
+    self.throttle(1., params, dt);
+  }
+
+  pub fn throttle(&mut self, amount: f32, params: &SimParams, dt: f32) {
+    self.tick_throttle(amount.clamp(0., 1.), params, dt);
+    self.fuel_flow = 0.;
+    // a wreck has no engine to burn with
+    if matches!(self.state, ShipState::Destroyed) || self.fuel <= 0. || self.throttle_level == 0. {
       return;
     }
 
-    let vel = rotate_vec2_by_rad(&vec2(1., 0.), self.mov.rot) * SHIP_ACCELERATION * dt / self.mov.mass;
+    let acceleration = self.thrust * params.control_sensitivity * self.throttle_level;
+    // the last drop of fuel only pays for part of a step
+    let burn_time = dt.min(self.fuel / acceleration);
+    let vel = rotate_vec2_by_rad(&vec2(1., 0.), self.mov.rot) * acceleration * burn_time / self.mov.mass;
     match self.state {
       ShipState::InSpace => {
         self.mov.vel += vel;
       },
-      ShipState::Landed(_, ref mut takeoff_vel) => {
+      ShipState::Landed(_, ref mut takeoff_vel, _) => {
         *takeoff_vel += vel;
       },
       _ => {}
     }
-    self.fuel -= (SHIP_ACCELERATION * dt).max(0.);
+    self.total_dv_used += acceleration * burn_time / self.mov.mass;
+    self.fuel_flow = acceleration;
+    self.fuel = (self.fuel - acceleration * burn_time).max(0.);
+  }
+
+  // net acceleration from thrust and gravity since the previous sample, in g
+  pub fn sample_g_force(&mut self, sim_dt: f32) {
+    if sim_dt <= 0. {
+      return;
+    }
+    self.g_force = (self.mov.vel - self.last_vel).length() / sim_dt / STANDARD_GRAVITY;
+    self.peak_g_force = self.peak_g_force.max(self.g_force);
+    self.last_vel = self.mov.vel;
+  }
+
+  fn set_cargo(&mut self, cargo_mass: f32) {
+    self.cargo_mass = cargo_mass;
+    self.mov.mass = SHIP_DRY_MASS + cargo_mass;
+  }
+
+  pub fn load_cargo(&mut self, amount: f32) -> Result<(), String> {
+    let ShipState::Landed(cb, _, _) = &self.state else {
+      return Err("Cargo can only be loaded while landed".to_owned());
+    };
+    if self.cargo_mass + amount > MAX_CARGO {
+      return Err(format!("Cargo hold full ({:.1})", MAX_CARGO));
+    }
+    self.cargo_origin = Some(cb.clone());
+    self.set_cargo(self.cargo_mass + amount);
+    Ok(())
+  }
+
+  // unloads everything, but only at a different body than the cargo came from
+  pub fn deliver_cargo(&mut self) -> Result<f32, String> {
+    let ShipState::Landed(cb, _, _) = &self.state else {
+      return Err("Cargo can only be delivered while landed".to_owned());
+    };
+    match &self.cargo_origin {
+      None => return Err("No cargo on board".to_owned()),
+      Some(origin) if Rc::ptr_eq(origin, cb) => return Err(format!("Cargo was loaded at {}", cb.borrow().name)),
+      _ => {},
+    }
+    let delivered = self.cargo_mass;
+    self.cargo_origin = None;
+    self.set_cargo(0.);
+    Ok(delivered)
+  }
+
+  pub fn fuel_fraction(&self) -> f32 {
+    if self.max_fuel <= 0. {
+      return 0.;
+    }
+    (self.fuel / self.max_fuel).clamp(0., 1.)
+  }
+
+  // burning a unit of fuel yields a unit of thrust impulse, whatever the throttle
+  pub fn remaining_dv(&self) -> f32 {
+    self.fuel.max(0.) / self.mov.mass
+  }
+
+  fn tick_throttle(&mut self, commanded: f32, params: &SimParams, dt: f32) {
+    self.throttle_level = match params.throttle_response {
+      ThrottleResponse::Linear => commanded,
+      ThrottleResponse::SpoolUp => {
+        let step = dt / THROTTLE_SPOOL_TIME;
+        self.throttle_level + (commanded - self.throttle_level).clamp(-step, step)
+      },
+    };
+  }
+
+  // delta-v a burn at this throttle would give over the duration, limited by remaining fuel
+  pub fn burn_preview(&self, amount: f32, duration: f32, params: &SimParams) -> Vec2 {
+    let acceleration = self.thrust * params.control_sensitivity * amount.clamp(0., 1.);
+    if acceleration <= 0. {
+      return Vec2::ZERO;
+    }
+    let burn_time = duration.min(self.fuel.max(0.) / acceleration);
+    rotate_vec2_by_rad(&vec2(1., 0.), self.mov.rot) * acceleration * burn_time / self.mov.mass
+  }
+
+  fn turn(&mut self, direction: f32, params: &SimParams, dt: f32) {
+    match params.turn_model {
+      TurnModel::Instant => {
+        self.mov.rot += direction * self.turn_rate.to_radians() * params.control_sensitivity * dt;
+        self.mov.wrap_rot();
+      },
+      TurnModel::Inertial => {
+        let max_rot_vel = self.turn_rate.to_radians() * params.control_sensitivity;
+        self.mov.rot_vel = (self.mov.rot_vel + direction * SHIP_ROT_ACCELERATION.to_radians() * params.control_sensitivity * dt).clamp(-max_rot_vel, max_rot_vel);
+      }
+    }
+  }
+
+  pub fn turn_left(&mut self, params: &SimParams, dt: f32) {
+    self.turn(-1., params, dt);
+  }
+
+  pub fn turn_right(&mut self, params: &SimParams, dt: f32) {
+    self.turn(1., params, dt);
+  }
+
+  pub fn point_toward(&mut self, direction: Vec2, params: &SimParams, dt: f32) {
+    if direction.length_squared() == 0. {
+      return;
+    }
+    let diff = normalize_angle(direction.y.atan2(direction.x) - self.mov.rot);
+    let max_step = self.turn_rate.to_radians() * params.control_sensitivity * dt;
+    self.mov.rot += diff.clamp(-max_step, max_step);
+    self.mov.wrap_rot();
+    self.mov.rot_vel = 0.;
+  }
+
+  pub fn hold_attitude(&mut self, hold: AttitudeHold, target: Option<&CelestialBody>, params: &SimParams, dt: f32) {
+    let ShipState::InSpace = self.state else {
+      return;
+    };
+    let direction = match hold {
+      AttitudeHold::Prograde | AttitudeHold::Retrograde if self.mov.vel.length() < HOLD_MIN_SPEED => return,
+      AttitudeHold::Prograde => self.mov.vel,
+      AttitudeHold::Retrograde => -self.mov.vel,
+      AttitudeHold::Target => target.map_or(Vec2::ZERO, |t| t.mov.pos - self.mov.pos),
+    };
+    self.point_toward(direction, params, dt);
   }
 
-  pub fn turn_left(&mut self, dt: f32) {
-    self.mov.rot -= SHIP_ROT_SPEED.to_radians() * dt;
+  pub fn damp_rotation(&mut self, dt: f32) {
+    let delta = SHIP_ROT_ACCELERATION.to_radians() * dt;
+    self.mov.rot_vel -= self.mov.rot_vel.clamp(-delta, delta);
   }
 
-  pub fn turn_right(&mut self, dt: f32) {
-    self.mov.rot += SHIP_ROT_SPEED.to_radians() * dt;
+  // stronger than damp_rotation, paid for with RCS fuel
+  pub fn kill_rotation(&mut self, dt: f32) {
+    if self.fuel <= 0. {
+      return;
+    }
+    let delta = KILL_ROTATION_ACCELERATION.to_radians() * dt;
+    let change = self.mov.rot_vel.clamp(-delta, delta);
+    self.mov.rot_vel -= change;
+    self.fuel = (self.fuel - change.abs().to_degrees() * RCS_FUEL_PER_DEGREE).max(0.);
   }
 
-  fn land(&mut self, cb: CelestialBodyReference) {
+  fn land(&mut self, cb: CelestialBodyReference, config: &SimConfig) {
+    let max_landing_angle = config.max_landing_angle;
     let rot = -(self.mov.pos - cb.borrow().mov.pos).angle_between(vec2(1., 0.));
-    println!("{} > {}, {}, {}", (self.mov.rot - rot).abs(), 30_f32.to_radians(), (self.mov.vel - cb.borrow().mov.vel).length_squared(), TERMINAL_VELOCITY.powi(2));
-    if (self.mov.rot % 360_f32.to_radians() - rot).abs() > 30_f32.to_radians() || (self.mov.vel - cb.borrow().mov.vel).length_squared() > TERMINAL_VELOCITY.powi(2) {
+    let max_landing_speed = cb.borrow().max_landing_speed;
+    let angle_error = normalize_angle(self.mov.rot - rot).abs();
+    if angle_error > max_landing_angle.to_radians() || (self.mov.vel - cb.borrow().mov.vel).length_squared() > max_landing_speed.powi(2) {
       self.state = ShipState::Destroyed;
       return;
     }
+    let speed = (self.mov.vel - cb.borrow().mov.vel).length();
+    self.last_landing_score = landing_score(speed, max_landing_speed, angle_error, max_landing_angle.to_radians());
 
     self.mov.rot = rot;
+    self.mov.rot_vel = 0.;
     self.fuel = self.max_fuel;
-    self.state = ShipState::Landed(cb.clone(), Vec2::ZERO);
+    let offset = self.surface_offset(&cb.borrow());
+    self.mov.pos = cb.borrow().mov.pos + offset;
+    // matching the body's velocity is a contact, not an acceleration for the g readout
+    self.mov.vel = cb.borrow().mov.vel;
+    self.last_vel = self.mov.vel;
+    self.state = ShipState::Landed(cb.clone(), Vec2::ZERO, offset);
+  }
+
+  // a grazing, mostly tangential touch slides along the surface losing speed to friction
+  // instead of landing or crashing outright
+  fn skid(&mut self, cb: &CelestialBody, friction: f32, dt: f32) -> bool {
+    if friction <= 0. {
+      return false;
+    }
+    let normal = (self.mov.pos - cb.mov.pos).normalize_or_zero();
+    let rel_vel = self.mov.vel - cb.mov.vel;
+    let normal_speed = rel_vel.dot(normal);
+    let tangential = rel_vel - normal * normal_speed;
+    let tangential_speed = tangential.length();
+    if tangential_speed <= cb.max_landing_speed
+      || tangential_speed > cb.max_landing_speed * SKID_SPEED_FACTOR
+      || normal_speed.abs() > tangential_speed * SKID_NORMAL_RATIO {
+      return false;
+    }
+    self.mov.pos = cb.mov.pos + normal * (cb.radius + self.collision_radius);
+    self.mov.vel = cb.mov.vel + tangential * (-friction * dt).exp();
+    true
+  }
+
+  // resting spot above the surface along the normal through the current position
+  fn surface_offset(&self, cb: &CelestialBody) -> Vec2 {
+    (self.mov.pos - cb.mov.pos).normalize_or_zero() * (cb.radius + self.landing_standoff)
   }
 
   fn takeoff(&mut self, takeoff_vel: Vec2) {
@@ -283,18 +764,23 @@ impl Ship {
     self.mov.vel += takeoff_vel;
   }
 
-  pub fn process_collision(&mut self, celestial_bodies: &[CelestialBodyReference], dt: f32) {
+  pub fn process_collision(&mut self, celestial_bodies: &[CelestialBodyReference], params: &SimParams, dt: f32) {
+    if self.frozen {
+      return;
+    }
     match self.state.clone() {
       ShipState::InSpace => {
         for cb in celestial_bodies {
-          if self.check_collision(Vec2::ZERO, &cb.borrow(), dt) {
-            self.land(cb.clone());
+          if self.check_collision(Vec2::ZERO, &cb.borrow(), dt) && !self.skid(&cb.borrow(), params.skid_friction, dt) {
+            self.land(cb.clone(), &params.config);
           }
         }
       },
-      ShipState::Landed(cb, takeoff_vel) => {
-        if !self.check_collision(takeoff_vel, &cb.borrow(), dt) {
+      ShipState::Landed(cb, takeoff_vel, offset) => {
+        if takeoff_vel.dot(offset) > 0. {
           self.takeoff(takeoff_vel);
+        } else {
+          self.mov.pos = cb.borrow().mov.pos + offset;
         }
       },
       _ => {}
@@ -302,14 +788,13 @@ impl Ship {
   }
 
   pub fn check_collision(&self, vel: Vec2, cb: &CelestialBody, dt: f32) -> bool {
-    let mut m = self.mov.clone();
-    m.vel += vel;
-    m.update(dt);
-
-    point_in_circle(&m.pos, &cb.mov.pos, cb.radius + SHIP_SIZE / 2.)
+    collides_after(self.mov.pos, self.mov.vel + vel - cb.mov.vel, dt, cb.mov.pos, cb.radius + self.collision_radius)
   }
 
-  pub fn apply_gravity(&mut self, celestial_bodies: &[CelestialBodyReference], dt: f32) {
+  pub fn apply_gravity(&mut self, celestial_bodies: &[CelestialBodyReference], g: f32, exponent: f32, softening: f32) {
+    if self.frozen {
+      return;
+    }
     match &self.state {
       ShipState::InSpace | ShipState::Destroyed => {
         self.in_hill_radius_of.clear();
@@ -317,63 +802,348 @@ impl Ship {
           if cb.borrow().pos_in_hill_radius(&self.mov.pos) {
             self.in_hill_radius_of.push(cb.clone());
             let mut cb = cb.borrow_mut();
-            let (vela, velb) = gravity_vel(self.mov.pos, self.mov.mass, cb.mov.pos, cb.mov.mass, dt);
-            self.mov.vel += vela;
-            cb.mov.vel += velb;
+            let (acca, accb) = gravity_acc(self.mov.pos, self.mov.mass, cb.mov.pos, cb.mov.mass, g, exponent, softening);
+            self.mov.acc += acca;
+            cb.mov.acc += accb;
           }
         }
       },
-      ShipState::Landed(cb, _) => {
+      ShipState::Landed(cb, _, _) => {
         self.mov.vel = cb.borrow().mov.vel;
       }
     }
   }
+
+  pub fn dominant_body(&self) -> Option<CelestialBodyReference> {
+    self.in_hill_radius_of.iter()
+      .min_by(|a, b| a.borrow().hill_radius.total_cmp(&b.borrow().hill_radius))
+      .cloned()
+  }
+
+  pub fn apply_force_fields(&mut self, force_fields: &[Box<dyn ForceField>]) {
+    if self.frozen {
+      return;
+    }
+    if let ShipState::Landed(..) = self.state {
+      return;
+    }
+    for ff in force_fields {
+      self.mov.acc += ff.acceleration(self.mov.pos, self.mov.vel, self.mov.mass);
+    }
+  }
+}
+
+fn draw_dashed_line(a: Vec2, b: Vec2, dash: f32, color: Color) {
+  let dashes = ((b - a).length() / dash) as usize;
+  for i in (0..dashes).step_by(2) {
+    let (from, to) = (a.lerp(b, i as f32 / dashes as f32), a.lerp(b, (i + 1) as f32 / dashes as f32));
+    draw_line(from.x, from.y, to.x, to.y, 1., color);
+  }
+}
+
+fn draw_ship_triangle(pos: Vec2, rot: f32, focus: Vec2, scale: f32, color: Color) {
+  let v = vec2((SHIP_SIZE / scale).max(3.), 0.);
+  let act_pos = (pos - focus) / scale;
+  let (v1, v2, v3) = (
+    act_pos + rotate_vec2_by_rad(&v, rot),
+    act_pos + rotate_vec2_by_rad(&v, rot + 135_f32.to_radians()),
+    act_pos + rotate_vec2_by_rad(&v, rot - 135_f32.to_radians()),
+  );
+  draw_triangle_lines(v1, v2, v3, 2., color);
 }
 
 impl GameObject for Ship {
-  fn update(&mut self, dt: f32) {
+  fn update(&mut self, integrator: IntegratorKind, dt: f32) {
+    if self.frozen {
+      // held in place, so there is nothing to draw between
+      (self.mov.prev_pos, self.mov.prev_rot) = (self.mov.pos, self.mov.rot);
+      return;
+    }
     // self.mov.vel += rotate_vec2_by_rad(&vec2(1., 0.), self.mov.rot) * self.burn * dt / self.mov.mass;
-    self.mov.update(dt);
+    self.mov.update(integrator, dt);
+  }
+
+  fn complete_step(&mut self, integrator: IntegratorKind, dt: f32) {
+    if self.frozen {
+      return;
+    }
+    self.mov.complete_step(integrator, dt);
+  }
+
+  fn is_alive(&self) -> bool {
+    !matches!(self.state, ShipState::Destroyed)
   }
 
-  fn draw(&self, focus: Vec2, scale: f32) {
-    let v = vec2((SHIP_SIZE / scale).max(3.), 0.);
-    let act_pos = (self.mov.pos - focus) / scale;
+  fn draw(&self, focus: Vec2, scale: f32, detail: DetailLevel, _params: &SimParams, alpha: f32) {
+    let act_pos = (self.mov.render_pos(alpha) - focus) / scale;
     let vel = self.mov.vel / scale;
-    let (v1, v2, v3) = (
-      act_pos + rotate_vec2_by_rad(&v, self.mov.rot),
-      act_pos + rotate_vec2_by_rad(&v, self.mov.rot + 135_f32.to_radians()),
-      act_pos + rotate_vec2_by_rad(&v, self.mov.rot - 135_f32.to_radians()),
-    );
-    draw_triangle_lines(v1, v2, v3, 2., WHITE);
+    draw_ship_triangle(self.mov.render_pos(alpha), self.mov.render_rot(alpha), focus, scale, self.color);
+    if !detail.shows_labels() {
+      return;
+    }
     draw_line(
       act_pos.x,
       act_pos.y,
       act_pos.x + vel.x,
       act_pos.y + vel.y,
-      2., WHITE
+      2., self.color
     );
     draw_text(
       &format!("|v|: {:.2}, v: [{:.2}][{:.2}]", self.mov.vel.length(), self.mov.vel.x, self.mov.vel.y),
       act_pos.x,
       act_pos.y - SHIP_SIZE - INFO_FONT_SIZE + 4.,
-      INFO_FONT_SIZE, WHITE
+      INFO_FONT_SIZE, self.color
     );
     draw_text(
-      &format!("{:?}, fuel: {:.2}", self.state, self.fuel),
+      &format!("{}: {:?}{}, fuel: {:.2}", self.name, self.state, if self.frozen { " (frozen)" } else { "" }, self.fuel),
       act_pos.x,
       act_pos.y - SHIP_SIZE - 2. * INFO_FONT_SIZE + 4.,
-      INFO_FONT_SIZE, WHITE
+      INFO_FONT_SIZE, self.color
     );
   }
 }
 
-fn simulate_hill_radius(ships: &[ShipReference], iterations: usize, dt: f32) -> Vec<TrialElement> {
+// debug shortcut: a circular orbit around the body, keeping the ship's bearing from it
+#[cfg(debug_assertions)]
+fn teleport_to_orbit(ship: &mut Ship, cb: &CelestialBody, radius: f32, config: &SimConfig) {
+  let bearing = ship.mov.pos - cb.mov.pos;
+  let (p, v) = get_initial_position_and_velocity(cb.mov.mass, radius, bearing.y.atan2(bearing.x).to_degrees(), config);
+  ship.mov.pos = cb.mov.pos + p;
+  ship.mov.vel = cb.mov.vel + v;
+  ship.last_vel = ship.mov.vel;
+  ship.mov.rot_vel = 0.;
+  ship.state = ShipState::InSpace;
+  ship.fuel = ship.max_fuel;
+}
+
+fn spawn_ship_near(cb: &CelestialBodyReference, index: usize, params: &SimParams, rng: &mut SeededRng) -> Ship {
+  let cb = cb.borrow();
+  let (periapsis, apoapsis) = params.ship.spawn_orbit;
+  let (p, v) = get_elliptical_position_and_velocity(cb.mov.mass, cb.radius * periapsis, cb.radius * apoapsis.max(periapsis), get_random_angle(rng), &params.config);
+  Ship::new(cb.mov.pos + p, cb.mov.vel + v, &params.ship, format!("Ship {}", index + 1), SHIP_PALETTE[index % SHIP_PALETTE.len()])
+}
+
+// distance and compass bearing in degrees, 0 along +x and growing towards +y
+fn distance_and_bearing(from: Vec2, to: Vec2) -> (f32, f32) {
+  let d = to - from;
+  (d.length(), d.y.atan2(d.x).to_degrees().rem_euclid(360.))
+}
+
+fn draw_arrow(from: Vec2, direction: Vec2, length: f32, color: Color) {
+  let tip = from + direction * length;
+  draw_line(from.x, from.y, tip.x, tip.y, 2., color);
+  for side in [135_f32, -135.] {
+    let barb = tip + rotate_vec2_by_rad(&direction, side.to_radians()) * length / 4.;
+    draw_line(tip.x, tip.y, barb.x, barb.y, 2., color);
+  }
+}
+
+fn relative_velocity(a: &Movable, b: &Movable) -> Vec2 {
+  a.vel - b.vel
+}
+
+fn closing_rate(ship: &Ship, body: &CelestialBody) -> f32 {
+  let rel_pos = ship.mov.pos - body.mov.pos;
+  let distance = rel_pos.length();
+  if distance == 0. {
+    return 0.;
+  }
+  rel_pos.dot(relative_velocity(&ship.mov, &body.mov)) / distance
+}
+
+fn orbital_parent(body: &CelestialBodyReference, celestial_bodies: &[CelestialBodyReference]) -> Option<CelestialBodyReference> {
+  let b = body.borrow();
+  celestial_bodies.iter()
+    .filter(|cb| !Rc::ptr_eq(cb, body))
+    .filter(|cb| {
+      let cb = cb.borrow();
+      cb.mov.mass > b.mov.mass && cb.pos_in_hill_radius(&b.mov.pos)
+    })
+    .min_by(|a, b| a.borrow().hill_radius.total_cmp(&b.borrow().hill_radius))
+    .cloned()
+}
+
+// circular orbit velocity at the current distance, keeping the direction of travel
+fn circularize_body(body: &mut CelestialBody, parent: &CelestialBody, g: f32) {
+  let rel_pos = body.mov.pos - parent.mov.pos;
+  let rel_vel = body.mov.vel - parent.mov.vel;
+  let r = rel_pos.length();
+  if r == 0. {
+    return;
+  }
+  let direction = if rel_pos.perp_dot(rel_vel) >= 0. { rel_pos.perp() } else { -rel_pos.perp() };
+  body.mov.vel = parent.mov.vel + direction / r * (g * parent.mov.mass / r).sqrt();
+}
+
+fn future_position(body: &CelestialBodyReference, celestial_bodies: &[CelestialBodyReference], t: f32) -> Vec2 {
+  let b = body.borrow();
+  match orbital_parent(body, celestial_bodies) {
+    Some(parent) => {
+      let (parent_pos, parent_vel) = (parent.borrow().mov.pos, parent.borrow().mov.vel);
+      future_position(&parent, celestial_bodies, t) + position_on_rail(b.mov.pos - parent_pos, b.mov.vel - parent_vel, t)
+    },
+    None => b.mov.pos + b.mov.vel * t,
+  }
+}
+
+fn plan_transfer(ship: &Ship, target: &CelestialBody, star: &CelestialBody, tof: f32, g: f32) -> Option<(Vec2, Vec<Vec2>)> {
+  let mu = star.mov.mass * g;
+  let r1 = ship.mov.pos - star.mov.pos;
+  let r2 = position_on_rail(target.mov.pos - star.mov.pos, target.mov.vel - star.mov.vel, tof);
+  let v1 = lambert_2d(r1, r2, tof, mu)?;
+  let arc = transfer_arc(r1, v1, tof, mu, TRANSFER_ARC_SAMPLES).iter().map(|p| *p + star.mov.pos).collect();
+  Some((v1 - (ship.mov.vel - star.mov.vel), arc))
+}
+
+fn crossed_orbits(ship: &Ship, star: &CelestialBody, celestial_bodies: &[CelestialBodyReference], g: f32) -> Vec<String> {
+  let elements = osculating_elements(ship.mov.pos - star.mov.pos, ship.mov.vel - star.mov.vel, star.mov.mass * g);
+  let apoapsis = elements.apoapsis.unwrap_or(f32::INFINITY);
+  celestial_bodies.iter()
+    .map(|cb| cb.borrow())
+    .filter(|cb| matches!(cb.cb_type, CelestialBodyType::Planet))
+    .filter(|cb| orbits_can_intersect(elements.periapsis, apoapsis, (cb.mov.pos - star.mov.pos).length(), cb.hill_radius))
+    .map(|cb| cb.name.clone())
+    .collect()
+}
+
+#[derive(Default)]
+struct Prediction {
+  // one per body and ship, each in time order
+  trails: Vec<Vec<TrialElement>>,
+  touchdowns: Vec<Movable>,
+  ship_paths: Vec<Vec<Vec2>>,
+  closest_approaches: Vec<Option<CloseApproach>>,
+  target_approaches: Vec<Option<TargetApproach>>,
+  // simulation time the prediction started from
+  made_at: f64,
+}
+
+// nearest the predicted path gets to a body surface, zero clearance on a touchdown
+struct CloseApproach {
+  body: String,
+  clearance: f32,
+  speed: f32,
+  time: f32,
+}
+
+// nearest the predicted ship gets to the target's centre, or where it comes down first
+struct TargetApproach {
+  target: String,
+  distance: f32,
+  time: f32,
+  impact: Option<String>,
+}
+
+// the path is only sampled every step, so between samples it may cut up to step * speed
+// closer to the surface than the samples show
+fn prediction_confidence(min_clearance: f32, step: f32, speed: f32) -> f32 {
+  let uncertainty = step * speed;
+  if uncertainty <= 0. {
+    return 1.;
+  }
+  (min_clearance / uncertainty).clamp(0., 1.)
+}
+
+// Catmull-Rom through the points in order, TRAIL_SUBDIVISIONS segments between each pair;
+// the end points repeat as their own neighbours
+fn trail_polyline(points: &[Vec2]) -> Vec<Vec2> {
+  if points.len() < 3 {
+    return points.to_vec();
+  }
+  let mut line = vec![];
+  for i in 0..points.len() - 1 {
+    let (p0, p1, p2, p3) = (points[i.saturating_sub(1)], points[i], points[i + 1], points[(i + 2).min(points.len() - 1)]);
+    for k in 0..TRAIL_SUBDIVISIONS {
+      let t = k as f32 / TRAIL_SUBDIVISIONS as f32;
+      let (t2, t3) = (t * t, t * t * t);
+      line.push(0.5 * (2. * p1 + (p2 - p0) * t + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2 + (3. * p1 - p0 - 3. * p2 + p3) * t3));
+    }
+  }
+  line.push(points[points.len() - 1]);
+  line
+}
+
+// consecutive points joined by lines, each segment faded by the age of its older end
+fn draw_trail(trail: &[TrialElement], focus: Vec2, scale: f32, smooth: bool) {
+  let points: Vec<Vec2> = trail.iter().map(|(p, _, _)| *p).collect();
+  let (line, per_point) = if smooth { (trail_polyline(&points), TRAIL_SUBDIVISIONS) } else { (points, 1) };
+  for (i, w) in line.windows(2).enumerate() {
+    let (_, color, timer) = &trail[(i / per_point).min(trail.len() - 1)];
+    let color = Color::new(color.r, color.g, color.b, color.a * (1. - timer.progress()));
+    let (a, b) = ((w[0] - focus) / scale, (w[1] - focus) / scale);
+    draw_line(a.x, a.y, b.x, b.y, 1., color);
+  }
+}
+
+// simulated seconds per real second: this frame ran steps steps of step_dt
+fn effective_sim_rate(steps: usize, step_dt: f32, frame_time: f32) -> f32 {
+  if frame_time <= 0. {
+    return 0.;
+  }
+  steps as f32 * step_dt / frame_time
+}
+
+// one step of a ship and the bodies around it, integrated the way Simulation::step does
+#[allow(clippy::too_many_arguments)]
+fn step_ship_with_bodies(celestial_bodies: &[CelestialBodyReference], s: &mut Ship, g: f32, exponent: f32, softening: f32, scales: &[PairScale], integrator: IntegratorKind, dt: f32) {
+  apply_gravity_to_celestial_bodies(celestial_bodies, g, exponent, softening, scales);
+  s.apply_gravity(celestial_bodies, g, exponent, softening);
+  for cb in celestial_bodies {
+    cb.borrow_mut().update(integrator, dt);
+  }
+  s.update(integrator, dt);
+  for _ in 1..integrator.stages() {
+    apply_gravity_to_celestial_bodies(celestial_bodies, g, exponent, softening, scales);
+    s.apply_gravity(celestial_bodies, g, exponent, softening);
+    for cb in celestial_bodies {
+      cb.borrow_mut().complete_step(integrator, dt);
+    }
+    s.complete_step(integrator, dt);
+  }
+}
+
+// the bodies whose hill spheres hold the ship, plus the nearest major body it is closing in on
+// from outside, so a coming encounter bends the prediction before the ship gets there
+fn prediction_bodies(ship: &Ship, major_celestial_bodies: &[CelestialBodyReference]) -> Vec<CelestialBodyReference> {
+  let mut celestial_bodies = ship.in_hill_radius_of.clone();
+  let soi_gap = |cb: &CelestialBodyReference| ship.mov.pos.distance(cb.borrow().mov.pos) - cb.borrow().soi_radius();
+  let approaching = major_celestial_bodies.iter()
+    .filter(|cb| !celestial_bodies.iter().any(|b| Rc::ptr_eq(b, cb)))
+    .filter(|cb| closing_rate(ship, &cb.borrow()) < 0.)
+    .min_by(|a, b| soi_gap(a).total_cmp(&soi_gap(b)))
+    .cloned();
+  celestial_bodies.extend(approaching);
+  celestial_bodies
+}
+
+#[allow(clippy::too_many_arguments)]
+fn simulate_hill_radius(ships: &[ShipReference], major_celestial_bodies: &[CelestialBodyReference], target: Option<&CelestialBodyReference>, iterations: usize, g: f32, exponent: f32, softening: f32, scales: &[PairScale], integrator: IntegratorKind, dt: f32) -> Prediction {
   let _z = ZoneGuard::new("simulate_hill");
-  let mut simulated_trail = vec![];
+  let mut simulated_trails: Vec<Vec<TrialElement>> = vec![];
+  let mut touchdowns = vec![];
+  let mut ship_paths = vec![];
+  let mut closest_approaches = vec![];
+  let mut target_approaches = vec![];
   'ships: for s in ships {
-    let celestial_bodies: Vec<CelestialBodyReference> = s.borrow().in_hill_radius_of.clone();
+    let mut celestial_bodies = prediction_bodies(&s.borrow(), major_celestial_bodies);
+    // the target has to move along with the ship even when it does not pull on it much
+    if let Some(target) = target.filter(|t| !celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, t))) {
+      celestial_bodies.push(target.clone());
+    }
     let mut s = s.borrow_mut();
+    // predict the motion the ship would have once unfrozen
+    let frozen = replace(&mut s.frozen, false);
+    ship_paths.push(vec![s.mov.pos]);
+    let path = ship_paths.last_mut().unwrap();
+    closest_approaches.push(None);
+    let closest = closest_approaches.last_mut().unwrap();
+    target_approaches.push(None);
+    let target_approach = target_approaches.last_mut().unwrap();
+    // the bodies' trails, then the ship's
+    let first_trail = simulated_trails.len();
+    simulated_trails.extend((0..=celestial_bodies.len()).map(|_| vec![]));
+    let ship_trail = simulated_trails.len() - 1;
+    simulated_trails[ship_trail].push((s.mov.pos, YELLOW, Timer::new(10.)));
 
     s.save();
     for cb in &celestial_bodies {
@@ -381,32 +1151,51 @@ fn simulate_hill_radius(ships: &[ShipReference], iterations: usize, dt: f32) ->
     }
 
     for i in 0..iterations {
-      apply_gravity_to_celestial_bodies(&celestial_bodies, dt);
-      s.apply_gravity(&celestial_bodies, dt);
-
-      for cb in &celestial_bodies {
-        cb.borrow_mut().update(dt);
+      step_ship_with_bodies(&celestial_bodies, &mut s, g, exponent, softening, scales, integrator, dt);
+      for (j, cb) in celestial_bodies.iter().enumerate() {
         if i % 5 == 0 || i == iterations - 1 {
-          simulated_trail.push(((cb.borrow().mov.pos), cb.borrow().color, Timer::new(10.)));
+          simulated_trails[first_trail + j].push(((cb.borrow().mov.pos), cb.borrow().color, Timer::new(10.)));
         }
       }
-      s.update(dt);
+      if (i + 1) % PREDICTION_SAMPLE_EVERY == 0 {
+        path.push(s.mov.pos);
+      }
 
       let state = s.state.clone();
+      if let (ShipState::InSpace, Some(target)) = (&state, target) {
+        let distance = s.mov.pos.distance(target.borrow().mov.pos);
+        if target_approach.as_ref().is_none_or(|a: &TargetApproach| distance < a.distance) {
+          *target_approach = Some(TargetApproach { target: target.borrow().name.clone(), distance, time: (i + 1) as f32 * dt, impact: None });
+        }
+      }
       if let ShipState::InSpace = state {
         for cb in &celestial_bodies {
-          if s.check_collision(Vec2::ZERO, &cb.borrow(), PHYSICS_STEP) {
-            simulated_trail.push(((s.mov.pos), ORANGE, Timer::new(10.)));
-            s.state = ShipState::Landed(cb.clone(), Vec2::ZERO);
+          let b = cb.borrow();
+          let clearance = (s.mov.pos.distance(b.mov.pos) - b.radius - s.collision_radius).max(0.);
+          if closest.as_ref().is_none_or(|c: &CloseApproach| clearance < c.clearance) {
+            *closest = Some(CloseApproach { body: b.name.clone(), clearance, speed: (s.mov.vel - b.mov.vel).length(), time: (i + 1) as f32 * dt });
+          }
+        }
+        for cb in &celestial_bodies {
+          if s.check_collision(Vec2::ZERO, &cb.borrow(), dt) {
+            simulated_trails[ship_trail].push(((s.mov.pos), ORANGE, Timer::new(10.)));
+            touchdowns.push(s.mov.clone());
+            *closest = Some(CloseApproach { body: cb.borrow().name.clone(), clearance: 0., speed: (s.mov.vel - cb.borrow().mov.vel).length(), time: (i + 1) as f32 * dt });
+            if let Some(target) = target {
+              *target_approach = Some(TargetApproach { target: target.borrow().name.clone(), distance: 0., time: (i + 1) as f32 * dt, impact: Some(cb.borrow().name.clone()) });
+            }
+            let offset = s.surface_offset(&cb.borrow());
+            s.state = ShipState::Landed(cb.clone(), Vec2::ZERO, offset);
             for cb in &celestial_bodies {
               cb.borrow_mut().mov.load();
             }
             s.load();
+            s.frozen = frozen;
             continue 'ships;
           }
         }
         if i % 5 == 0 || i == iterations - 1 {
-          simulated_trail.push(((s.mov.pos), YELLOW, Timer::new(10.)));
+          simulated_trails[ship_trail].push(((s.mov.pos), YELLOW, Timer::new(10.)));
         }
       }
     }
@@ -415,66 +1204,125 @@ fn simulate_hill_radius(ships: &[ShipReference], iterations: usize, dt: f32) ->
       cb.borrow_mut().mov.load();
     }
     s.load();
+    s.frozen = frozen;
+  }
+
+  Prediction {
+    trails: simulated_trails,
+    touchdowns,
+    ship_paths,
+    closest_approaches,
+    target_approaches,
+    made_at: 0.,
+  }
+}
+
+// ship positions at evenly spaced times over the horizon, integrated with step dt; the path
+// ends early at the first touchdown
+#[allow(clippy::too_many_arguments)]
+fn predict_ship_path(ship: &ShipReference, celestial_bodies: &[CelestialBodyReference], horizon: f32, samples: usize, g: f32, exponent: f32, softening: f32, scales: &[PairScale], integrator: IntegratorKind, dt: f32) -> Vec<Vec2> {
+  let mut s = ship.borrow_mut();
+  let frozen = replace(&mut s.frozen, false);
+  s.save();
+  for cb in celestial_bodies {
+    cb.borrow_mut().mov.save();
+  }
+
+  let mut path = vec![];
+  let mut t = 0.;
+  'steps: while path.len() < samples {
+    step_ship_with_bodies(celestial_bodies, &mut s, g, exponent, softening, scales, integrator, dt);
+    t += dt;
+    for cb in celestial_bodies {
+      if s.check_collision(Vec2::ZERO, &cb.borrow(), dt) {
+        break 'steps;
+      }
+    }
+    while path.len() < samples && t >= horizon * (path.len() + 1) as f32 / samples as f32 {
+      path.push(s.mov.pos);
+    }
+  }
+
+  for cb in celestial_bodies {
+    cb.borrow_mut().mov.load();
+  }
+  s.load();
+  s.frozen = frozen;
+  path
+}
+
+// reruns the prediction with steps spread around base_step and bounds the runs on either side
+// of their mean path; the band is only as long as the shortest run
+#[allow(clippy::too_many_arguments)]
+fn prediction_envelope(ship: &ShipReference, major_celestial_bodies: &[CelestialBodyReference], runs: usize, base_step: f32, iterations: usize, g: f32, exponent: f32, softening: f32, scales: &[PairScale], integrator: IntegratorKind) -> (Vec<Vec2>, Vec<Vec2>) {
+  let horizon = base_step * iterations as f32;
+  let celestial_bodies = prediction_bodies(&ship.borrow(), major_celestial_bodies);
+  let paths: Vec<Vec<Vec2>> = (0..runs)
+    .map(|k| {
+      let spread = if runs > 1 { k as f32 / (runs - 1) as f32 - 0.5 } else { 0. };
+      predict_ship_path(ship, &celestial_bodies, horizon, ENVELOPE_SAMPLES, g, exponent, softening, scales, integrator, base_step * (1. + ENVELOPE_STEP_SPREAD * spread))
+    })
+    .collect();
+  let len = paths.iter().map(|p| p.len()).min().unwrap_or(0);
+  let mean: Vec<Vec2> = (0..len).map(|i| paths.iter().fold(Vec2::ZERO, |sum, p| sum + p[i]) / runs as f32).collect();
+
+  let (mut inner, mut outer) = (vec![], vec![]);
+  for i in 0..len {
+    let tangent = mean[(i + 1).min(len - 1)] - mean[i.saturating_sub(1)];
+    let normal = tangent.perp().normalize_or_zero();
+    let offsets = paths.iter().map(|p| (p[i] - mean[i]).dot(normal));
+    let (low, high) = offsets.fold((0_f32, 0_f32), |(low, high), d| (low.min(d), high.max(d)));
+    inner.push(mean[i] + normal * low);
+    outer.push(mean[i] + normal * high);
+  }
+  (inner, outer)
+}
+
+fn draw_world(sim: &Simulation, focus: Vec2, scale: f32, detail: DetailLevel, params: &SimParams, alpha: f32) {
+  for go in &sim.game_objects {
+    go.borrow().draw(focus, scale, detail, params, alpha);
+  }
+  {
+    let active = sim.ship.borrow();
+    let p = (active.mov.render_pos(alpha) - focus) / scale;
+    draw_circle_lines(p.x, p.y, (SHIP_SIZE / scale).max(3.) * 2., 1., Color::new(active.color.r, active.color.g, active.color.b, 0.6));
+  }
+  let star = sim.cb_parent.borrow();
+  for cb in &sim.major_celestial_bodies {
+    let cb = cb.borrow();
+    if let CelestialBodyType::Planet | CelestialBodyType::Moon = cb.cb_type {
+      draw_terminator(&cb, &star, params.config.au.powi(2), focus, scale, &params.min_display_radius);
+    }
+  }
+}
 
+// what check_collision tests: each body's radius grown by the ship's, and the ship's
+// path over the next step
+#[cfg(debug_assertions)]
+fn draw_collision_debug(sim: &Simulation, focus: Vec2, scale: f32, dt: f32) {
+  let ship = sim.ship.borrow();
+  let half_screen = vec2(screen_width(), screen_height()) / 2.;
+  for cb in &sim.all_celestial_bodies {
+    let cb = cb.borrow();
+    let p = (cb.mov.pos - focus) / scale;
+    let r = (cb.radius + ship.collision_radius) / scale;
+    if p.x.abs() - r > half_screen.x || p.y.abs() - r > half_screen.y {
+      continue;
+    }
+    draw_circle_lines(p.x, p.y, r, 1., RED);
   }
+  let from = (ship.mov.pos - focus) / scale;
+  let to = (ship.mov.pos + ship.mov.vel * dt - focus) / scale;
+  draw_line(from.x, from.y, to.x, to.y, 2., RED);
+  draw_circle_lines(to.x, to.y, (ship.collision_radius / scale).max(1.), 1., RED);
+}
 
-  simulated_trail
-}
-
-// fn simulate(ships: &[ShipReference], celestial_bodies: &[CelestialBodyReference], iterations: usize, dt: f32) -> Vec<TrialElement> {
-//   let _z = ZoneGuard::new("simulate");
-//   for cb in celestial_bodies {
-//     cb.borrow_mut().mov.save();
-//   }
-//   for s in ships {
-//     s.borrow_mut().save();
-//   }
-//   let mut simulated_trail = vec![];
-
-//   for i in 0..iterations {
-//     apply_gravity_to_celestial_bodies(celestial_bodies, dt);
-//     for s in ships {
-//       s.borrow_mut().apply_gravity(&celestial_bodies, dt);
-//     }
-
-//     for cb in celestial_bodies {
-//       cb.borrow_mut().update(dt);
-//       match cb.borrow().cb_type {
-//         CelestialBodyType::Asteroid(_) => {},
-//         _ => {
-//           if i % 5 == 0 || i == iterations - 1 {
-//             simulated_trail.push(((cb.borrow().mov.pos), cb.borrow().color, Timer::new(10.)));
-//           }
-//         }
-//       }
-//     }
-//     'ships: for s in ships {
-//       s.borrow_mut().update(dt);
-//       let state = s.borrow().state.clone();
-//       if let ShipState::InSpace = state {
-//         for cb in celestial_bodies {
-//           if s.borrow().check_collision(Vec2::ZERO, &cb.borrow(), PHYSICS_STEP) {
-//             simulated_trail.push(((s.borrow().mov.pos), ORANGE, Timer::new(10.)));
-//             s.borrow_mut().state = ShipState::Landed(cb.clone(), Vec2::ZERO);
-//             continue 'ships;
-//           }
-//         }
-//         if i % 5 == 0 || i == iterations - 1 {
-//           simulated_trail.push(((s.borrow().mov.pos), YELLOW, Timer::new(10.)));
-//         }
-//       }
-//     }
-//   }
-
-//   for cb in celestial_bodies {
-//     cb.borrow_mut().mov.load();
-//   }
-//   for s in ships {
-//     s.borrow_mut().load();
-//   }
-
-//   simulated_trail
-// }
+fn split_camera(half: usize) -> Camera2D {
+  let (w, h) = (screen_width(), screen_height());
+  let mut camera = Camera2D::from_display_rect(Rect::new(-w / 4., -h / 2., w / 2., h));
+  camera.viewport = Some(((half as f32 * w / 2.) as i32, 0, (w / 2.) as i32, h as i32));
+  camera
+}
 
 fn window_conf() -> Conf {
   Conf {
@@ -486,6 +1334,12 @@ fn window_conf() -> Conf {
   }
 }
 
+// exponential smoothing, frame rate independent; snaps once within a negligible distance
+fn ease_toward(current: f32, target: f32, rate: f32, dt: f32) -> f32 {
+  let eased = current + (target - current) * (1. - (-rate * dt).exp());
+  if (target - eased).abs() <= target.abs() * 1e-3 { target } else { eased }
+}
+
 fn get_scale_delta(scale: f32) -> f32 {
   if scale >= 200. {
     return 100.;
@@ -499,63 +1353,148 @@ fn get_scale_delta(scale: f32) -> f32 {
   if scale >= 10. {
     return 2.;
   }
-  return 0.5;
+  0.5
 }
 
-fn get_random_angle() -> f32 {
-  rand::gen_range(-180., 180.)
+// rotation of the view that keeps the axis from the body to the moon fixed on screen
+fn locked_frame_angle(body: &CelestialBody, moon: &CelestialBody) -> f32 {
+  let axis = moon.mov.pos - body.mov.pos;
+  axis.y.atan2(axis.x)
 }
 
-fn initialize(seed: u64) -> (CelestialBodyReference, Vec<CelestialBodyReference>, Vec<CelestialBodyReference>, Vec<CelestialBodyReference>, Vec<ShipReference>, ShipReference, Vec<GameObjectReference>) {
-  srand(seed);
+// the keys that turn the ship left and right, swapped when turning is inverted
+fn turn_keys(invert: bool) -> (KeyCode, KeyCode) {
+  if invert { (KeyCode::D, KeyCode::A) } else { (KeyCode::A, KeyCode::D) }
+}
 
-  let sol_mass = 30000000.;
-  let sol_mass_ratio = 2000.;
+fn scale_for_body(radius: f32, target_px: f32) -> f32 {
+  (radius / target_px).clamp(MIN_SCALE, MAX_SCALE)
+}
+
+// scale at which an orbit of this semi-major axis spans the viewport with some margin
+fn scale_for_orbit(semi_major_axis: f32, viewport: Vec2) -> f32 {
+  (semi_major_axis * 2. * ORBIT_SCALE_MARGIN / viewport.min_element()).clamp(MIN_SCALE, MAX_SCALE)
+}
+
+fn barycenter(celestial_bodies: &[CelestialBodyReference]) -> Vec2 {
+  let (weighted, mass) = celestial_bodies.iter().fold((Vec2::ZERO, 0.), |(p, m), cb| {
+    let cb = cb.borrow();
+    (p + cb.mov.pos * cb.mov.mass, m + cb.mov.mass)
+  });
+  if mass > 0. { weighted / mass } else { Vec2::ZERO }
+}
+
+// scale at which every body, radius included, fits the viewport around center
+fn fit_scale(celestial_bodies: &[CelestialBodyReference], center: Vec2, viewport: Vec2) -> f32 {
+  let extent = celestial_bodies.iter()
+    .map(|cb| cb.borrow().mov.pos.distance(center) + cb.borrow().radius)
+    .fold(0., f32::max);
+  (extent * 2. * OVERVIEW_MARGIN / viewport.min_element()).clamp(MIN_SCALE, MAX_SCALE)
+}
+
+fn log_event(event_log: &mut Vec<String>, entry: String) {
+  event_log.push(entry);
+  if event_log.len() > EVENT_LOG_SIZE {
+    event_log.remove(0);
+  }
+}
+
+fn get_random_angle(rng: &mut SeededRng) -> f32 {
+  rng.gen_range(-180., 180.)
+}
+
+// a star with body_count - 1 asteroids packed on close rings; every asteroid gets its own
+// angular cell on its own ring, so no two start at the same position
+fn stress_world(seed: u64, body_count: usize, params: &SimParams) -> World {
+  let mut rng = SeededRng::new(seed);
 
   let sol = wrap_object(
-    CelestialBody::new(
-      vec2(screen_width() / 2., screen_height() / 2.),
-      sol_mass,
-      7000.,
-      CelestialBodyType::Star,
-      ORANGE,
-      "Praxidike".to_owned()
-    )
+    CelestialBody::new(system_center(), 30000000., 7000., CelestialBodyType::Star, ORANGE, "Stress".to_owned(), params)
   );
-  let planet0 = wrap_object(
-    CelestialBody::from_parent(
+  let mut all_celestial_bodies = vec![sol.clone()];
+  let mut minor_celestial_bodies = vec![];
+  let cell_angle = 360. / STRESS_BODIES_PER_RING as f32;
+  for i in 0..body_count.saturating_sub(1) {
+    let ring = (i / STRESS_BODIES_PER_RING) as f32;
+    let distance = params.config.au * 0.5 + ring * STRESS_RING_SPACING + rng.gen_range(-0.25, 0.25) * STRESS_RING_SPACING;
+    let angle = (i % STRESS_BODIES_PER_RING) as f32 * cell_angle + rng.gen_range(-0.4, 0.4) * cell_angle;
+    let asteroid = wrap_object(
+      CelestialBody::from_parent(&sol.borrow(), distance, angle, 100., 20., CelestialBodyType::Asteroid, GRAY, format!("Stress {}", i), params)
+    );
+    minor_celestial_bodies.push(asteroid.clone());
+    all_celestial_bodies.push(asteroid);
+  }
+
+  let ship = wrap_object(spawn_ship_near(&sol, 0, params, &mut rng));
+  let mut game_objects: Vec<GameObjectReference> = vec![sol.clone()];
+  for a in &minor_celestial_bodies {
+    game_objects.push(a.clone());
+  }
+  game_objects.push(ship.clone());
+
+  (sol.clone(), all_celestial_bodies, vec![sol], minor_celestial_bodies, vec![ship.clone()], ship, game_objects, 0)
+}
+
+fn initialize(seed: u64, params: &SimParams) -> World {
+  let mut rng = SeededRng::new(seed);
+  let mut names = NamesGen::new();
+  let mut designation = 0;
+  let mut next_name = || names.take().unwrap_or_else(|| {
+    designation += 1;
+    format!("S/{}", designation)
+  });
+
+  let sol_mass = 30000000.;
+  let sol_mass_ratio = 2000.;
+
+  let sol = wrap_object(
+    CelestialBody::new(
+      system_center(),
+      sol_mass,
+      7000.,
+      CelestialBodyType::Star,
+      ORANGE,
+      next_name(),
+      params,
+    )
+  );
+  let planet0 = wrap_object(
+    CelestialBody::from_parent(
       &sol.borrow(),
-      AU * 0.4,
-      get_random_angle(),
+      params.config.au * 0.4,
+      get_random_angle(&mut rng),
       sol_mass / (sol_mass_ratio / 0.05),
       100.,
       CelestialBodyType::Planet,
       BROWN,
-      "Ananke".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet1 = wrap_object(
     CelestialBody::from_parent(
       &sol.borrow(),
-      AU * 0.7,
-      get_random_angle(),
+      params.config.au * 0.7,
+      get_random_angle(&mut rng),
       sol_mass / (sol_mass_ratio / 0.8),
       210.,
       CelestialBodyType::Planet,
       BEIGE,
-      "Iocaste".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet2 = wrap_object(
     CelestialBody::from_parent(
       &sol.borrow(),
-      AU,
-      get_random_angle(),
+      params.config.au,
+      get_random_angle(&mut rng),
       sol_mass / sol_mass_ratio,
       300.,
       CelestialBodyType::Planet,
       BLUE,
-      "Ganymede".to_owned(),
+      next_name(),
+      params,
     )
   );
 
@@ -563,96 +1502,108 @@ fn initialize(seed: u64) -> (CelestialBodyReference, Vec<CelestialBodyReference>
     CelestialBody::from_parent(
       &planet2.borrow(),
       planet2.borrow().hill_radius * 0.14,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       900.,
       80.,
       CelestialBodyType::Moon,
       GRAY,
-      "Thebe".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet3 = wrap_object(
     CelestialBody::from_parent(
       &sol.borrow(),
-      AU * 1.5,
-      get_random_angle(),
+      params.config.au * 1.5,
+      get_random_angle(&mut rng),
       sol_mass / (1000. / 0.8),
       200.,
       CelestialBodyType::Planet,
       RED,
-      "Themisto".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet3_0 = wrap_object(
     CelestialBody::from_parent(
       &planet3.borrow(),
       planet3.borrow().hill_radius * 0.09,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       100.,
       60.,
       CelestialBodyType::Moon,
       GRAY,
-      "Kalyke".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet3_1 = wrap_object(
     CelestialBody::from_parent(
       &planet3.borrow(),
       planet3.borrow().hill_radius * 0.15,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       90.,
       50.,
       CelestialBodyType::Moon,
       GRAY,
-      "Mneme".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet4 = wrap_object(
     CelestialBody::from_parent(
       &sol.borrow(),
-      AU * 5.3,
-      get_random_angle(),
+      params.config.au * 5.3,
+      get_random_angle(&mut rng),
       sol_mass / (sol_mass_ratio / 10.),
       3100.,
       CelestialBodyType::Planet,
       BEIGE,
-      "Euanthe".to_owned(),
+      next_name(),
+      params,
     )
   );
+  planet4.borrow_mut().max_landing_speed = params.config.max_landing_speed * 3.;
+  planet1.borrow_mut().atmosphere_height = Some(60.);
+  planet2.borrow_mut().atmosphere_height = Some(40.);
+  planet4.borrow_mut().atmosphere_height = Some(400.);
   let planet4_0 = wrap_object(
     CelestialBody::from_parent(
       &planet4.borrow(),
       planet4.borrow().hill_radius * 0.14,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       90.,
       75.,
       CelestialBodyType::Moon,
       GRAY,
-      "Kale".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet4_1 = wrap_object(
     CelestialBody::from_parent(
       &planet4.borrow(),
       planet4.borrow().hill_radius * 0.23,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       130.,
       90.,
       CelestialBodyType::Moon,
       GRAY,
-      "Eurydome".to_owned(),
+      next_name(),
+      params,
     )
   );
   let planet4_2 = wrap_object(
     CelestialBody::from_parent(
       &planet4.borrow(),
       planet4.borrow().hill_radius * 0.31,
-      get_random_angle(),
+      get_random_angle(&mut rng),
       95.,
       75.,
       CelestialBodyType::Moon,
       GRAY,
-      "Sponde".to_owned(),
+      next_name(),
+      params,
     )
   );
   let mut all_celestial_bodies: Vec<CelestialBodyReference> = vec![
@@ -700,195 +1651,1494 @@ fn initialize(seed: u64) -> (CelestialBodyReference, Vec<CelestialBodyReference>
     planet4_2.clone(),
   ];
 
-  let cb = major_celestial_bodies.choose().unwrap().clone();
-  let (p, v) = get_initial_position_and_velocity(cb.borrow().mov.mass, cb.borrow().radius * 1.5, get_random_angle());
-  let ship = wrap_object(
-    Ship::new(cb.borrow().mov.pos + p, cb.borrow().mov.vel + v, 1000.)
-  );
+  // drawn from the seed's own generator so a seed always spawns near the same body
+  let spawn_body_index = rng.gen_index(major_celestial_bodies.len());
+  let ship = wrap_object(spawn_ship_near(&major_celestial_bodies[spawn_body_index], 0, params, &mut rng));
   game_objects.push(ship.clone());
 
-  let asteroid_belt_distance = AU * 2.7;
-  for angle in 0..360 {
+  let belt = &params.belt;
+  let (inner_radius, outer_radius) = (params.config.au * belt.inner_radius, params.config.au * belt.outer_radius);
+  let sector_angle = 360. / belt.sectors as f32;
+  for sector in 0..belt.sectors {
     let mut last_distance = 0.;
     let mut last_radius = 0.;
-    let asteroid_cnt = rand::gen_range(1, 5);
+    let (fewest, most) = (belt.per_sector.0.min(belt.per_sector.1), belt.per_sector.0.max(belt.per_sector.1));
+    let asteroid_cnt = fewest + rng.gen_index(most - fewest + 1);
     for i in 0..asteroid_cnt {
-      let angle_increment = rand::gen_range(0., 1.);
-      let distance = asteroid_belt_distance + last_distance + last_radius + rand::gen_range(500., 1000.);
-      let radius = 10. + rand::gen_range(10., 40.);
-      let mass = rand::gen_range(50., 100.);
+      let angle_increment = rng.gen_range(0., 1.);
+      let distance = inner_radius + last_distance + last_radius + rng.gen_range(belt.gap.0, belt.gap.1);
+      // drawn the way the fixed belt did, 10 + 10..40 for the default
+      let radius = belt.radius.0 / 2. + rng.gen_range(belt.radius.0 / 2., belt.radius.1 - belt.radius.0 / 2.);
+      let mass = rng.gen_range(belt.mass.0, belt.mass.1);
+      if distance > outer_radius {
+        break;
+      }
 
       let asteroid = wrap_object(
         CelestialBody::from_parent(
           &sol.borrow(),
           distance,
-          angle as f32 + angle_increment,
+          (sector as f32 + angle_increment) * sector_angle,
           mass,
           radius,
           CelestialBodyType::Asteroid,
           GRAY,
-          format!("Ast {:.1}/{}", angle, i),
+          format!("Ast {}/{}", sector, i),
+          params,
         )
       );
 
       minor_celestial_bodies.push(asteroid.clone());
       all_celestial_bodies.push(asteroid.clone());
-      game_objects.push(asteroid.clone());
 
-      last_distance = distance - asteroid_belt_distance;
+      last_distance = distance - inner_radius;
       last_radius = radius;
     }
   }
 
+  // depth is rolled after the belt so the seeded layout stays the same
+  let mut asteroids_by_depth = minor_celestial_bodies.clone();
+  for a in &asteroids_by_depth {
+    a.borrow_mut().depth = rng.gen_range(-1., 1.);
+  }
+  let kept = thin_belt(&minor_celestial_bodies, params.max_asteroids);
+  if kept.len() < minor_celestial_bodies.len() {
+    let kept_ptrs: HashSet<*const RefCell<CelestialBody>> = kept.iter().map(Rc::as_ptr).collect();
+    let is_kept = |cb: &CelestialBodyReference| kept_ptrs.contains(&Rc::as_ptr(cb));
+    all_celestial_bodies.retain(|cb| !matches!(cb.borrow().cb_type, CelestialBodyType::Asteroid) || is_kept(cb));
+    asteroids_by_depth.retain(is_kept);
+    minor_celestial_bodies.retain(is_kept);
+  }
+  asteroids_by_depth.sort_by(|a, b| a.borrow().depth.total_cmp(&b.borrow().depth));
+  for a in asteroids_by_depth {
+    game_objects.push(a);
+  }
+
   let ships: Vec<ShipReference> = vec![ship.clone()];
 
-  (sol, all_celestial_bodies, major_celestial_bodies, minor_celestial_bodies, ships, ship, game_objects)
+  (sol, all_celestial_bodies, major_celestial_bodies, minor_celestial_bodies, ships, ship, game_objects, spawn_body_index)
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
   set_pc_assets_folder("assets");
-  let mut seed = 3;
+  let mut audio_cues = AudioCues::load().await;
+  let mut seed_history = SeedHistory::new(3);
   let mut show_trails = false;
+  let mut smooth_trail = false;
 
-  let (
-    mut cb_parent,
-    mut all_celestial_bodies,
-    mut major_celestial_bodies,
-    mut minor_celestial_bodies,
-    mut ships,
-    mut ship,
-    mut game_objects
-  ) = initialize(seed);
-
-  let mut focus;
+  let mut params = SimParams::default();
+  let mut sim = Simulation::new(seed_history.current(), &params);
+
+  let mut focus = sim.ship.borrow().mov.pos;
   let mut scale = 1.;
+  let mut target_scale = 1.;
+  // target scale of the latest wheel zoom, which eases about the cursor
+  let mut zoom_anchor = None;
   let mut trail_emitter_timer = Timer::new(2.);
-  let mut trail_elements: Vec<TrialElement> = vec![];
+  // the ships' own trails, one per ship that has been flown
+  let mut trail_elements: Vec<(ShipReference, Vec<TrialElement>)> = vec![];
+  let mut sparks: Vec<(Vec2, Timer)> = vec![];
   let mut simulated_trail_timer = Timer::new(0.5);
-  let mut simulated_trail: Vec<TrialElement> = vec![];
-  let mut day_count: u32 = 1;
-  let mut day_timer = Timer::new(DAY_TIME);
+  let mut prediction = Prediction::default();
+  let mut prediction_check: Option<PredictionCheck> = None;
+  let mut event_log: Vec<String> = vec![];
 
+  // time acceleration: simulated seconds per real second
   let mut tick = 1;
-
-  set_camera(&Camera2D::from_display_rect(Rect::new(-screen_width() / 2., -screen_height() / 2., screen_width(), screen_height())));
-
+  let mut stepper = StepAccumulator::new();
+  let mut focus_pause = FocusPause::new();
+  let mut paused = false;
+  let mut target: Option<usize> = None;
+  let mut transfer_days: u32 = 100;
+  let mut lead_days: u32 = 0;
+  let mut planned_burn: Option<PlannedBurn> = None;
+  let mut pending_ships: Vec<Ship> = vec![];
+  let mut locked_frame = false;
+  let mut frame_angle: f32 = 0.;
+  let mut overview: Option<Overview> = None;
+  let mut lock_orbit_scale = false;
+  let mut show_tether = false;
+  let mut focus_target = FocusTarget::Ship;
+  let mut home_body: Option<CelestialBodyReference> = sim.major_celestial_bodies.get(sim.spawn_body_index).cloned();
+  let mut ascent: Option<AscentAutopilot> = None;
+  let mut attitude_hold: Option<AttitudeHold> = None;
+  let mut escape: Option<EscapeAutopilot> = None;
+  let mut docking: Option<LPointAutopilot> = None;
+  let mut integrator_compare: Option<IntegratorComparison> = None;
+  let mut compare_sim: Option<Simulation> = None;
+  let mut throttle = 0.;
+  let mut show_heatmap = false;
+  let mut show_burn_preview = false;
+  let mut show_envelope = false;
+  let mut envelope: (Vec<Vec2>, Vec<Vec2>) = (vec![], vec![]);
+  let mut chase_camera: Option<SmoothCamera> = None;
+  let mut recorder = TrajectoryRecorder::new();
+  let mut replay: Option<Replay> = None;
+  let mut timeline = Timeline::new();
+  let mut event_log_panel = Panel::new(
+    "event_log",
+    Rect::new(5., screen_height() - 10. - EVENT_LOG_SIZE as f32 * INFO_FONT_SIZE, 400., EVENT_LOG_SIZE as f32 * INFO_FONT_SIZE + 6.),
+  );
+  let mut elements_panel = Panel::new("elements", Rect::new(screen_width() - 256., 162., 250., 8. * INFO_FONT_SIZE + 6.));
+  elements_panel.visible = false;
+  let mut minimap_panel = Panel::new("minimap", Rect::new(screen_width() - MINIMAP_SIZE - 10., screen_height() - MINIMAP_SIZE - 30., MINIMAP_SIZE, MINIMAP_SIZE));
+  load_panels(PANELS_PATH, &mut [&mut event_log_panel, &mut elements_panel, &mut minimap_panel]);
+  let mut library = SystemLibrary::open(LIBRARY_PATH);
+  let mut leaderboard = Leaderboard::open(LEADERBOARD_PATH);
+  let mut library_cursor: usize = 0;
+  let mut library_prompt: Option<TextPrompt> = None;
+  let mut autosave = AutoSave::new(AUTOSAVE_DIR, params.autosave_interval, params.autosave_keep);
+  if let Some(path) = newest_autosave(AUTOSAVE_DIR) {
+    log_event(&mut event_log, format!("Found {}, Ctrl+R resumes from it", path.display()));
+  }
+  #[cfg(debug_assertions)]
+  let mut show_collision_debug = false;
+  let mut show_apsis_line = false;
+  let mut show_conservation = false;
+  let mut conservation_baseline = ConservationTotals::measure(&sim.major_celestial_bodies, &params);
+  let mut conservation_law = (params.gravity_exponent, params.softening, params.gravity_scales.clone());
+  let mut orbit_ghosts: Option<OrbitGhosts> = None;
+  let mut detail = DetailLevel::Full;
+  let mut composing = false;
+  // Some(true) while the periapsis handle is dragged, Some(false) for the apoapsis one
+  let mut apsis_drag: Option<bool> = None;
+  let mut pan = Pan::new();
+  let mut edits = EditHistory::new();
+  let mut placed_count: u32 = 0;
+  let mut heatmap: Option<PotentialHeatmap> = None;
+  let mut heatmap_timer = Timer::new(HEATMAP_REFRESH_TIME);
 
   loop {
     // let dt = get_frame_time();
     let dt = PHYSICS_STEP;
+    // rebuilt every frame so a resized window keeps the view centred and the HUD anchored
+    let full_camera = screen_camera(screen_width(), screen_height());
+    set_camera(&full_camera);
+    clear_background(params.clear_color);
+    let hud = HudAnchors::new(screen_width(), screen_height());
+
+    // naming a library entry holds the whole frame, so typed letters do not fire hotkeys
+    let typed = typed_chars();
+    if let Some(prompt) = &mut library_prompt {
+      match prompt.update(&typed, is_key_pressed(KeyCode::Backspace), is_key_released(KeyCode::Enter), is_key_released(KeyCode::Escape)) {
+        PromptOutcome::Editing => {
+          draw_text(&format!("Library name: {}_", prompt.text), hud.left, hud.top + 30., 24., WHITE);
+          draw_text("Enter to save, Esc to cancel", hud.left, hud.top + 60., 24., LIGHTGRAY);
+        },
+        PromptOutcome::Submitted(name) => {
+          match library.add(&name, SystemConfig::new(sim.seed, &params)) {
+            Ok(()) => log_event(&mut event_log, format!("Saved \"{}\" to library", name)),
+            Err(e) => log_event(&mut event_log, format!("Saving library failed: {}", e)),
+          }
+          library_prompt = None;
+        },
+        PromptOutcome::Cancelled => library_prompt = None,
+      }
+      next_frame().await;
+      continue;
+    }
 
-    trail_elements.retain_mut(|(_p, _c, t)| {
-      t.update(dt);
-      !t.is_just_over()
-    });
+    for (_, trail) in &mut trail_elements {
+      trail.retain_mut(|(_p, _c, t)| {
+        t.update(dt);
+        !t.is_just_over()
+      });
+    }
+    trail_elements.retain(|(_, trail)| !trail.is_empty());
 
-    if is_key_released(KeyCode::B) {
-      seed = seed + 1;
-      (
-        cb_parent,
-        all_celestial_bodies,
-        major_celestial_bodies,
-        minor_celestial_bodies,
-        ships,
-        ship,
-        game_objects
-      ) = initialize(seed);
-      simulated_trail = vec![];
+    let ctrl_down = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+    let mut reseed = None;
+    if ctrl_down && is_key_released(KeyCode::B) {
+      params.clear_color = params.next_clear_color();
+    }
+    if ctrl_down && is_key_released(KeyCode::G) {
+      params.star_glow = !params.star_glow;
+    }
+    if !ctrl_down && is_key_released(KeyCode::B) {
+      reseed = Some(seed_history.advance());
+    }
+    if is_key_released(KeyCode::V) {
+      reseed = seed_history.back();
+    }
+    if !ctrl_down && is_key_released(KeyCode::N) {
+      reseed = seed_history.forward();
+    }
+    if is_key_released(KeyCode::End) {
+      library_prompt = Some(TextPrompt::new(format!("Seed {} (day {})", sim.seed, sim.day_count)));
+    }
+    let library_len = library.list().len();
+    if library_len > 0 && is_key_released(KeyCode::PageDown) {
+      library_cursor = (library_cursor + 1) % library_len;
+    }
+    if library_len > 0 && is_key_released(KeyCode::PageUp) {
+      library_cursor = (library_cursor + library_len - 1) % library_len;
+    }
+    if is_key_released(KeyCode::Home) {
+      if let Some(config) = library.list().get(library_cursor).and_then(|name| library.load(name)) {
+        params = config.apply(&params);
+        reseed = Some(seed_history.push(config.seed));
+      }
+    }
+    if is_key_released(KeyCode::Slash) {
+      params.ship = if params.ship == ShipConfig::default() { ShipConfig::hard() } else { ShipConfig::default() };
+      reseed = Some(sim.seed);
+    }
+    if is_key_released(KeyCode::F11) {
+      params.max_asteroids = params.next_max_asteroids();
+      reseed = Some(sim.seed);
+    }
+    let mut next_sim = reseed.map(|seed| Simulation::new(seed, &params));
+    if is_key_released(KeyCode::Apostrophe) {
+      next_sim = Some(stress_scenario(STRESS_BODY_COUNT, &params));
+    }
+    // rewinds the live simulation to the replay cursor, or a fixed step back outside replay
+    let mut rewound = false;
+    if ctrl_down && is_key_released(KeyCode::Y) {
+      let target = match &replay {
+        Some(r) => sim.elapsed_seconds - (recorder.end_time() - r.time) as f64,
+        None => sim.elapsed_seconds - REWIND_STEP,
+      };
+      match timeline.rewind(target, &params) {
+        Some((rewound_params, rewound_sim)) => {
+          params = rewound_params;
+          next_sim = Some(rewound_sim);
+          rewound = true;
+        },
+        None => log_event(&mut event_log, format!("Nothing recorded that far back ({:.0}s)", target)),
+      }
+    }
+    if ctrl_down && is_key_released(KeyCode::R) {
+      let restored = newest_autosave(AUTOSAVE_DIR)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| restore_state(&json, &params));
+      match restored {
+        Some((restored_params, restored_sim)) => {
+          params = restored_params;
+          seed_history.push(restored_sim.seed);
+          next_sim = Some(restored_sim);
+        },
+        None => log_event(&mut event_log, "No readable auto-save to resume from".to_owned()),
+      }
+    }
+    if is_key_released(KeyCode::F5) {
+      match save_state(Path::new(QUICKSAVE_PATH), &sim, &params) {
+        Ok(()) => log_event(&mut event_log, format!("Saved to {}", QUICKSAVE_PATH)),
+        Err(e) => log_event(&mut event_log, format!("Saving failed: {}", e)),
+      }
+    }
+    if is_key_released(KeyCode::F9) {
+      match load_state(Path::new(QUICKSAVE_PATH), &params) {
+        Ok((loaded_params, loaded_sim)) => {
+          params = loaded_params;
+          seed_history.push(loaded_sim.seed);
+          next_sim = Some(loaded_sim);
+        },
+        Err(e) => log_event(&mut event_log, format!("Loading failed: {}", e)),
+      }
+    }
+    if let Some(next) = next_sim {
+      let force_fields_enabled = !sim.force_fields.is_empty();
+      sim = next;
+      // building the new system stalls this frame, which must not read as a focus loss
+      focus_pause.expect_stall();
+      // a rewind keeps its bodies, any other new system drops the overrides on the old ones
+      let ids: HashSet<usize> = sim.all_celestial_bodies.iter().map(|cb| cb.borrow().id).collect();
+      params.gravity_scales.retain(|p| ids.contains(&p.a) && ids.contains(&p.b));
+      if force_fields_enabled {
+        sim.force_fields = default_force_fields(&sim.cb_parent);
+      }
+      prediction = Prediction::default();
+      prediction_check = None;
+      conservation_baseline = ConservationTotals::measure(&sim.major_celestial_bodies, &params);
       trail_elements = vec![];
-      day_count = 0;
-      day_timer = Timer::new(DAY_TIME);
+      sparks = vec![];
+      event_log = vec![];
+      target = None;
+      planned_burn = None;
+      pending_ships.clear();
+      focus_target = FocusTarget::Ship;
+      home_body = sim.major_celestial_bodies.get(sim.spawn_body_index).cloned();
+      ascent = None;
+      escape = None;
+      docking = None;
+      attitude_hold = None;
+      integrator_compare = None;
+      compare_sim = None;
+      heatmap = None;
+      if !rewound {
+        timeline = Timeline::new();
+      }
+      edits = EditHistory::new();
+      recorder = TrajectoryRecorder::new();
+      pan.reset();
+      if orbit_ghosts.is_some() {
+        orbit_ghosts = Some(OrbitGhosts::new());
+      }
+      replay = None;
+      if let Some(camera) = &mut chase_camera {
+        let ship = sim.ship.borrow();
+        *camera = SmoothCamera::new(ship.mov.pos, ship.mov.vel);
+      }
+    }
+    if is_key_released(KeyCode::F6) {
+      compare_sim = match compare_sim {
+        Some(_) => None,
+        None => Some(Simulation::new(sim.seed + 1, &params)),
+      };
+    }
+    if ctrl_down && is_key_released(KeyCode::M) {
+      params.integrator = params.integrator.next();
+    }
+    if !ctrl_down && is_key_released(KeyCode::M) {
+      params.turn_model = params.turn_model.next();
+      for s in &sim.ships {
+        s.borrow_mut().mov.rot_vel = 0.;
+      }
+    }
+    if ctrl_down && is_key_released(KeyCode::C) {
+      focus_target = focus_target.next_body(&sim.major_celestial_bodies);
+      pan.reset();
+    }
+    if let (true, true, Some(t)) = (!ctrl_down, is_key_released(KeyCode::C), target) {
+      let body = &sim.major_celestial_bodies[t];
+      target_scale = scale_for_body(body.borrow().radius, screen_height() / 4.);
+      focus_target = FocusTarget::Body(body.clone());
+    }
+    if !ctrl_down && is_key_released(KeyCode::L) {
+      show_tether = !show_tether;
+    }
+    if !ctrl_down && is_key_released(KeyCode::H) {
+      log_event(&mut event_log, format!("State hash: {:016x}", state_hash(&sim)));
+    }
+    if is_key_released(KeyCode::F1) {
+      params.invert_turn = !params.invert_turn;
+    }
+    if is_key_released(KeyCode::F2) {
+      params.control_sensitivity = params.next_sensitivity();
+    }
+    if ctrl_down && is_key_released(KeyCode::F3) {
+      params.barnes_hut = !params.barnes_hut;
+    }
+    if !ctrl_down && is_key_released(KeyCode::F3) {
+      params.mutual_asteroid_gravity = !params.mutual_asteroid_gravity;
+    }
+    if is_key_released(KeyCode::F4) {
+      params.config.g = -params.config.g;
+    }
+    if is_key_released(KeyCode::F8) {
+      show_heatmap = !show_heatmap;
+      heatmap = None;
+    }
+    if is_key_released(KeyCode::F12) {
+      let path = format!("solsys_{}_day{}.svg", sim.seed, sim.day_count);
+      let viewport = Viewport { focus, scale, size: vec2(screen_width(), screen_height()), labels: detail.shows_labels(), radii: params.min_display_radius };
+      let trails: Vec<Vec<(Vec2, Color)>> = if show_trails && detail.shows_trails() {
+        trail_elements.iter().map(|(_, trail)| trail).chain(&prediction.trails)
+          .map(|trail| trail.iter().map(|(p, c, _)| (*p, *c)).collect())
+          .collect()
+      } else {
+        vec![]
+      };
+      match export_svg(&sim, &trails, &path, &viewport) {
+        Ok(()) => log_event(&mut event_log, format!("Exported {}", path)),
+        Err(e) => log_event(&mut event_log, format!("SVG export failed: {}", e)),
+      }
+    }
+    if is_key_released(KeyCode::KpDecimal) {
+      let report = system_report(&sim, params.config.g, params.day_length, params.config.au);
+      let path = format!("solsys_{}_day{}.txt", sim.seed, sim.day_count);
+      match export_report(&report, &path) {
+        Ok(()) => log_event(&mut event_log, format!("Exported {}", path)),
+        Err(e) => log_event(&mut event_log, format!("Report export failed: {}", e)),
+      }
+    }
+    #[cfg(debug_assertions)]
+    if is_key_released(KeyCode::Backslash) {
+      show_collision_debug = !show_collision_debug;
+    }
+    #[cfg(debug_assertions)]
+    if let (true, Some(t)) = (is_key_released(KeyCode::Kp2), target) {
+      let body = sim.major_celestial_bodies[t].borrow();
+      teleport_to_orbit(&mut sim.ship.borrow_mut(), &body, body.radius * TELEPORT_ORBIT_RADIUS_FACTOR, &params.config);
+      log_event(&mut event_log, format!("Teleported to orbit around {}", body.name));
+    }
+    if is_key_released(KeyCode::Semicolon) {
+      detail = detail.next();
+    }
+    if ctrl_down && is_key_released(KeyCode::Comma) {
+      show_apsis_line = !show_apsis_line;
+    }
+    if !ctrl_down && is_key_released(KeyCode::Comma) {
+      orbit_ghosts = match orbit_ghosts {
+        Some(_) => None,
+        None => Some(OrbitGhosts::new()),
+      };
+    }
+    let mouse = Vec2::from(mouse_position());
+    let (pressed, down) = (is_mouse_button_pressed(MouseButton::Left), is_mouse_button_down(MouseButton::Left));
+    let mut panels_moved = false;
+    let mut grabbed = false;
+    for panel in [&mut event_log_panel, &mut elements_panel, &mut minimap_panel] {
+      // overlapping panels: only the first one under the cursor is picked up
+      panels_moved |= panel.drag(mouse, pressed && !grabbed, down);
+      grabbed |= panel.is_dragged();
+    }
+    if ctrl_down && is_key_released(KeyCode::Q) {
+      let ship = sim.ship.borrow();
+      match ship.dominant_body() {
+        Some(body) => {
+          let b = body.borrow();
+          let elements = osculating_elements(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, params.config.g * b.mov.mass);
+          let report = elements_report(&elements, &b.name, params.day_length);
+          // no clipboard copy: macroquad 0.3 only reaches it through the internal GL context
+          println!("{}", report);
+          log_event(&mut event_log, format!("Printed orbital elements around {} to the console", b.name));
+        },
+        None => log_event(&mut event_log, "No dominant body to report elements against".to_owned()),
+      }
+    }
+    if !ctrl_down && is_key_released(KeyCode::Q) {
+      elements_panel.visible = !elements_panel.visible;
+      panels_moved = true;
+    }
+    if ctrl_down && is_key_released(KeyCode::N) {
+      minimap_panel.visible = !minimap_panel.visible;
+      panels_moved = true;
+    }
+    if panels_moved {
+      if let Err(e) = save_panels(PANELS_PATH, &[&event_log_panel, &elements_panel, &minimap_panel]) {
+        log_event(&mut event_log, format!("Saving panels failed: {}", e));
+      }
+    }
+    if !ctrl_down && is_key_released(KeyCode::Y) {
+      replay = match replay {
+        Some(_) => None,
+        None => Replay::new(&recorder),
+      };
+    }
+    if !ctrl_down && is_key_released(KeyCode::R) {
+      chase_camera = match chase_camera {
+        Some(_) => None,
+        None => {
+          let ship = sim.ship.borrow();
+          Some(SmoothCamera::new(ship.mov.pos, ship.mov.vel))
+        },
+      };
+    }
+    if ctrl_down && is_key_released(KeyCode::E) {
+      show_envelope = !show_envelope;
+      envelope = (vec![], vec![]);
+    }
+    if !ctrl_down && is_key_released(KeyCode::E) {
+      show_burn_preview = !show_burn_preview;
+    }
+    let mut ship_switched = false;
+    if replay.is_none() && is_key_released(KeyCode::Tab) {
+      ship_switched = sim.select_next_ship();
+    }
+    // a branch of the active ship: same state, fuel and landing body, flown independently
+    if is_key_released(KeyCode::KpAdd) {
+      let mut copy = sim.ship.borrow().clone();
+      let index = sim.ships.len() + pending_ships.len();
+      copy.name = format!("{} copy", copy.name);
+      copy.color = SHIP_PALETTE[index % SHIP_PALETTE.len()];
+      pending_ships.push(copy);
+    }
+    if is_key_released(KeyCode::KpSubtract) {
+      params.gravity_every = params.next_gravity_every();
+    }
+    if is_key_released(KeyCode::Kp1) {
+      let result = sim.ship.borrow_mut().load_cargo(CARGO_UNIT);
+      if let Err(e) = result {
+        log_event(&mut event_log, e);
+      }
+    }
+    if is_key_released(KeyCode::Kp3) {
+      let result = sim.ship.borrow_mut().deliver_cargo();
+      match result {
+        Ok(mass) => log_event(&mut event_log, format!("Delivered {:.1} cargo", mass)),
+        Err(e) => log_event(&mut event_log, e),
+      }
+    }
+    if let (true, Some(t)) = (is_key_released(KeyCode::Kp4), target) {
+      let body = sim.major_celestial_bodies[t].clone();
+      if let Some(parent) = orbital_parent(&body, &sim.major_celestial_bodies) {
+        edits.push(Edit::VelocityChanged(body.clone(), body.borrow().mov.vel));
+        circularize_body(&mut body.borrow_mut(), &parent.borrow(), params.config.g);
+        log_event(&mut event_log, format!("Circularized {} around {}", body.borrow().name, parent.borrow().name));
+      }
+    }
+    if is_key_released(KeyCode::KpEqual) {
+      params.physics_threads = params.next_physics_threads();
+    }
+    if is_key_released(KeyCode::Kp9) {
+      params.skid_friction = if params.skid_friction > 0. { 0. } else { SKID_FRICTION };
     }
-    if is_key_released(KeyCode::Space) {
+    if is_key_released(KeyCode::Kp7) {
+      params.despawn_escaped = !params.despawn_escaped;
+    }
+    if ctrl_down && is_key_released(KeyCode::U) {
+      params.merge_bodies = !params.merge_bodies;
+    }
+    if ctrl_down && is_key_released(KeyCode::H) {
+      show_conservation = !show_conservation;
+    }
+    if is_key_released(KeyCode::CapsLock) {
+      params.min_display_radius.asteroid = params.min_display_radius.next_asteroid();
+      log_event(&mut event_log, format!("Asteroid minimum radius {:.0}px", params.min_display_radius.asteroid));
+    }
+    if is_key_released(KeyCode::Kp5) {
+      locked_frame = !locked_frame;
+    }
+    if is_key_released(KeyCode::KpDivide) {
+      params.throttle_response = params.throttle_response.next();
+    }
+    if is_key_released(KeyCode::KpMultiply) {
+      params.tracer_mass_threshold = if params.tracer_mass_threshold > 0. { 0. } else { TRACER_MASS_THRESHOLD };
+    }
+    if is_key_released(KeyCode::F10) {
+      params.gravity_exponent = params.next_gravity_exponent();
+    }
+    if is_key_released(KeyCode::F7) {
+      params.day_length = params.next_day_length();
+    }
+    if !ctrl_down && is_key_released(KeyCode::O) {
+      params = params.with_accuracy(params.accuracy.next());
+    }
+    if let (true, true, Some(t)) = (ctrl_down, is_key_released(KeyCode::T), target) {
+      let body = sim.major_celestial_bodies[t].clone();
+      log_event(&mut event_log, format!("Home set to {}", body.borrow().name));
+      home_body = Some(body);
+    }
+    // Ctrl+1..3 tag the target, Ctrl+4..6 filter the view by those tags, Ctrl+0 dims or hides the rest
+    for (i, tag) in BODY_TAGS.iter().enumerate() {
+      if let (true, true, Some(t)) = (ctrl_down, is_key_released(THROTTLE_KEYS[i + 1]), target) {
+        let mut body = sim.major_celestial_bodies[t].borrow_mut();
+        toggle_tag(&mut body.tags, tag);
+        let entry = format!("{} tags: {}", body.name, if body.tags.is_empty() { "none".to_owned() } else { body.tags.join(", ") });
+        log_event(&mut event_log, entry);
+      }
+      if ctrl_down && is_key_released(THROTTLE_KEYS[i + 1 + BODY_TAGS.len()]) {
+        params.tag_filter.toggle(tag);
+      }
+    }
+    if ctrl_down && is_key_released(KeyCode::Key0) {
+      params.tag_filter.hide = !params.tag_filter.hide;
+    }
+    if !ctrl_down && is_key_released(KeyCode::T) {
+      target = match target {
+        None => Some(1),
+        Some(t) if t + 1 < sim.major_celestial_bodies.len() => Some(t + 1),
+        _ => None,
+      };
+    }
+    if is_key_released(KeyCode::Delete) {
+      if let Some(t) = target.take() {
+        let body = sim.major_celestial_bodies[t].clone();
+        if focus_target.body().is_some_and(|f| Rc::ptr_eq(f, &body)) {
+          focus_target = FocusTarget::Ship;
+        }
+        log_event(&mut event_log, format!("Removed {}", body.borrow().name));
+        edits.push(Edit::Removed(body.clone()));
+        remove_body(&mut sim, &body);
+      }
+    }
+    let cursor = focus + rotate_vec2_by_rad(&((Vec2::from(mouse_position()) - vec2(screen_width(), screen_height()) / 2.) * scale), frame_angle);
+    if is_key_released(KeyCode::Insert) {
+      let star_pos = sim.cb_parent.borrow().mov.pos;
+      let offset = cursor - star_pos;
+      placed_count += 1;
+      let body = wrap_object(CelestialBody::from_parent(
+        &sim.cb_parent.borrow(),
+        offset.length().max(sim.cb_parent.borrow().radius * 2.),
+        offset.y.atan2(offset.x).to_degrees(),
+        PLACED_BODY_MASS,
+        PLACED_BODY_RADIUS,
+        CelestialBodyType::Asteroid,
+        LIGHTGRAY,
+        format!("Placed {}", placed_count),
+        &params,
+      ));
+      log_event(&mut event_log, format!("Placed {}", body.borrow().name));
+      edits.push(Edit::Added(body.clone()));
+      add_body(&mut sim, body);
+    }
+    if let (true, Some(t)) = (is_mouse_button_released(MouseButton::Right), target) {
+      let mut body = sim.major_celestial_bodies[t].borrow_mut();
+      edits.push(Edit::VelocityChanged(sim.major_celestial_bodies[t].clone(), body.mov.vel));
+      let fling = (cursor - body.mov.pos) / FLING_TIME;
+      body.mov.vel += fling;
+    }
+    if ctrl_down && is_key_released(KeyCode::Z) {
+      if let Some(name) = edits.undo(&mut sim) {
+        log_event(&mut event_log, format!("Undid edit of {}", name));
+      }
+    }
+    if ctrl_down && is_key_released(KeyCode::X) {
+      params.asteroid_collisions = !params.asteroid_collisions;
+      log_event(&mut event_log, format!("Asteroid impacts {}", if params.asteroid_collisions { "on" } else { "off" }));
+    }
+    if is_key_released(KeyCode::LeftBracket) {
+      transfer_days = (transfer_days - 10).max(10);
+    }
+    if is_key_released(KeyCode::RightBracket) {
+      transfer_days += 10;
+    }
+    if is_key_released(KeyCode::Minus) {
+      lead_days = lead_days.saturating_sub(10);
+    }
+    if is_key_released(KeyCode::Equal) {
+      lead_days += 10;
+    }
+    // schedules the planned transfer's burn after the lead time, or drops the scheduled one
+    if is_key_released(KeyCode::Enter) {
+      planned_burn = match (planned_burn.take(), target) {
+        (None, Some(t)) if lead_days > 0 => {
+          let label = format!("Transfer to {}", sim.major_celestial_bodies[t].borrow().name);
+          Some(PlannedBurn::new(label, sim.elapsed_seconds as f32 + lead_days as f32 * params.day_length))
+        },
+        _ => None,
+      };
+    }
+    if target.is_none() {
+      planned_burn = None;
+    }
+    if !ctrl_down && is_key_released(KeyCode::G) {
+      // the periodic prediction can be up to a refresh old, so the check starts from a fresh one
+      if prediction_check.is_none() {
+        prediction = simulate_hill_radius(&sim.ships, &sim.major_celestial_bodies, focus_target.body(), 200, params.config.g, params.gravity_exponent, params.softening, &params.gravity_scales, params.integrator, SIMULATION_STEP);
+        prediction.made_at = sim.elapsed_seconds;
+      }
+      let active_index = sim.ships.iter().position(|s| Rc::ptr_eq(s, &sim.ship));
+      prediction_check = match (&prediction_check, active_index.and_then(|i| prediction.ship_paths.get(i))) {
+        (None, Some(path)) => {
+          let steps_per_sample = (PREDICTION_SAMPLE_EVERY as f32 * SIMULATION_STEP / params.step_dt()).round() as usize;
+          Some(PredictionCheck::new(path.clone(), steps_per_sample))
+        },
+        _ => None,
+      };
+    }
+    if is_key_released(KeyCode::F) {
+      sim.force_fields = if sim.force_fields.is_empty() { default_force_fields(&sim.cb_parent) } else { vec![] };
+    }
+    if ctrl_down && is_key_released(KeyCode::Space) {
+      smooth_trail = !smooth_trail;
+    }
+    if !ctrl_down && is_key_released(KeyCode::Space) {
       show_trails = !show_trails;
     }
-    if is_key_released(KeyCode::I) {
+    if ctrl_down && is_key_released(KeyCode::I) {
+      // cycles the live scheme against Verlet, plain Euler against the live scheme, then off
+      let kinds = match integrator_compare.as_ref().map(|c| c.kinds()) {
+        None => Some([IntegratorKind::SemiImplicitEuler, IntegratorKind::VelocityVerlet]),
+        Some((IntegratorKind::SemiImplicitEuler, _)) => Some([IntegratorKind::Euler, IntegratorKind::SemiImplicitEuler]),
+        _ => None,
+      };
+      integrator_compare = kinds.map(|kinds| IntegratorComparison::new(&sim.ship.borrow(), kinds));
+    }
+    if !ctrl_down && is_key_released(KeyCode::I) {
       tick = (tick * 2).min(1024);
     }
-    if is_key_released(KeyCode::J) {
+    // cycles the gravity scale between the focused body and the target
+    if let (true, true, Some(t), Some(focused)) = (ctrl_down, is_key_released(KeyCode::J), target, focus_target.body()) {
+      let (a, b) = (focused.borrow(), sim.major_celestial_bodies[t].borrow());
+      if a.id != b.id {
+        let scale = next_pair_scale(pair_gravity_scale(&params.gravity_scales, a.id, b.id));
+        params.set_pair_scale(a.id, b.id, scale);
+        log_event(&mut event_log, format!("Gravity between {} and {} x{}", a.name, b.name, scale));
+      }
+    }
+    if !ctrl_down && is_key_released(KeyCode::J) {
       tick = (tick / 2).max(1);
     }
-    if is_key_released(KeyCode::K) {
+    if !ctrl_down && is_key_released(KeyCode::K) {
       tick = 1;
     }
-    {
-      let mut ship = ship.borrow_mut();
-      if is_key_down(KeyCode::W) {
-        ship.throttle_up(dt);
+    if is_key_released(KeyCode::GraveAccent) {
+      composing = !composing;
+      pan.reset();
+    }
+    // dragging an apsis handle rebuilds the ship's velocity for the new apsides at the same position
+    let apsis_body = match (composing, &sim.ship.borrow().state) {
+      (true, ShipState::InSpace) => sim.ship.borrow().dominant_body(),
+      _ => None,
+    };
+    match (apsis_body, apsis_drag) {
+      (Some(body), Some(periapsis_handle)) if down => {
+        let b = body.borrow();
+        let mut ship = sim.ship.borrow_mut();
+        let mu = params.config.g * b.mov.mass;
+        let (rel_pos, rel_vel) = (ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel);
+        let current = osculating_elements(rel_pos, rel_vel, mu);
+        let dragged = cursor.distance(b.mov.pos);
+        let (periapsis, apoapsis) = match (periapsis_handle, current.apoapsis) {
+          (true, Some(apoapsis)) => (dragged, apoapsis),
+          (false, _) => (current.periapsis, dragged),
+          (true, None) => (dragged, dragged),
+        };
+        let (_, vel) = state_from_elements(&elements_with_apsides(rel_pos, rel_vel, periapsis, apoapsis, mu), mu);
+        ship.mov.vel = b.mov.vel + vel;
+      },
+      (Some(body), None) if pressed && !grabbed => {
+        let b = body.borrow();
+        let ship = sim.ship.borrow();
+        let elements = osculating_elements(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, params.config.g * b.mov.mass);
+        if let Some((periapsis, apoapsis)) = elements.apsis_points() {
+          let grab = APSIS_HANDLE_RADIUS * 2. * scale;
+          if cursor.distance(b.mov.pos + periapsis) < grab {
+            apsis_drag = Some(true);
+          } else if cursor.distance(b.mov.pos + apoapsis) < grab {
+            apsis_drag = Some(false);
+          }
+        }
+      },
+      (_, Some(_)) => apsis_drag = None,
+      _ => {},
+    }
+    if composing {
+      let arrows = vec2(
+        is_key_down(KeyCode::Right) as i32 as f32 - is_key_down(KeyCode::Left) as i32 as f32,
+        is_key_down(KeyCode::Down) as i32 as f32 - is_key_down(KeyCode::Up) as i32 as f32,
+      );
+      pan.offset += arrows * COMPOSE_PAN_SPEED * scale * get_frame_time();
+    }
+    if is_key_released(KeyCode::P) {
+      paused = !paused;
+    }
+    // a single physics tick while paused
+    let single_step = paused && is_key_released(KeyCode::Period);
+    let input_seen = get_last_key_pressed().is_some() || [MouseButton::Left, MouseButton::Right, MouseButton::Middle].iter().any(|b| is_mouse_button_pressed(*b));
+    focus_pause.update(focus_event(get_frame_time(), input_seen), params.pause_on_focus_loss);
+    // ship controls stay live only while physics runs, otherwise turning would still apply
+    let simulation_halted = focus_pause.is_paused() || replay.is_some() || composing || (paused && !single_step);
+    let physics_steps = if simulation_halted || paused {
+      stepper.clear();
+      if simulation_halted { 0 } else { params.substeps }
+    } else {
+      stepper.drain(get_frame_time(), tick as f32, params.step_dt())
+    };
+    for (i, key) in THROTTLE_KEYS.iter().enumerate() {
+      if !ctrl_down && is_key_released(*key) {
+        throttle = i as f32 / 10.;
       }
-      if is_key_down(KeyCode::A) {
-        ship.turn_left(dt);
+    }
+    // held keys are read once a frame and applied on every physics step below
+    let (left_key, right_key) = turn_keys(params.invert_turn);
+    let (turning_left, turning_right) = (is_key_down(left_key), is_key_down(right_key));
+    let (full_throttle, damping, killing_rotation) = (is_key_down(KeyCode::W), is_key_down(KeyCode::S), is_key_down(KeyCode::Backspace));
+    let hold_target = target.map(|t| sim.major_celestial_bodies[t].clone());
+    if simulation_halted {
+      sim.ship.borrow_mut().fuel_flow = 0.;
+    } else {
+      let mut ship = sim.ship.borrow_mut();
+      // cycles prograde, retrograde, the target when there is one, and off
+      if ctrl_down && is_key_released(KeyCode::O) {
+        attitude_hold = match attitude_hold {
+          None => Some(AttitudeHold::Prograde),
+          Some(AttitudeHold::Prograde) => Some(AttitudeHold::Retrograde),
+          Some(AttitudeHold::Retrograde) if target.is_some() => Some(AttitudeHold::Target),
+          _ => None,
+        };
+      }
+      if !ctrl_down && is_key_released(KeyCode::Z) {
+        ship.frozen = !ship.frozen;
+      }
+      if !ctrl_down && is_key_released(KeyCode::U) {
+        ascent = match ascent {
+          Some(_) => None,
+          None => AscentAutopilot::new(&ship),
+        };
       }
-      if is_key_down(KeyCode::D) {
-        ship.turn_right(dt);
+      if is_key_released(KeyCode::Kp0) {
+        escape = match escape {
+          None if ascent.is_none() && docking.is_none() => EscapeAutopilot::new(&ship, params.config.g).map_err(|e| log_event(&mut event_log, e)).ok(),
+          _ => None,
+        };
       }
-      if is_key_released(KeyCode::X) {
-        scale = 1.;
+      // cycles the target planet's L4, L5 and off
+      if ctrl_down && is_key_released(KeyCode::L) {
+        let point = match docking.as_ref().map(|d| d.point) {
+          None if ascent.is_none() && escape.is_none() => Some(TrojanPoint::L4),
+          Some(TrojanPoint::L4) => Some(TrojanPoint::L5),
+          _ => None,
+        };
+        let body = target.map(|t| sim.major_celestial_bodies[t].clone()).filter(|b| matches!(b.borrow().cb_type, CelestialBodyType::Planet));
+        docking = match (point, body) {
+          (Some(point), Some(body)) => {
+            let tof = transfer_days as f32 * params.day_length;
+            LPointAutopilot::new(&ship, &body, &sim.cb_parent, point, tof, sim.elapsed_seconds, params.config.g)
+              .inspect(|d| log_event(&mut event_log, format!("Docking with {} {:?}: {:.1} delta-v, arrival in {} days", body.borrow().name, point, d.delta_v, transfer_days)))
+              .map_err(|e| log_event(&mut event_log, e))
+              .ok()
+          },
+          (Some(_), None) => {
+            log_event(&mut event_log, "L-point docking needs a target planet".to_owned());
+            None
+          },
+          _ => None,
+        };
+      }
+    }
+    if !ctrl_down && is_key_released(KeyCode::X) {
+      target_scale = 1.;
+      focus_target = FocusTarget::Ship;
+      pan.reset();
+      zoom_anchor = None;
+    }
+    // dragging lets go of whatever was followed, the view stays where it was
+    if is_mouse_button_pressed(MouseButton::Middle) && replay.is_none() && overview.is_none() {
+      focus_target = FocusTarget::Free(focus - pan.offset);
+    }
+    pan.update(Vec2::from(mouse_position()), is_mouse_button_down(MouseButton::Middle), scale, get_frame_time());
+    if mouse_wheel().1 > 0. {
+      target_scale = (target_scale - get_scale_delta(target_scale)).max(MIN_SCALE);
+      zoom_anchor = Some(target_scale);
+    } else if mouse_wheel().1 < 0. {
+      target_scale = (target_scale + get_scale_delta(target_scale)).min(MAX_SCALE);
+      zoom_anchor = Some(target_scale);
+    }
+    if is_key_released(KeyCode::Kp6) {
+      lock_orbit_scale = !lock_orbit_scale;
+    }
+    // keeps the whole orbit of the focused body around its parent in view
+    if let (true, Some(body)) = (lock_orbit_scale, focus_target.body()) {
+      if let Some(parent) = orbital_parent(body, &sim.major_celestial_bodies) {
+        let (b, p) = (body.borrow(), parent.borrow());
+        let elements = osculating_elements(b.mov.pos - p.mov.pos, b.mov.vel - p.mov.vel, params.config.g * p.mov.mass);
+        if elements.eccentricity < 1. {
+          target_scale = scale_for_orbit(elements.semi_major_axis, vec2(screen_width(), screen_height()));
+        }
       }
-      if mouse_wheel().1 > 0. {
-        scale = (scale - get_scale_delta(scale)).max(0.5);
-      } else if mouse_wheel().1 < 0. {
-        scale = (scale + get_scale_delta(scale)).min(5000.);
+    }
+    let previous_scale = scale;
+    scale = ease_toward(scale, target_scale, ZOOM_EASE_RATE, get_frame_time());
+    // any other zoom since the wheel (focus, orbit lock, overview) eases about the focus as before
+    if zoom_anchor == Some(target_scale) && overview.is_none() {
+      let cursor_offset = Vec2::from(mouse_position()) - vec2(screen_width(), screen_height()) / 2.;
+      pan.zoom_about(rotate_vec2_by_rad(&cursor_offset, frame_angle), previous_scale, scale);
+    }
+    // momentary: held down shows the whole system, released puts the camera back
+    if is_key_pressed(KeyCode::Kp8) && overview.is_none() {
+      overview = Some(Overview::begin(scale, target_scale, &mut pan));
+    }
+    if is_key_released(KeyCode::Kp8) {
+      if let Some(saved) = overview.take() {
+        (scale, target_scale) = saved.end(&mut pan);
       }
     }
+    let overview_center = barycenter(&sim.major_celestial_bodies);
+    if overview.is_some() {
+      scale = fit_scale(&sim.major_celestial_bodies, overview_center, vec2(screen_width(), screen_height()));
+      target_scale = scale;
+    }
 
-    for _ in 0..tick
+    let mut active_lost = false;
+    sim.asteroid_pairs.start_frame();
+    for _ in 0..physics_steps
     {
-      apply_gravity_to_celestial_bodies(&major_celestial_bodies, dt);
-      apply_gravity_asteroids(&minor_celestial_bodies, &cb_parent, dt);
-      apply_gravity_to_ships(&ships, &all_celestial_bodies, dt);
-
-      for go in &game_objects {
-        go.borrow_mut().update(dt);
-      }
+      // controls and autopilots act once per physics step, over the same dt as the physics
       {
-        let _z = ZoneGuard::new("collision");
-        for s in &ships {
-          s.borrow_mut().process_collision(&all_celestial_bodies, dt);
+        let mut ship = sim.ship.borrow_mut();
+        let control_dt = params.step_dt();
+        // an autopilot coasting leaves the engine off
+        ship.fuel_flow = 0.;
+        // the autopilot drives the throttle itself
+        if ascent.is_none() && escape.is_none() && docking.is_none() {
+          ship.throttle(if full_throttle { 1. } else { throttle }, &params, control_dt);
+        }
+        if turning_left {
+          ship.turn_left(&params, control_dt);
+        }
+        if turning_right {
+          ship.turn_right(&params, control_dt);
+        }
+        if damping {
+          ship.damp_rotation(control_dt);
+        }
+        // turning by hand or an autopilot's steering takes over from the hold
+        let steering = turning_left || turning_right || ascent.is_some() || escape.is_some() || docking.is_some();
+        if let (Some(hold), false) = (attitude_hold, steering) {
+          ship.hold_attitude(hold, hold_target.as_ref().map(|t| t.borrow()).as_deref(), &params, control_dt);
+        }
+        if killing_rotation {
+          ship.kill_rotation(control_dt);
+        }
+        if let Some(report) = ascent.as_mut().and_then(|a| a.update(&mut ship, &params, control_dt)) {
+          log_event(&mut event_log, report);
+          ascent = None;
+        }
+        if let Some(report) = escape.as_mut().and_then(|e| e.update(&mut ship, &params, control_dt)) {
+          log_event(&mut event_log, report);
+          escape = None;
+        }
+        if let Some(report) = docking.as_mut().and_then(|d| d.update(&mut ship, &params, sim.elapsed_seconds, control_dt)) {
+          log_event(&mut event_log, report);
+          docking = None;
+        }
+      }
+      for event in sim.step_adaptive(&params, params.step_dt()) {
+        if let SimEvent::DayPassed = event {
+          continue;
+        }
+        // every impact sparks, only one that threw off debris is worth a log line
+        if let SimEvent::Impact { at, debris } = &event {
+          sparks.push((*at, Timer::new_timeout(SPARK_TIME)));
+          if *debris == 0 {
+            continue;
+          }
+        }
+        log_event(&mut event_log, event.to_string());
+        if let SimEvent::Destroyed { ship } = &event {
+          active_lost |= Rc::ptr_eq(&sim.ships[*ship], &sim.ship);
+        }
+        // the target is an index into the major bodies, which a merge shifts
+        if let SimEvent::Merged { .. } = &event {
+          target = None;
+        }
+        if let SimEvent::Landed { ship, .. } = &event {
+          let score = sim.ships[*ship].borrow().last_landing_score;
+          log_event(&mut event_log, format!("Landing score {}", score));
+          match leaderboard.record(sim.seed, score) {
+            Ok(true) => log_event(&mut event_log, format!("New best landing for seed {}", sim.seed)),
+            Ok(false) => {},
+            Err(e) => log_event(&mut event_log, format!("Saving landing record failed: {}", e)),
+          }
+        }
+        if let SimEvent::SoiChange { ship, body } = &event {
+          if Rc::ptr_eq(&sim.ships[*ship], &sim.ship) {
+            audio_cues.trigger(Cue::SoiChange, 0.);
+          }
+          let (s, b) = (sim.ships[*ship].borrow(), body.borrow());
+          log_event(&mut event_log, orbit_summary(s.mov.pos - b.mov.pos, s.mov.vel - b.mov.vel, params.config.g * b.mov.mass, &b.name, params.day_length));
         }
       }
-      day_timer.update(dt);
-      if day_timer.is_just_over() {
-        day_count += 1;
+      if let Some(pc) = &mut prediction_check {
+        pc.record(sim.ship.borrow().mov.pos);
       }
+      if let Some(other) = &mut compare_sim {
+        other.step(&params, params.step_dt());
+      }
+      if let Some(c) = &mut integrator_compare {
+        c.step(&sim.major_celestial_bodies, params.config.g, params.gravity_exponent, params.softening, params.step_dt());
+      }
+    }
+    // losing the active ship hands control to the next live one, if any
+    if active_lost {
+      if sim.select_next_ship() {
+        ship_switched = true;
+      } else {
+        log_event(&mut event_log, "Press Y to replay".to_owned());
+      }
+    }
+    if focus_target.body().is_some_and(|f| !sim.all_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, f))) {
+      focus_target = FocusTarget::Ship;
+    }
+    // wrecks stop being drawn and pulled on; the paths predicted for them go with them
+    if sim.remove_dead_ships() > 0 {
+      prediction = Prediction::default();
+    }
+    // autopilots, the recorded trajectory and the prediction check all belong to the previous ship
+    if ship_switched {
+      log_event(&mut event_log, format!("Flying {}", sim.ship.borrow().name));
+      ascent = None;
+      escape = None;
+      docking = None;
+      attitude_hold = None;
+      prediction_check = None;
+      recorder = TrajectoryRecorder::new();
+    }
+    // inserted after stepping so the collections stay untouched while they are iterated
+    for copy in pending_ships.drain(..) {
+      log_event(&mut event_log, format!("Cloned {}", copy.name));
+      add_ship(&mut sim, wrap_object(copy));
+    }
+    if physics_steps > 0 {
+      timeline.record(&sim, &params);
+    }
+    // deleting, merging, despawning or undoing can take the home body out of the system
+    if home_body.as_ref().is_some_and(|home| !sim.all_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, home))) {
+      home_body = None;
+    }
+    let sim_dt = physics_steps as f32 * params.step_dt();
+    recorder.record(sim.ship.borrow().mov.pos, sim_dt);
+    for s in &sim.ships {
+      s.borrow_mut().sample_g_force(sim_dt);
+    }
+    let sim_time = sim.elapsed_seconds as f32;
+    if let Some(burn) = &mut planned_burn {
+      if burn.update(sim_time) {
+        log_event(&mut event_log, format!("{}: burn now", burn.label));
+      } else if burn.is_due() && sim.ship.borrow().throttle_level > 0. {
+        log_event(&mut event_log, format!("{}: burn executed", burn.label));
+        planned_burn = None;
+      }
+    }
+    if let Some(ghosts) = &mut orbit_ghosts {
+      ghosts.update(&sim.ship.borrow(), params.config.g, sim_dt);
     }
-    focus = ship.borrow().mov.pos;
+    let replay_pos = replay.as_mut().and_then(|r| {
+      let rate = if is_key_down(KeyCode::Right) { REPLAY_SCRUB_RATE } else if is_key_down(KeyCode::Left) { -REPLAY_SCRUB_RATE } else { 1. };
+      r.advance(&recorder, rate * get_frame_time() * tick as f32);
+      recorder.position_at(r.time)
+    });
+    if let Some(camera) = &mut chase_camera {
+      let ship = sim.ship.borrow();
+      camera.update(ship.mov.pos, ship.mov.vel, get_frame_time());
+    }
+    // the accumulator owes part of a step, so everything is drawn that far between its last two
+    // states; a followed object is looked at where it is drawn
+    let render_alpha = stepper.alpha(params.step_dt());
+    focus = match (replay_pos, &focus_target, &chase_camera) {
+      _ if overview.is_some() => overview_center,
+      (Some(pos), _, _) => pos,
+      (None, FocusTarget::Body(body), _) => body.borrow().mov.render_pos(render_alpha),
+      (None, FocusTarget::Free(pos), _) => *pos,
+      (None, FocusTarget::Ship, Some(camera)) => camera.pos,
+      (None, FocusTarget::Ship, None) => sim.ship.borrow().mov.render_pos(render_alpha),
+    } + pan.offset;
+    // viewed from the focused body, the axis towards the target stays fixed on screen
+    let locked_pair = match (locked_frame, focus_target.body(), target) {
+      (true, Some(body), Some(t)) if !Rc::ptr_eq(body, &sim.major_celestial_bodies[t]) => Some((body.clone(), sim.major_celestial_bodies[t].clone())),
+      _ => None,
+    };
+    frame_angle = locked_pair.as_ref().map_or(0., |(body, moon)| locked_frame_angle(&body.borrow(), &moon.borrow()));
+    let mut world_camera = full_camera;
+    world_camera.rotation = -frame_angle.to_degrees();
+    set_camera(&world_camera);
 
     trail_emitter_timer.update(dt);
     simulated_trail_timer.update(dt);
-    if simulated_trail_timer.is_just_over() {
-      // simulated_trail = simulate(&ships, &major_celestial_bodies, 200, SIMULATION_STEP);
-      simulated_trail = simulate_hill_radius(&ships, 200, SIMULATION_STEP);
+    // a single step moves the ship away from the last prediction, so it is redone right away
+    if simulated_trail_timer.is_just_over() || (paused && physics_steps > 0) {
+      prediction = simulate_hill_radius(&sim.ships, &sim.major_celestial_bodies, focus_target.body(), 200, params.config.g, params.gravity_exponent, params.softening, &params.gravity_scales, params.integrator, SIMULATION_STEP);
+      prediction.made_at = sim.elapsed_seconds;
+      if show_envelope {
+        envelope = prediction_envelope(&sim.ship, &sim.major_celestial_bodies, ENVELOPE_RUNS, SIMULATION_STEP, 200, params.config.g, params.gravity_exponent, params.softening, &params.gravity_scales, params.integrator);
+      }
+    }
+    heatmap_timer.update(dt);
+    // counted in real time while running, so the interval does not depend on the step size
+    if params.autosave_interval > 0. && !paused {
+      match autosave.update(get_frame_time(), &sim, &params) {
+        Some(Ok(path)) => log_event(&mut event_log, format!("Auto-saved to {}", path.display())),
+        Some(Err(e)) => log_event(&mut event_log, format!("Auto-save failed: {}", e)),
+        None => {},
+      }
+    }
+    if show_heatmap && (heatmap.is_none() || heatmap_timer.is_just_over()) {
+      let half_extent = vec2(screen_width(), screen_height()) * scale * 0.75;
+      heatmap = Some(PotentialHeatmap::sample(focus, half_extent, &sim.major_celestial_bodies, params.config.g));
     }
     if trail_emitter_timer.is_just_over() {
-      trail_elements.push(((ship.borrow().mov.pos), WHITE, Timer::new(TRAIL_CLEANUP_IIME)));
+      let element = ((sim.ship.borrow().mov.pos), WHITE, Timer::new(TRAIL_CLEANUP_IIME));
+      match trail_elements.iter_mut().find(|(s, _)| Rc::ptr_eq(s, &sim.ship)) {
+        Some((_, trail)) => trail.push(element),
+        None => trail_elements.push((sim.ship.clone(), vec![element])),
+      }
     }
 
     {
       let _z = ZoneGuard::new("draw");
-      for go in &game_objects {
-        go.borrow().draw(focus, scale);
+      if let Some(other) = &compare_sim {
+        set_camera(&split_camera(1));
+        let other_focus = other.ship.borrow().mov.pos;
+        draw_world(other, other_focus, scale, detail, &params, render_alpha);
+        set_camera(&split_camera(0));
+      }
+      if let Some(heatmap) = &heatmap {
+        heatmap.draw(focus, scale);
       }
+      draw_world(&sim, focus, scale, detail, &params, render_alpha);
     }
 
-    if show_trails {
+    if show_trails && detail.shows_trails() {
       let _z = ZoneGuard::new("show_trails");
-      for (te_pos, color, _) in &trail_elements {
-        let p = (*te_pos - focus) / scale;
-        draw_rectangle(p.x - 2., p.y - 2., 4., 4., *color);
+      for (_, trail) in &trail_elements {
+        draw_trail(trail, focus, scale, smooth_trail);
+      }
+      for trail in &prediction.trails {
+        draw_trail(trail, focus, scale, false);
       }
-      for (te_pos, color, _) in &simulated_trail {
-        let p = (*te_pos - focus) / scale;
-        draw_rectangle(p.x - 2., p.y - 2., 4., 4., *color);
+    }
+    for mov in &prediction.touchdowns {
+      draw_ship_triangle(mov.pos, mov.rot, focus, scale, Color::new(1., 1., 1., 0.3));
+    }
+    sparks.retain_mut(|(pos, timer)| {
+      timer.update(get_frame_time());
+      let p = (*pos - focus) / scale;
+      draw_circle_lines(p.x, p.y, SPARK_RADIUS * timer.progress(), 2., Color::new(1., 0.8, 0.3, 1. - timer.progress()));
+      !timer.is_over()
+    });
+    if show_envelope {
+      let (inner, outer) = &envelope;
+      let band = Color::new(1., 1., 0., 0.15);
+      for (i, o) in inner.windows(2).zip(outer.windows(2)) {
+        let (a, b, c, d) = ((i[0] - focus) / scale, (i[1] - focus) / scale, (o[0] - focus) / scale, (o[1] - focus) / scale);
+        draw_triangle(a, b, c, band);
+        draw_triangle(b, d, c, band);
       }
     }
 
 
-    draw_text(&format!("Scale: {}, tick: {}", scale, tick), -screen_width() / 2. + 5., -screen_height() / 2. + 30., 24., WHITE);
-    // draw_text(&format!("FPS: {}", get_fps()), -screen_width() / 2. + 5., -screen_height() / 2. + 60., 24., WHITE);
-    // draw_text(&format!("Seed: {}", seed), -screen_width() / 2. + 5., -screen_height() / 2. + 90., 24., WHITE);
-    draw_text(&format!("Elapsed time: {} days", day_count), screen_width() / 2. - 256., -screen_height() / 2. + 30., 24., WHITE);
+    if let Some(pc) = &prediction_check {
+      let to_screen = |p: Vec2| (p - focus) / scale;
+      for w in pc.predicted.windows(2) {
+        let (a, b) = (to_screen(w[0]), to_screen(w[1]));
+        draw_line(a.x, a.y, b.x, b.y, 1., YELLOW);
+      }
+      for w in pc.actual.windows(2) {
+        let (a, b) = (to_screen(w[0]), to_screen(w[1]));
+        draw_line(a.x, a.y, b.x, b.y, 1., GREEN);
+      }
+      for (p, a) in pc.predicted.iter().zip(&pc.actual) {
+        let (p, a) = (to_screen(*p), to_screen(*a));
+        draw_line(p.x, p.y, a.x, a.y, 1., RED);
+      }
+    }
+
+    if lead_days > 0 {
+      for cb in &sim.major_celestial_bodies {
+        let p = (future_position(cb, &sim.major_celestial_bodies, lead_days as f32 * params.day_length) - focus) / scale;
+        let cb = cb.borrow();
+        let c = cb.color;
+        draw_circle(p.x, p.y, cb.display_radius(scale, &params.min_display_radius), Color::new(c.r, c.g, c.b, 0.3));
+      }
+    }
+
+    if let (true, Some(t)) = (show_tether, target) {
+      let ship = sim.ship.borrow();
+      let target = sim.major_celestial_bodies[t].borrow();
+      let (a, b) = ((ship.mov.pos - focus) / scale, (target.mov.pos - focus) / scale);
+      let rate = closing_rate(&ship, &target);
+      let color = if rate < 0. { RED } else { GREEN };
+      draw_line(a.x, a.y, b.x, b.y, 1., color);
+      let mid = (a + b) / 2.;
+      draw_text(
+        &format!("d: {:.0}, rel v: {:.2}", ship.mov.pos.distance(target.mov.pos), relative_velocity(&ship.mov, &target.mov).length()),
+        mid.x, mid.y, INFO_FONT_SIZE, color
+      );
+    }
+
+    if show_burn_preview {
+      let ship = sim.ship.borrow();
+      let amount = if throttle > 0. { throttle } else { 1. };
+      let dv = ship.burn_preview(amount, BURN_PREVIEW_TIME, &params);
+      let p = (ship.mov.pos - focus) / scale;
+      let tip = p + dv / scale;
+      draw_line(p.x, p.y, tip.x, tip.y, 2., ORANGE);
+      draw_circle(tip.x, tip.y, 3., ORANGE);
+      draw_text(&format!("dv {:.1} in {}s", dv.length(), BURN_PREVIEW_TIME), tip.x + 5., tip.y, INFO_FONT_SIZE, ORANGE);
+    }
+    if let Some(pos) = replay_pos {
+      let path: Vec<Vec2> = recorder.path().map(|p| (p - focus) / scale).collect();
+      for w in path.windows(2) {
+        draw_line(w[0].x, w[0].y, w[1].x, w[1].y, 1., Color::new(1., 1., 0., 0.4));
+      }
+      let p = (pos - focus) / scale;
+      draw_circle_lines(p.x, p.y, (SHIP_SIZE / scale).max(3.) * 2., 2., YELLOW);
+    }
+    if let Some(ghosts) = &orbit_ghosts {
+      ghosts.draw(focus, scale, sim.ship.borrow().color);
+    }
+    if let Some(c) = &integrator_compare {
+      c.draw(focus, scale);
+    }
+    if let Some(home) = &home_body {
+      let ship_pos = sim.ship.borrow().mov.pos;
+      let direction = (home.borrow().mov.pos - ship_pos).normalize_or_zero();
+      draw_arrow((ship_pos - focus) / scale, direction, HOME_ARROW_LENGTH, home.borrow().color);
+    }
+    if let (true, ShipState::InSpace, Some(body)) = (composing, &sim.ship.borrow().state, sim.ship.borrow().dominant_body()) {
+      let (s, b) = (sim.ship.borrow(), body.borrow());
+      let elements = osculating_elements(s.mov.pos - b.mov.pos, s.mov.vel - b.mov.vel, params.config.g * b.mov.mass);
+      if let Some((periapsis, apoapsis)) = elements.apsis_points() {
+        for (handle, dragged) in [(periapsis, apsis_drag == Some(true)), (apoapsis, apsis_drag == Some(false))] {
+          let p = (b.mov.pos + handle - focus) / scale;
+          draw_circle_lines(p.x, p.y, APSIS_HANDLE_RADIUS, if dragged { 2. } else { 1. }, b.color);
+        }
+      }
+    }
+    if let (true, ShipState::InSpace, Some(body)) = (show_apsis_line, &sim.ship.borrow().state, sim.ship.borrow().dominant_body()) {
+      let (s, b) = (sim.ship.borrow(), body.borrow());
+      let elements = osculating_elements(s.mov.pos - b.mov.pos, s.mov.vel - b.mov.vel, params.config.g * b.mov.mass);
+      if let Some((periapsis, apoapsis)) = elements.apsis_points() {
+        draw_dashed_line((b.mov.pos + periapsis - focus) / scale, (b.mov.pos + apoapsis - focus) / scale, APSIS_DASH_LENGTH, b.color);
+      }
+    }
+    if overview.is_some() {
+      let ship = sim.ship.borrow();
+      let p = (ship.mov.pos - focus) / scale;
+      draw_circle_lines(p.x, p.y, 10., 2., ship.color);
+      draw_line(p.x - 14., p.y, p.x + 14., p.y, 1., ship.color);
+      draw_line(p.x, p.y - 14., p.x, p.y + 14., 1., ship.color);
+    }
+    #[cfg(debug_assertions)]
+    if show_collision_debug {
+      draw_collision_debug(&sim, focus, scale, params.step_dt());
+    }
+    let mut transfer_line = None;
+    if let Some(t) = target {
+      let target = sim.major_celestial_bodies[t].borrow();
+      let p = (target.mov.pos - focus) / scale;
+      draw_circle_lines(p.x, p.y, target.display_radius(scale, &params.min_display_radius) + 6., 1., target.color);
+      let transfer = plan_transfer(&sim.ship.borrow(), &target, &sim.cb_parent.borrow(), transfer_days as f32 * params.day_length, params.config.g);
+      let transfer_info = match transfer {
+        Some((dv, arc)) => {
+          for w in arc.windows(2) {
+            let (a, b) = ((w[0] - focus) / scale, (w[1] - focus) / scale);
+            draw_line(a.x, a.y, b.x, b.y, 1., target.color);
+          }
+          format!("dv: {:.2}", dv.length())
+        },
+        None => "no solution".to_owned(),
+      };
+      // the predicted path against the target's own two-body motion around its parent
+      let active_path = sim.ships.iter().position(|s| Rc::ptr_eq(s, &sim.ship)).and_then(|i| prediction.ship_paths.get(i));
+      let parent = orbital_parent(&sim.major_celestial_bodies[t], &sim.major_celestial_bodies);
+      let intercept_info = match (active_path, parent) {
+        (Some(path), Some(parent)) => {
+          let since = (sim.elapsed_seconds - prediction.made_at) as f32;
+          let parent_at = |t: f32| future_position(&parent, &sim.major_celestial_bodies, t);
+          let ship_pred: Vec<(f32, Vec2)> = path.iter().enumerate()
+            .map(|(k, p)| ((k * PREDICTION_SAMPLE_EVERY) as f32 * SIMULATION_STEP - since, *p))
+            .filter(|(t, _)| *t >= 0.)
+            .map(|(t, p)| (t, p - parent_at(t)))
+            .collect();
+          let p = parent.borrow();
+          let state = (target.mov.pos - p.mov.pos, target.mov.vel - p.mov.vel);
+          let mu = params.config.g * p.mov.mass;
+          if let Some((t, ship, body)) = closest_intercept(&ship_pred, state, mu) {
+            let (a, b) = ((ship + parent_at(t) - focus) / scale, (body + parent_at(t) - focus) / scale);
+            draw_circle_lines(a.x, a.y, 4., 1., sim.ship.borrow().color);
+            draw_circle_lines(b.x, b.y, 4., 1., target.color);
+            draw_line(a.x, a.y, b.x, b.y, 1., target.color);
+          }
+          format!(", intercept miss {:.0}", intercept_miss(&ship_pred, state, mu))
+        },
+        _ => String::new(),
+      };
+      transfer_line = Some(format!("Transfer to {} in {} days, {}{}", target.name, transfer_days, transfer_info, intercept_info));
+    }
+
+    set_camera(&full_camera);
+    if compare_sim.is_some() {
+      draw_line(0., hud.top, 0., hud.bottom, 2., DARKGRAY);
+      if let Some(other) = &compare_sim {
+        draw_text(&format!("Compare seed: {}, day {}", other.seed, other.day_count), 5., hud.top + 120., 24., WHITE);
+      }
+    }
+    if let Some(line) = transfer_line {
+      draw_text(&line, hud.left, hud.top + 60., 24., WHITE);
+    }
+    if let Some(burn) = &planned_burn {
+      if burn.is_due() {
+        // blinks until the burn is executed
+        if (get_time() * 2.) as i64 % 2 == 0 {
+          draw_text(&format!("BURN NOW: {}", burn.label), -100., hud.top + 180., 24., ORANGE);
+        }
+      } else {
+        draw_text(&format!("{}: burn in {:.1}s", burn.label, burn.countdown(sim_time)), hud.left, hud.top + 240., 24., WHITE);
+      }
+    }
+    if lead_days > 0 {
+      draw_text(&format!("Lead time: {} days", lead_days), hud.left, hud.top + 120., 24., WHITE);
+    }
+    if let (ShipState::InSpace, Some(body)) = (&sim.ship.borrow().state, sim.ship.borrow().dominant_body()) {
+      let (s, b) = (sim.ship.borrow(), body.borrow());
+      if b.soi_radius().is_finite() {
+        if let Some(t) = time_to_soi_exit(s.mov.pos - b.mov.pos, s.mov.vel - b.mov.vel, params.config.g * b.mov.mass, b.soi_radius()) {
+          draw_text(&format!("Leaving {} SOI in {:.1} days", b.name, t / params.day_length), hud.left, hud.top + 210., 24., WHITE);
+        }
+      }
+    }
+    if let ShipState::InSpace = sim.ship.borrow().state {
+      let crossed = crossed_orbits(&sim.ship.borrow(), &sim.cb_parent.borrow(), &sim.major_celestial_bodies, params.config.g);
+      if !crossed.is_empty() {
+        draw_text(&format!("Path crosses orbits of: {}", crossed.join(", ")), hud.left, hud.top + 180., 24., ORANGE);
+      }
+    }
+    let active_index = sim.ships.iter().position(|s| Rc::ptr_eq(s, &sim.ship));
+    audio_cues.update(get_frame_time());
+    if let Some(Some(approach)) = active_index.and_then(|i| prediction.closest_approaches.get(i)) {
+      let time_to_event = approach.time - (sim.elapsed_seconds - prediction.made_at) as f32;
+      if time_to_event > 0. {
+        if approach.clearance <= 0. {
+          audio_cues.trigger(Cue::Collision, time_to_event);
+        } else if prediction_confidence(approach.clearance, SIMULATION_STEP, approach.speed) < LOW_PREDICTION_CONFIDENCE {
+          audio_cues.trigger(Cue::CloseApproach, time_to_event);
+        }
+      }
+      let (line, color) = if approach.clearance <= 0. {
+        (format!("Predicted touchdown on {} at {:.1}", approach.body, approach.speed), ORANGE)
+      } else {
+        let confidence = prediction_confidence(approach.clearance, SIMULATION_STEP, approach.speed);
+        let color = if confidence < LOW_PREDICTION_CONFIDENCE { ORANGE } else { WHITE };
+        (format!("Closest approach {}: {:.0} clear, confidence {:.0}%", approach.body, approach.clearance, confidence * 100.), color)
+      };
+      draw_text(&line, hud.left, hud.top + 270., 24., color);
+    }
+    if let Some(Some(approach)) = active_index.and_then(|i| prediction.target_approaches.get(i)) {
+      let days = (approach.time - (sim.elapsed_seconds - prediction.made_at) as f32).max(0.) / params.day_length;
+      let line = match &approach.impact {
+        Some(name) => format!("Impact on {} in {:.1} days", name, days),
+        None => format!("Closest approach to {}: {:.0}, in {:.1} days", approach.target, approach.distance, days),
+      };
+      draw_text(&line, hud.left, hud.top + 480., 24., if approach.impact.is_some() { ORANGE } else { WHITE });
+    }
+    if let Some(pc) = &prediction_check {
+      let status = if pc.is_done() { "done" } else { "recording" };
+      draw_text(&format!("Prediction divergence ({}): max {:.2}", status, pc.max_deviation()), hud.left, hud.top + 150., 24., WHITE);
+    }
+
+    draw_text(&format!("Elapsed: {:.2}s, steps: {}", sim.elapsed_seconds, sim.step_count), hud.left, hud.top + 300., 24., WHITE);
+    let (g_force, peak_g_force) = (sim.ship.borrow().g_force, sim.ship.borrow().peak_g_force);
+    draw_text(&format!("Acceleration: {:.2} g (peak {:.2} g)", g_force, peak_g_force), hud.left, hud.top + 330., 24., WHITE);
+    if let Some(c) = &integrator_compare {
+      let (a, b) = c.kinds();
+      draw_text(&format!("{:?} vs {:?}: separation {:.1}, max {:.1}", a, b, c.separation(), c.max_deviation()), hud.left, hud.top + 390., 24., WHITE);
+    }
+    if show_conservation {
+      // a different force law has a different potential, so drift is measured from the switch
+      if conservation_law != (params.gravity_exponent, params.softening, params.gravity_scales.clone()) {
+        conservation_law = (params.gravity_exponent, params.softening, params.gravity_scales.clone());
+        conservation_baseline = ConservationTotals::measure(&sim.major_celestial_bodies, &params);
+      }
+      let totals = ConservationTotals::measure(&sim.major_celestial_bodies, &params);
+      let (energy_drift, momentum_drift) = totals.drift_from(&conservation_baseline);
+      draw_text(&format!("Kinetic {:.3e}, potential {:.3e}, total {:.3e} ({:+.4}%), momentum {:.3e} ({:.4}%)", totals.kinetic, totals.potential, totals.energy(), energy_drift, totals.momentum.length(), momentum_drift), hud.left, hud.top + 510., 24., WHITE);
+    }
+    if let Some(home) = &home_body {
+      let (distance, bearing) = distance_and_bearing(sim.ship.borrow().mov.pos, home.borrow().mov.pos);
+      draw_text(&format!("Home {}: {:.0} away, bearing {:.0} deg", home.borrow().name, distance, bearing), hud.left, hud.top + 420., 24., WHITE);
+    }
+    let sim_rate = effective_sim_rate(physics_steps, params.step_dt(), get_frame_time());
+    if sim.ships.len() > 1 {
+      let index = sim.ships.iter().position(|s| Rc::ptr_eq(s, &sim.ship)).unwrap_or(0);
+      draw_text(&format!("Flying {} ({}/{}, Tab for the next)", sim.ship.borrow().name, index + 1, sim.ships.len()), hud.left, hud.top + 450., 24., WHITE);
+    }
+    draw_text(&format!("Sim speed: {:.1} s per real second ({:.1} days/s)", sim_rate, sim_rate / params.day_length), hud.left, hud.top + 360., 24., YELLOW);
+    draw_text(&format!("Scale: {}, time x{}, turn: {:?}, accuracy: {:?}, integrator: {:?}, detail: {:?}, gravity every {}, threads {}", scale, tick, params.turn_model, params.accuracy, params.integrator, detail, params.gravity_every, params.physics_threads), hud.left, hud.top + 30., 24., WHITE);
+    // draw_text(&format!("FPS: {}", get_fps()), hud.left, hud.top + 60., 24., WHITE);
+    if params.config.g < 0. {
+      draw_text("ANTIGRAVITY", -100., hud.top + 60., 24., RED);
+    }
+    if params.gravity_exponent != 2. {
+      draw_text(&format!("GRAVITY ~ 1/r^{}", params.gravity_exponent), -100., hud.top + 90., 24., RED);
+    }
+    let screen = vec2(screen_width(), screen_height());
+    for panel in [&event_log_panel, &elements_panel, &minimap_panel] {
+      if panel.visible && (panel.is_dragged() || panel.rect.contains(Vec2::from(mouse_position()))) {
+        let o = panel.origin(screen);
+        draw_rectangle_lines(o.x, o.y, panel.rect.w, panel.rect.h, 1., DARKGRAY);
+      }
+    }
+    if elements_panel.visible {
+      let origin = elements_panel.origin(screen);
+      let mut row = 0.;
+      for cb in &sim.major_celestial_bodies {
+        let Some(parent) = orbital_parent(cb, &sim.major_celestial_bodies) else { continue };
+        let (b, p) = (cb.borrow(), parent.borrow());
+        let elements = osculating_elements(b.mov.pos - p.mov.pos, b.mov.vel - p.mov.vel, params.config.g * p.mov.mass);
+        draw_text(
+          &format!("{}: e {:.4}, a {:.0}, w {:.1}", b.name, elements.eccentricity, elements.semi_major_axis, elements.argument_of_periapsis.to_degrees()),
+          origin.x, origin.y + INFO_FONT_SIZE + row * INFO_FONT_SIZE, INFO_FONT_SIZE, b.color
+        );
+        row += 1.;
+      }
+    }
+    if minimap_panel.visible {
+      draw_minimap(&sim, minimap_panel.origin(screen), minimap_panel.rect.w, focus, screen * scale, frame_angle);
+    }
+    if let Some(name) = library.list().get(library_cursor) {
+      draw_text(&format!("Library [{}/{}]: {} (PgUp/PgDn, Home to load)", library_cursor + 1, library.list().len(), name), hud.right_column, hud.bottom - 10., INFO_FONT_SIZE, LIGHTGRAY);
+    }
+    if composing {
+      draw_text("COMPOSING (physics paused, arrows pan, drag apsis handles)", -100., hud.top + 150., 24., YELLOW);
+    }
+    let fuel_flow = sim.ship.borrow().fuel_flow;
+    if fuel_flow > 0. {
+      draw_text(&format!("ENGINE ON, fuel flow {:.1}/s", fuel_flow), -100., hud.top + 240., 24., ORANGE);
+    }
+    if let Some((body, moon)) = &locked_pair {
+      draw_text(&format!("LOCKED FRAME {} -> {}", body.borrow().name, moon.borrow().name), -100., hud.top + 210., 24., YELLOW);
+    }
+    if let Some(r) = &replay {
+      let rewind = timeline.start_time().map_or("nothing to rewind to".to_owned(), |start| format!("Ctrl+Y rewinds here, back to {:.0}s", start));
+      draw_text(&format!("REPLAY t={:.1} (Left/Right to scrub, {}, Y to exit)", r.time - recorder.end_time(), rewind), -100., hud.top + 120., 24., YELLOW);
+    }
+    if focus_pause.is_paused() {
+      draw_text("PAUSED (window unfocused, any key to resume)", -100., hud.top + 30., 24., YELLOW);
+    } else if paused {
+      draw_text("PAUSED (P to resume, Period to step)", -100., hud.top + 30., 24., YELLOW);
+    }
+    draw_text(
+      &format!(
+        "Controls: {}{}{}{}{}{}{}{}sensitivity x{}",
+        match (params.mutual_asteroid_gravity, params.barnes_hut) {
+          (true, false) => "belt pairs, ",
+          (true, true) => "belt Barnes-Hut, ",
+          (false, _) => "",
+        },
+        if params.skid_friction > 0. { "skids, " } else { "" },
+        if lock_orbit_scale { "orbit scale lock, " } else { "" },
+        if params.despawn_escaped { "belt cleanup, " } else { "" },
+        if params.merge_bodies { "merging, " } else { "" },
+        if params.tracer_mass_threshold > 0. { "light moons, " } else { "" },
+        if params.ship == ShipConfig::hard() { "hard mode, " } else { "" },
+        if params.invert_turn { "inverted, " } else { "" },
+        params.control_sensitivity
+      ),
+      hud.right_column, hud.top + 60., INFO_FONT_SIZE, LIGHTGRAY
+    );
+    if let Some(a) = &ascent {
+      draw_text(&format!("Ascent autopilot: {:?}", a.phase), hud.right_column, hud.top + 90., INFO_FONT_SIZE, YELLOW);
+    }
+    if let Some(e) = &escape {
+      draw_text(&format!("Escape autopilot: {:.1} delta-v to go", e.remaining), hud.right_column, hud.top + 90., INFO_FONT_SIZE, YELLOW);
+    }
+    if let Some(d) = &docking {
+      draw_text(
+        &format!("{:?} autopilot ({:?}): {:.1} delta-v, {:.0} away, {:.1} days left", d.point, d.phase, d.delta_v, d.distance, d.time_to_arrival(sim.elapsed_seconds) / params.day_length),
+        hud.right_column, hud.top + 90., INFO_FONT_SIZE, YELLOW
+      );
+    }
+    if let Some(hold) = attitude_hold {
+      let heading = match hold {
+        AttitudeHold::Prograde => "prograde",
+        AttitudeHold::Retrograde => "retrograde",
+        AttitudeHold::Target => "toward the target",
+      };
+      draw_text(&format!("Holding {} (Ctrl+O to change)", heading), hud.right_column, hud.top + 270., INFO_FONT_SIZE, YELLOW);
+    }
+    let spawn_body = sim.major_celestial_bodies.get(sim.spawn_body_index).map_or(String::new(), |cb| cb.borrow().name.clone());
+    let best_landing = leaderboard.best(sim.seed).map_or("none".to_owned(), |score| score.to_string());
+    draw_text(&format!("Seed: {}, asteroids: {}, spawn: {}, best landing: {}", seed_history.current(), sim.minor_celestial_bodies.len(), spawn_body, best_landing), hud.left, hud.top + 90., 24., WHITE);
+    let (year, day) = calendar(sim.day_count, params.days_per_year);
+    draw_text(&format!("Year {}, Day {} (day length {})", year, day, params.day_length), hud.right_column, hud.top + 30., 24., WHITE);
+    let shown_throttle = sim.ship.borrow().throttle_level;
+    draw_text(&format!("Throttle: {:.0}% ({:?})", shown_throttle * 100., params.throttle_response), hud.right_column, hud.top + 120., INFO_FONT_SIZE, LIGHTGRAY);
+    draw_rectangle(hud.right - 140., hud.top + 108., 120. * shown_throttle, 14., ORANGE);
+    draw_rectangle_lines(hud.right - 140., hud.top + 108., 120., 14., 1., LIGHTGRAY);
+    let fuel_fraction = sim.ship.borrow().fuel_fraction();
+    draw_text(&format!("Fuel: {:.0}%", fuel_fraction * 100.), hud.right_column, hud.top + 240., INFO_FONT_SIZE, LIGHTGRAY);
+    draw_rectangle(hud.right - 140., hud.top + 228., 120. * fuel_fraction, 14., if fuel_fraction < 0.2 { RED } else { GREEN });
+    draw_rectangle_lines(hud.right - 140., hud.top + 228., 120., 14., 1., LIGHTGRAY);
+    let (dv_used, dv_left, cargo) = (sim.ship.borrow().total_dv_used, sim.ship.borrow().remaining_dv(), sim.ship.borrow().cargo_mass);
+    draw_text(&format!("Delta-v used: {:.1}, remaining: {:.1}, cargo: {:.1}", dv_used, dv_left, cargo), hud.right_column, hud.top + 150., INFO_FONT_SIZE, LIGHTGRAY);
+    if !params.tag_filter.tags.is_empty() {
+      let mode = if params.tag_filter.hide { "hiding" } else { "dimming" };
+      draw_text(&format!("Tag filter: {} ({} the rest)", params.tag_filter.tags.join(", "), mode), hud.right_column, hud.top + 180., INFO_FONT_SIZE, LIGHTGRAY);
+    }
+    match &focus_target {
+      FocusTarget::Ship => {},
+      FocusTarget::Body(body) => draw_text(&format!("Camera on {} (Ctrl+C next body, X back to ship)", body.borrow().name), hud.right_column, hud.top + 210., INFO_FONT_SIZE, LIGHTGRAY),
+      FocusTarget::Free(_) => draw_text("Camera free (Ctrl+C to follow a body, X back to ship)", hud.right_column, hud.top + 210., INFO_FONT_SIZE, LIGHTGRAY),
+    }
+    let origin = event_log_panel.origin(screen);
+    for (i, entry) in event_log.iter().rev().enumerate() {
+      draw_text(entry, origin.x, origin.y + event_log_panel.rect.h - 6. - i as f32 * INFO_FONT_SIZE, INFO_FONT_SIZE, LIGHTGRAY);
+    }
 
     #[cfg(debug_assertions)]
     macroquad_profiler::profiler(Default::default());
@@ -897,3 +3147,743 @@ async fn main() {
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_body(pos: Vec2) -> CelestialBodyReference {
+    Rc::new(RefCell::new(CelestialBody::new(pos, 1000., 50., CelestialBodyType::Planet, BLUE, "Test".to_owned(), &SimParams::default())))
+  }
+
+  #[test]
+  fn turn_models_advance_rot_for_a_held_key() {
+    let dt = 0.1;
+    let instant = SimParams { turn_model: TurnModel::Instant, ..SimParams::default() };
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &instant.ship, "Test".to_owned(), WHITE);
+    for _ in 0..5 {
+      ship.turn_right(&instant, dt);
+    }
+    // at once at the turn rate, with nothing left spinning
+    assert!((ship.mov.rot - (instant.ship.turn_rate * 5. * dt).to_radians()).abs() < 1e-5);
+    assert_eq!(ship.mov.rot_vel, 0.);
+
+    let inertial = SimParams { turn_model: TurnModel::Inertial, ..SimParams::default() };
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &inertial.ship, "Test".to_owned(), WHITE);
+    for _ in 0..5 {
+      ship.turn_left(&inertial, dt);
+      ship.update(inertial.integrator, dt);
+    }
+    // the spin builds up by the rotational acceleration each step: 1 + 2 + .. + 5 increments
+    let expected = -(SHIP_ROT_ACCELERATION * dt * dt * 15.).to_radians();
+    assert!((ship.mov.rot - expected).abs() < 1e-5, "{} vs {}", ship.mov.rot, expected);
+    assert!((ship.mov.rot_vel + (SHIP_ROT_ACCELERATION * 5. * dt).to_radians()).abs() < 1e-5);
+  }
+
+  #[test]
+  fn land_accepts_rotation_past_a_full_turn() {
+    let cb = test_body(Vec2::ZERO);
+    // on the +x side of the body the surface normal is at angle 0
+    let mut ship = Ship::new(vec2(60., 0.), Vec2::ZERO, &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.mov.rot = 2. * std::f32::consts::PI + 0.05;
+    ship.land(cb, &SimConfig::default());
+    assert!(matches!(ship.state, ShipState::Landed(..)));
+  }
+
+  #[test]
+  fn predicted_touchdown_pose_is_the_last_simulated_step() {
+    let params = SimParams::default();
+    let star = test_body(Vec2::ZERO);
+    let planet = test_body(vec2(1000., 0.));
+    let mut ship = Ship::new(vec2(1200., 0.), vec2(-100., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.mov.rot = 1.;
+    ship.in_hill_radius_of = vec![planet.clone()];
+    let ships = vec![Rc::new(RefCell::new(ship))];
+    let prediction = simulate_hill_radius(&ships, &[star, planet], None, 200, params.config.g, 2., 0., &[], params.integrator, SIMULATION_STEP);
+
+    assert_eq!(prediction.touchdowns.len(), 1);
+    let (last, color, _) = prediction.trails.last().unwrap().last().unwrap();
+    assert_eq!(*color, ORANGE);
+    assert_eq!(prediction.touchdowns[0].pos, *last);
+    assert_eq!(prediction.touchdowns[0].rot, 1.);
+    assert_eq!(ships[0].borrow().mov.pos, vec2(1200., 0.));
+  }
+
+  #[test]
+  fn a_prediction_step_that_jumps_past_a_body_still_touches_down() {
+    let params = SimParams::default();
+    let star = test_body(Vec2::ZERO);
+    let planet = test_body(vec2(1000., 0.));
+    // one prediction step carries the ship from 150 short of the centre to 350 beyond it
+    let mut ship = Ship::new(vec2(1650., 0.), vec2(-1000., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.in_hill_radius_of = vec![planet.clone()];
+    let ships = vec![Rc::new(RefCell::new(ship))];
+    let prediction = simulate_hill_radius(&ships, &[star, planet], None, 200, params.config.g, 2., 0., &[], params.integrator, SIMULATION_STEP);
+    assert_eq!(prediction.touchdowns.len(), 1);
+  }
+
+  fn pull_on_first(bodies: &[CelestialBodyReference], scales: &[PairScale]) -> Vec2 {
+    let g = SimConfig::default().g;
+    for cb in bodies {
+      cb.borrow_mut().mov.acc = Vec2::ZERO;
+    }
+    apply_gravity_to_celestial_bodies(bodies, g, 2., 0., scales);
+    bodies[0].borrow().mov.acc
+  }
+
+  #[test]
+  fn body_gravity_does_not_depend_on_the_body_order() {
+    let mut rng = SeededRng::new(11);
+    let bodies: Vec<CelestialBodyReference> = (0..12).map(|_| {
+      let body = test_body(vec2(rng.gen_range(-5000., 5000.), rng.gen_range(-5000., 5000.)));
+      body.borrow_mut().mov.mass = rng.gen_range(10., 100000.);
+      body
+    }).collect();
+    let accelerations = |order: &[CelestialBodyReference]| -> Vec<Vec2> {
+      for cb in order {
+        cb.borrow_mut().mov.acc = Vec2::ZERO;
+      }
+      apply_gravity_to_celestial_bodies(order, SimConfig::default().g, 2., 0., &[]);
+      bodies.iter().map(|cb| cb.borrow().mov.acc).collect()
+    };
+    let reference = accelerations(&bodies);
+    let mut shuffled = bodies.clone();
+    for i in (1..shuffled.len()).rev() {
+      shuffled.swap(i, rng.gen_index(i + 1));
+    }
+    let reversed: Vec<CelestialBodyReference> = bodies.iter().rev().cloned().collect();
+    for order in [shuffled, reversed] {
+      for (a, b) in reference.iter().zip(accelerations(&order)) {
+        assert!(a.distance(b) <= 1e-5 * a.length().max(1e-6), "{} vs {}", a, b);
+      }
+    }
+  }
+
+  #[test]
+  fn pair_scale_changes_only_its_own_pair() {
+    let (a, b, c) = (test_body(Vec2::ZERO), test_body(vec2(100., 0.)), test_body(vec2(0., 100.)));
+    let scales = [PairScale { a: b.borrow().id, b: a.borrow().id, scale: 0.5 }];
+    let ab = [a.clone(), b.clone()];
+    assert_eq!(pull_on_first(&ab, &scales), pull_on_first(&ab, &[]) * 0.5);
+    let ac = [a.clone(), c.clone()];
+    assert_eq!(pull_on_first(&ac, &scales), pull_on_first(&ac, &[]));
+  }
+
+  #[test]
+  fn pair_scale_reaches_tracers_and_the_belt() {
+    let g = SimConfig::default().g;
+    let (star, tracer) = (test_body(Vec2::ZERO), test_body(vec2(100., 0.)));
+    let (stars, tracers) = ([star.clone()], [tracer.clone()]);
+    let scales = [PairScale { a: tracer.borrow().id, b: star.borrow().id, scale: 0.5 }];
+    apply_gravity_to_tracers(&tracers, &stars, g, 2., 0., &[]);
+    let plain = replace(&mut tracer.borrow_mut().mov.acc, Vec2::ZERO);
+    apply_gravity_to_tracers(&tracers, &stars, g, 2., 0., &scales);
+    assert_eq!(tracer.borrow().mov.acc, plain * 0.5);
+
+    tracer.borrow_mut().mov.acc = Vec2::ZERO;
+    apply_gravity_asteroids(&tracers, &star, g, 2., 0., &scales, 1);
+    assert_eq!(tracer.borrow().mov.acc, plain * 0.5);
+  }
+
+  #[test]
+  fn pure_thrust_reads_as_thrust_over_mass() {
+    let params = SimParams::default();
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.throttle_up(&params, PHYSICS_STEP);
+    ship.sample_g_force(PHYSICS_STEP);
+    assert!((ship.g_force * STANDARD_GRAVITY - SHIP_ACCELERATION / ship.mov.mass).abs() < 1e-3);
+  }
+
+  #[test]
+  fn land_rejects_rotation_past_the_tolerance() {
+    let cb = test_body(Vec2::ZERO);
+    let mut ship = Ship::new(vec2(60., 0.), Vec2::ZERO, &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.mov.rot = -2. * std::f32::consts::PI + (SimConfig::default().max_landing_angle + 5.).to_radians();
+    ship.land(cb, &SimConfig::default());
+    assert!(matches!(ship.state, ShipState::Destroyed));
+  }
+
+  #[test]
+  fn a_seed_always_spawns_near_the_same_body() {
+    let params = SimParams::default();
+    let spawns: Vec<usize> = (0..8).map(|seed| Simulation::new(seed, &params).spawn_body_index).collect();
+    let again: Vec<usize> = (0..8).map(|seed| Simulation::new(seed, &params).spawn_body_index).collect();
+    assert_eq!(spawns, again);
+    assert!(spawns.iter().any(|&i| i != spawns[0]));
+  }
+
+  fn belt_snapshots(n: usize) -> Vec<(Vec2, f32)> {
+    (0..n).map(|i| (vec2(1000. + i as f32 * 37., i as f32 * -53.), 1.)).collect()
+  }
+
+  #[test]
+  fn threaded_belt_pass_matches_the_serial_one() {
+    let g = SimConfig::default().g;
+    let snapshots = belt_snapshots(1000);
+    let serial = compute_belt_accelerations(&snapshots, (Vec2::ZERO, 1000.), g, 2., 0.);
+    for threads in [2, 3, 8] {
+      assert_eq!(compute_belt_accelerations_threaded(&snapshots, (Vec2::ZERO, 1000.), g, 2., 0., threads), serial);
+    }
+
+    let star = test_body(Vec2::ZERO);
+    let asteroids: Vec<CelestialBodyReference> = snapshots.iter().map(|(pos, _)| test_body(*pos)).collect();
+    apply_gravity_asteroids(&asteroids, &star, g, 2., 0., &[], 4);
+    for a in &asteroids {
+      let a = a.borrow();
+      assert_eq!(a.mov.acc, gravity_acc(a.mov.pos, a.mov.mass, Vec2::ZERO, 1000., g, 2., 0.).0);
+    }
+  }
+
+  // cargo test --release belt_pass_benchmark -- --ignored --nocapture
+  #[test]
+  #[ignore]
+  fn belt_pass_benchmark() {
+    let g = SimConfig::default().g;
+    let snapshots = belt_snapshots(1000);
+    let runs = 1000;
+    for threads in [1, 2, 4, 8] {
+      let start = std::time::Instant::now();
+      for _ in 0..runs {
+        std::hint::black_box(compute_belt_accelerations_threaded(&snapshots, (Vec2::ZERO, 1000.), g, 2., 0., threads));
+      }
+      println!("1000 asteroids, {} threads: {:.3} ms per pass", threads, start.elapsed().as_secs_f64() * 1000. / runs as f64);
+    }
+  }
+
+  #[test]
+  fn hill_radius_uses_the_orbit_distance_away_from_the_origin() {
+    let params = SimParams::default();
+    let star = CelestialBody::new(vec2(50000., -30000.), 30000000., 7000., CelestialBodyType::Star, ORANGE, "Star".to_owned(), &params);
+    let planet = CelestialBody::from_parent(&star, 20000., 40., 30000., 500., CelestialBodyType::Planet, BLUE, "Planet".to_owned(), &params);
+    let expected = 20000. * (30000f32 / (3. * 30000000.)).cbrt();
+    assert!((planet.hill_radius - expected).abs() < 1e-3 * expected, "{} vs {}", planet.hill_radius, expected);
+  }
+
+  #[test]
+  fn sim_rate_is_simulated_time_over_frame_time() {
+    assert_eq!(effective_sim_rate(0, 0.01, 1. / 60.), 0.);
+    assert!((effective_sim_rate(4, 0.01, 0.02) - 2.).abs() < 1e-6);
+    // four substeps of a quarter step advance as far as one whole step
+    assert!((effective_sim_rate(4, 0.0025, 0.01) - effective_sim_rate(1, 0.01, 0.01)).abs() < 1e-6);
+    assert_eq!(effective_sim_rate(10, 0.01, 0.), 0.);
+  }
+
+  #[test]
+  fn trail_polyline_passes_through_the_points_in_order() {
+    let points = [vec2(0., 0.), vec2(10., 5.), vec2(20., -3.), vec2(25., 10.), vec2(18., 30.)];
+    let line = trail_polyline(&points);
+    assert_eq!(line.len(), (points.len() - 1) * TRAIL_SUBDIVISIONS + 1);
+    for (i, p) in points.iter().enumerate() {
+      assert_eq!(line[i * TRAIL_SUBDIVISIONS], *p);
+    }
+    assert_eq!(trail_polyline(&points[..2]), points[..2]);
+  }
+
+  #[test]
+  fn distance_and_bearing_to_a_fixed_home() {
+    let home = test_body(vec2(100., 200.));
+    let bearing_to = |from: Vec2| distance_and_bearing(from, home.borrow().mov.pos);
+    let (distance, bearing) = bearing_to(vec2(100., 100.));
+    assert_eq!((distance, bearing), (100., 90.));
+    let (distance, bearing) = bearing_to(vec2(130., 240.));
+    assert!((distance - 50.).abs() < 1e-4);
+    assert!((bearing - (180. + 53.130_1)).abs() < 1e-3);
+    assert_eq!(bearing_to(vec2(200., 200.)).1, 180.);
+    assert_eq!(bearing_to(vec2(0., 200.)).1, 0.);
+  }
+
+  #[test]
+  fn a_seed_and_belt_params_always_give_the_same_belt() {
+    let belt_positions = |seed, params: &SimParams| -> Vec<Vec2> {
+      Simulation::new(seed, params).minor_celestial_bodies.iter().map(|a| a.borrow().mov.pos).collect()
+    };
+    let params = SimParams::default();
+    let belt = belt_positions(3, &params);
+    assert!(!belt.is_empty());
+    assert_eq!(belt, belt_positions(3, &params));
+    assert_ne!(belt, belt_positions(4, &params));
+
+    let sparse = SimParams { belt: params::BeltParams { sectors: 90, per_sector: (1, 1), ..Default::default() }, ..SimParams::default() };
+    let sparse_belt = belt_positions(3, &sparse);
+    assert_eq!(sparse_belt.len(), 90);
+    assert_eq!(sparse_belt, belt_positions(3, &sparse));
+    // swapped bounds draw from the same range
+    let swapped = SimParams { belt: params::BeltParams { per_sector: (4, 1), ..Default::default() }, ..SimParams::default() };
+    assert_eq!(belt_positions(3, &swapped), belt);
+  }
+
+  #[test]
+  fn illumination_falls_off_with_the_inverse_square() {
+    let star = vec2(100., 100.);
+    let near = illumination(star + vec2(1000., 0.), star, 1000000.);
+    assert!((near - 1.).abs() < 1e-6);
+    assert!((illumination(star + vec2(0., -2000.), star, 1000000.) - near / 4.).abs() < 1e-6);
+    assert!((illumination(star + vec2(3000., 4000.) * 2., star, 1000000.) - near / 100.).abs() < 1e-6);
+  }
+
+  #[test]
+  fn a_larger_collision_radius_touches_down_further_out() {
+    let body = test_body(Vec2::ZERO);
+    let mut ship = Ship::new(vec2(85., 0.), vec2(-100., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    // the step ends 25 above the surface of the radius 50 body
+    assert!(!ship.check_collision(Vec2::ZERO, &body.borrow(), 0.1));
+    ship.collision_radius = 30.;
+    assert!(ship.check_collision(Vec2::ZERO, &body.borrow(), 0.1));
+  }
+
+  #[test]
+  fn depth_scales_only_the_drawing() {
+    let run = |depth: f32| {
+      let star = test_body(Vec2::ZERO);
+      star.borrow_mut().mov.mass = 1000000.;
+      let asteroid = test_body(vec2(3000., 0.));
+      asteroid.borrow_mut().mov.vel = vec2(0., 40.);
+      asteroid.borrow_mut().depth = depth;
+      let bodies = [star, asteroid.clone()];
+      for _ in 0..50 {
+        apply_gravity_to_celestial_bodies(&bodies, SimConfig::default().g, 2., 0., &[]);
+        let acc = asteroid.borrow().mov.acc;
+        for cb in &bodies {
+          cb.borrow_mut().update(IntegratorKind::SemiImplicitEuler, 0.1);
+        }
+        assert_ne!(acc, Vec2::ZERO);
+      }
+      let mov = asteroid.borrow().mov.clone();
+      (mov.pos, mov.vel)
+    };
+    assert_eq!(run(-1.), run(1.));
+    assert_eq!(depth_scale(0.), 1.);
+    assert!(depth_scale(-1.) < 1. && depth_scale(1.) > 1.);
+  }
+
+  #[test]
+  fn a_hard_body_survives_a_landing_a_default_body_rejects() {
+    let config = SimConfig::default();
+    let touch_down = |cb: CelestialBodyReference| {
+      let mut ship = Ship::new(vec2(60., 0.), vec2(-config.max_landing_speed * 2., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+      ship.land(cb, &config);
+      ship.state
+    };
+    assert!(matches!(touch_down(test_body(Vec2::ZERO)), ShipState::Destroyed));
+    let hard = test_body(Vec2::ZERO);
+    hard.borrow_mut().max_landing_speed = config.max_landing_speed * 3.;
+    assert!(matches!(touch_down(hard), ShipState::Landed(..)));
+  }
+
+  #[test]
+  fn future_position_starts_in_place_and_reaches_the_opposite_point_at_half_period() {
+    let g = SimConfig::default().g;
+    let star = test_body(Vec2::ZERO);
+    star.borrow_mut().mov.mass = 1000000.;
+    star.borrow_mut().hill_radius = 100000.;
+    let planet = test_body(vec2(1000., 0.));
+    let speed = (g * 1000000. / 1000_f32).sqrt();
+    planet.borrow_mut().mov.vel = vec2(0., speed);
+    let bodies = [star, planet.clone()];
+
+    assert_eq!(future_position(&planet, &bodies, 0.), vec2(1000., 0.));
+    let half_period = std::f32::consts::PI * 1000. / speed;
+    let opposite = future_position(&planet, &bodies, half_period);
+    assert!((opposite - vec2(-1000., 0.)).length() < 0.1, "{:?}", opposite);
+  }
+
+  #[test]
+  fn closing_rate_is_negative_approaching_and_positive_receding() {
+    let body = test_body(Vec2::ZERO);
+    let approaching = Ship::new(vec2(500., 0.), vec2(-20., 5.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    let receding = Ship::new(vec2(500., 0.), vec2(20., 5.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    assert_eq!(closing_rate(&approaching, &body.borrow()), -20.);
+    assert_eq!(closing_rate(&receding, &body.borrow()), 20.);
+  }
+
+  #[test]
+  fn snapped_scale_draws_the_body_at_the_target_radius() {
+    let radii = DisplayRadii::default();
+    let body = CelestialBody::new(Vec2::ZERO, 1000., 5000., CelestialBodyType::Planet, BLUE, "Test".to_owned(), &SimParams::default());
+    let scale = scale_for_body(body.radius, 200.);
+    assert!((body.display_radius(scale, &radii) - 200.).abs() < 0.01);
+  }
+
+  #[test]
+  fn inverted_controls_swap_the_rotation_of_a_key() {
+    let rotation_for_a = |invert_turn: bool| {
+      let params = SimParams { invert_turn, turn_model: TurnModel::Instant, ..SimParams::default() };
+      let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+      let (left_key, _) = turn_keys(params.invert_turn);
+      if left_key == KeyCode::A {
+        ship.turn_left(&params, 0.1);
+      } else {
+        ship.turn_right(&params, 0.1);
+      }
+      normalize_angle(ship.mov.rot)
+    };
+    let normal = rotation_for_a(false);
+    assert!(normal < 0.);
+    assert!((rotation_for_a(true) + normal).abs() < 1e-5);
+  }
+
+  #[test]
+  fn negative_g_pushes_two_bodies_apart() {
+    let (a, b) = (test_body(vec2(-100., 0.)), test_body(vec2(100., 0.)));
+    let bodies = [a.clone(), b.clone()];
+    apply_gravity_to_celestial_bodies(&bodies, -SimConfig::default().g, 2., 0., &[]);
+    assert!(a.borrow().mov.acc.x < 0. && b.borrow().mov.acc.x > 0.);
+    for cb in &bodies {
+      cb.borrow_mut().update(IntegratorKind::SemiImplicitEuler, 0.1);
+    }
+    assert!(a.borrow().mov.pos.distance(b.borrow().mov.pos) > 200.);
+  }
+
+  #[test]
+  fn half_throttle_gives_half_the_delta_v_of_full_throttle() {
+    let params = SimParams::default();
+    let burn = |amount: f32| {
+      let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+      for _ in 0..20 {
+        ship.throttle(amount, &params, 0.1);
+      }
+      ship.mov.vel.length()
+    };
+    assert!((burn(0.5) - burn(1.) / 2.).abs() < 1e-4);
+  }
+
+  #[test]
+  fn zoom_easing_reaches_the_target_within_two_seconds() {
+    for (from, to) in [(1., MAX_SCALE), (MAX_SCALE, MIN_SCALE)] {
+      let mut scale = from;
+      let steps = (0..120).position(|_| {
+        scale = ease_toward(scale, to, ZOOM_EASE_RATE, 1. / 60.);
+        scale == to
+      });
+      assert!(steps.is_some(), "stuck at {} on the way to {}", scale, to);
+    }
+  }
+
+  #[test]
+  fn spawned_ships_get_distinct_palette_colors() {
+    let params = SimParams::default();
+    let body = test_body(Vec2::ZERO);
+    let mut rng = SeededRng::new(1);
+    let (first, second) = (spawn_ship_near(&body, 0, &params, &mut rng), spawn_ship_near(&body, 1, &params, &mut rng));
+    assert!(SHIP_PALETTE.contains(&first.color) && SHIP_PALETTE.contains(&second.color));
+    assert_ne!(first.color, second.color);
+    assert_ne!(first.name, second.name);
+  }
+
+  #[test]
+  fn burn_preview_points_along_the_heading_and_scales_with_duration() {
+    let params = SimParams::default();
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    ship.mov.rot = 1.;
+    let (short, long) = (ship.burn_preview(1., 2., &params), ship.burn_preview(1., 4., &params));
+    assert!(short.normalize().distance(vec2(1_f32.cos(), 1_f32.sin())) < 1e-5);
+    assert!((long - short * 2.).length() < 1e-4);
+  }
+
+  #[test]
+  fn the_asteroid_cap_is_never_exceeded_and_keeps_the_belt_spread_out() {
+    for max_asteroids in [50, 200] {
+      let params = SimParams { max_asteroids, ..SimParams::default() };
+      let sim = Simulation::new(5, &params);
+      assert!(sim.minor_celestial_bodies.len() <= max_asteroids);
+      let star = sim.cb_parent.borrow().mov.pos;
+      let mut angles: Vec<f32> = sim.minor_celestial_bodies.iter()
+        .map(|a| {
+          let rel = a.borrow().mov.pos - star;
+          rel.y.atan2(rel.x)
+        })
+        .collect();
+      angles.sort_by(f32::total_cmp);
+      let wrap_gap = angles[0] + std::f32::consts::TAU - angles[angles.len() - 1];
+      let widest = angles.windows(2).map(|w| w[1] - w[0]).fold(wrap_gap, f32::max);
+      // an even spread leaves gaps near TAU / n, allow a few times that for the sector jitter
+      assert!(widest < 4. * std::f32::consts::TAU / max_asteroids as f32, "gap {} with {}", widest, max_asteroids);
+    }
+  }
+
+  #[test]
+  fn a_body_placed_on_a_circular_orbit_reads_zero_eccentricity() {
+    let params = SimParams::default();
+    let star = CelestialBody::new(Vec2::ZERO, 30000000., 7000., CelestialBodyType::Star, ORANGE, "Star".to_owned(), &params);
+    let planet = CelestialBody::from_parent(&star, params.config.au, 40., 30000., 500., CelestialBodyType::Planet, BLUE, "Planet".to_owned(), &params);
+    let elements = osculating_elements(planet.mov.pos - star.mov.pos, planet.mov.vel - star.mov.vel, params.config.g * star.mov.mass);
+    assert!(elements.eccentricity < 1e-3, "e = {}", elements.eccentricity);
+    assert!((elements.semi_major_axis - params.config.au).abs() < 1e-2 * params.config.au);
+  }
+
+  #[test]
+  fn kill_rotation_stops_the_spin_in_bounded_time() {
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.mov.rot_vel = -SHIP_ROT_SPEED.to_radians();
+    let dt = 0.1;
+    // a full-rate spin stops in turn rate / deceleration seconds
+    let bound = (SHIP_ROT_SPEED / KILL_ROTATION_ACCELERATION / dt).ceil() as usize;
+    for _ in 0..bound {
+      ship.kill_rotation(dt);
+    }
+    assert_eq!(ship.mov.rot_vel, 0.);
+    assert!(ship.fuel < ship.max_fuel);
+  }
+
+  #[test]
+  fn a_hard_scenario_spawns_a_ship_with_its_fuel_and_thrust() {
+    let params = SimParams { ship: ShipConfig::hard(), ..SimParams::default() };
+    let sim = Simulation::new(1, &params);
+    let mut ship = sim.ship.borrow_mut();
+    assert_eq!((ship.fuel, ship.max_fuel), (params.ship.fuel, params.ship.fuel));
+    ship.state = ShipState::InSpace;
+    let vel = ship.mov.vel;
+    ship.throttle(1., &params, 0.1);
+    assert!(((ship.mov.vel - vel).length() - params.ship.thrust * 0.1 / ship.mov.mass).abs() < 1e-4);
+    assert_eq!(ship.fuel, params.ship.fuel - params.ship.thrust * 0.1);
+  }
+
+  #[test]
+  fn a_tracer_feels_gravity_without_pulling_back() {
+    let g = SimConfig::default().g;
+    let star = test_body(Vec2::ZERO);
+    star.borrow_mut().mov.mass = 1000000.;
+    let moon = test_body(vec2(1000., 0.));
+    moon.borrow_mut().mov.mass = 10.;
+    let (massive, tracers) = partition_by_mass(&[star.clone(), moon.clone()], 1000.);
+    assert!(massive.len() == 1 && Rc::ptr_eq(&massive[0], &star));
+    assert!(tracers.len() == 1 && Rc::ptr_eq(&tracers[0], &moon));
+
+    apply_gravity_to_celestial_bodies(&massive, g, 2., 0., &[]);
+    apply_gravity_to_tracers(&tracers, &massive, g, 2., 0., &[]);
+    assert_eq!(star.borrow().mov.acc, Vec2::ZERO);
+    assert!(moon.borrow().mov.acc.x < 0.);
+  }
+
+  #[test]
+  fn spool_up_starts_below_full_thrust_and_reaches_it_over_the_spool_time() {
+    let params = SimParams { throttle_response: ThrottleResponse::SpoolUp, ..SimParams::default() };
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    let dt = 0.1;
+    ship.throttle(1., &params, dt);
+    assert!(ship.throttle_level > 0. && ship.throttle_level < 1.);
+    assert!(ship.fuel_flow < ship.thrust);
+    for _ in 1..(THROTTLE_SPOOL_TIME / dt).round() as usize {
+      ship.throttle(1., &params, dt);
+    }
+    assert!((ship.throttle_level - 1.).abs() < 1e-5);
+    ship.throttle(1., &params, dt);
+    assert_eq!((ship.throttle_level, ship.fuel_flow), (1., ship.thrust));
+  }
+
+  #[test]
+  fn a_burn_adds_its_delta_v_to_the_used_budget() {
+    let params = SimParams::default();
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    for _ in 0..10 {
+      ship.throttle(1., &params, 0.1);
+    }
+    let expected = params.ship.thrust * 1. / ship.mov.mass;
+    assert!((ship.total_dv_used - expected).abs() < 1e-4);
+    assert!((ship.total_dv_used - ship.mov.vel.length()).abs() < 1e-4);
+  }
+
+  #[test]
+  fn a_circularly_orbiting_moon_stands_still_in_the_locked_frame() {
+    let params = SimParams::default();
+    let planet = wrap_object(CelestialBody::new(vec2(500., -200.), 1000000., 100., CelestialBodyType::Planet, BLUE, "Planet".to_owned(), &params));
+    planet.borrow_mut().mov.vel = vec2(30., 10.);
+    let moon = wrap_object(CelestialBody::from_parent(&planet.borrow(), 2000., 70., 10., 20., CelestialBodyType::Moon, GRAY, "Moon".to_owned(), &params));
+    let bodies = [planet.clone(), moon.clone()];
+    let in_frame = || {
+      let (p, m) = (planet.borrow(), moon.borrow());
+      rotate_vec2_by_rad(&(m.mov.pos - p.mov.pos), -locked_frame_angle(&p, &m))
+    };
+    let start = in_frame();
+    let start_angle = locked_frame_angle(&planet.borrow(), &moon.borrow());
+    for _ in 0..2000 {
+      apply_gravity_to_celestial_bodies(&bodies, params.config.g, 2., 0., &[]);
+      for cb in &bodies {
+        cb.borrow_mut().update(IntegratorKind::SemiImplicitEuler, 0.01);
+      }
+      assert!(in_frame().distance(start) < 1., "{:?} drifted from {:?}", in_frame(), start);
+    }
+    // meanwhile the moon went a quarter of the way round in the world
+    let turned = normalize_angle(locked_frame_angle(&planet.borrow(), &moon.borrow()) - start_angle);
+    assert!(turned.abs() > 45_f32.to_radians());
+  }
+
+  #[test]
+  fn fuel_flow_matches_the_fuel_spent_and_is_zero_when_idle() {
+    let params = SimParams::default();
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    let dt = 0.1;
+    let before = ship.fuel;
+    ship.throttle(0.5, &params, dt);
+    assert!(ship.fuel_flow > 0.);
+    assert!((ship.fuel_flow * dt - (before - ship.fuel)).abs() < 1e-4);
+    let before = ship.fuel;
+    ship.throttle(0., &params, dt);
+    assert_eq!((ship.fuel_flow, ship.fuel), (0., before));
+  }
+
+  #[test]
+  fn a_destroyed_ship_burns_no_fuel_and_gains_no_delta_v() {
+    let params = SimParams::default();
+    let mut ship = Ship::new(Vec2::ZERO, Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    ship.state = ShipState::Destroyed;
+    let fuel = ship.fuel;
+    for _ in 0..10 {
+      ship.throttle(1., &params, 0.1);
+    }
+    assert_eq!((ship.fuel, ship.fuel_flow, ship.total_dv_used), (fuel, 0., 0.));
+    assert_eq!(ship.mov.vel, Vec2::ZERO);
+  }
+
+  #[test]
+  fn orbit_scale_fits_the_orbit_diameter_with_a_margin() {
+    for viewport in [vec2(800., 600.), vec2(1080., 1920.)] {
+      let semi_major_axis = 20000.;
+      let on_screen = semi_major_axis * 2. / scale_for_orbit(semi_major_axis, viewport);
+      assert!(on_screen < viewport.min_element());
+      assert!(on_screen > viewport.min_element() * 0.75);
+    }
+  }
+
+  #[test]
+  fn a_fast_grazing_pass_is_predicted_with_low_confidence() {
+    let params = SimParams::default();
+    let star = test_body(Vec2::ZERO);
+    let planet = test_body(vec2(1000., 0.));
+    let mut ship = Ship::new(vec2(700., 75.), vec2(200., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.in_hill_radius_of = vec![planet.clone()];
+    let ships = vec![Rc::new(RefCell::new(ship))];
+    let prediction = simulate_hill_radius(&ships, &[star, planet], None, 200, params.config.g, 2., 0., &[], params.integrator, SIMULATION_STEP);
+
+    let approach = prediction.closest_approaches[0].as_ref().unwrap();
+    assert!(approach.clearance > 0. && approach.clearance < 25.);
+    assert!(prediction_confidence(approach.clearance, SIMULATION_STEP, approach.speed) < 0.5);
+    // the same clearance passed slowly leaves little room for a miss between samples
+    assert_eq!(prediction_confidence(approach.clearance, SIMULATION_STEP, 5.), 1.);
+  }
+
+  #[test]
+  fn cargo_slows_the_thrust_until_it_is_delivered() {
+    let params = SimParams::default();
+    let (origin, destination) = (test_body(Vec2::ZERO), test_body(vec2(5000., 0.)));
+    let mut ship = Ship::new(vec2(55., 0.), Vec2::ZERO, &params.ship, "Test".to_owned(), WHITE);
+    let empty = ship.burn_preview(1., 1., &params).length();
+    ship.state = ShipState::Landed(origin, Vec2::ZERO, vec2(55., 0.));
+    ship.load_cargo(CARGO_UNIT * 2.).unwrap();
+    let loaded = ship.burn_preview(1., 1., &params).length();
+    assert!((loaded - empty * SHIP_DRY_MASS / (SHIP_DRY_MASS + CARGO_UNIT * 2.)).abs() < 1e-4);
+
+    ship.state = ShipState::Landed(destination, Vec2::ZERO, vec2(55., 0.));
+    assert_eq!(ship.deliver_cargo(), Ok(CARGO_UNIT * 2.));
+    assert_eq!(ship.burn_preview(1., 1., &params).length(), empty);
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  fn teleporting_puts_the_ship_on_a_circular_orbit_around_the_body() {
+    let config = SimConfig::default();
+    let body = test_body(vec2(3000., -2000.));
+    body.borrow_mut().mov.vel = vec2(40., 25.);
+    let mut ship = Ship::new(vec2(-500., 900.), vec2(70., 0.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    let b = body.borrow();
+    teleport_to_orbit(&mut ship, &b, b.radius * TELEPORT_ORBIT_RADIUS_FACTOR, &config);
+    let elements = osculating_elements(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, config.g * b.mov.mass);
+    assert!(elements.eccentricity < 1e-3, "e = {}", elements.eccentricity);
+    assert!((ship.mov.pos.distance(b.mov.pos) - b.radius * TELEPORT_ORBIT_RADIUS_FACTOR).abs() < 1e-2);
+    assert!(matches!(ship.state, ShipState::InSpace));
+  }
+
+  #[test]
+  fn circularizing_gives_the_circular_speed_perpendicular_to_the_radius() {
+    let g = SimConfig::default().g;
+    let parent = test_body(vec2(200., 100.));
+    parent.borrow_mut().mov.mass = 1000000.;
+    parent.borrow_mut().mov.vel = vec2(5., -3.);
+    let body = test_body(vec2(2200., 100.));
+    body.borrow_mut().mov.vel = vec2(13., 120.);
+    circularize_body(&mut body.borrow_mut(), &parent.borrow(), g);
+
+    let (b, p) = (body.borrow(), parent.borrow());
+    let (rel_pos, rel_vel) = (b.mov.pos - p.mov.pos, b.mov.vel - p.mov.vel);
+    assert!((rel_vel.length() - (g * p.mov.mass / rel_pos.length()).sqrt()).abs() < 1e-3);
+    assert!(rel_vel.dot(rel_pos).abs() < 1e-2);
+    // keeps going the same way round
+    assert!(rel_pos.perp_dot(rel_vel) > 0.);
+  }
+
+  #[test]
+  fn the_asteroid_minimum_sets_the_drawn_radius_when_zoomed_out() {
+    let asteroid = CelestialBody::new(Vec2::ZERO, 50., 20., CelestialBodyType::Asteroid, GRAY, "Test".to_owned(), &SimParams::default());
+    let mut radii = DisplayRadii::default();
+    assert_eq!(asteroid.display_radius(1000., &radii), 1.);
+    radii.asteroid = radii.next_asteroid();
+    assert_eq!(asteroid.display_radius(1000., &radii), 2.);
+    // zoomed in far enough the true size wins again
+    assert_eq!(asteroid.display_radius(1., &radii), 20.);
+  }
+
+  #[test]
+  fn an_eccentric_spawn_has_the_requested_apsides() {
+    let params = SimParams { ship: ShipConfig::hard(), ..SimParams::default() };
+    let body = test_body(vec2(100., 200.));
+    body.borrow_mut().mov.mass = 1000000.;
+    let ship = spawn_ship_near(&body, 0, &params, &mut SeededRng::new(3));
+    let b = body.borrow();
+    let elements = osculating_elements(ship.mov.pos - b.mov.pos, ship.mov.vel - b.mov.vel, params.config.g * b.mov.mass);
+    let (periapsis, apoapsis) = params.ship.spawn_orbit;
+    assert!((elements.periapsis - b.radius * periapsis).abs() < 1e-2 * b.radius);
+    assert!((elements.apoapsis.unwrap() - b.radius * apoapsis).abs() < 1e-2 * b.radius);
+  }
+
+  #[test]
+  fn glow_is_only_drawn_for_stars() {
+    let params = SimParams::default();
+    let bodies: Vec<CelestialBody> = [CelestialBodyType::Star, CelestialBodyType::Planet, CelestialBodyType::Moon, CelestialBodyType::Asteroid]
+      .into_iter()
+      .map(|cb_type| CelestialBody::new(Vec2::ZERO, 1000., 50., cb_type, YELLOW, "Test".to_owned(), &params))
+      .collect();
+    let glows = |params: &SimParams| bodies.iter().filter(|b| b.draws_glow(params)).count();
+    assert_eq!(glows(&params), 1);
+    assert!(bodies[0].draws_glow(&params));
+
+    let dark = SimParams { star_glow: false, ..SimParams::default() };
+    assert_eq!(glows(&dark), 0);
+  }
+
+  #[test]
+  fn atmosphere_is_only_shaded_for_bodies_that_have_one() {
+    let airless = test_body(Vec2::ZERO);
+    assert_eq!(airless.borrow().atmosphere_radius(2.), None);
+
+    let planet = test_body(Vec2::ZERO);
+    planet.borrow_mut().atmosphere_height = Some(60.);
+    assert_eq!(planet.borrow().atmosphere_radius(2.), Some((50. + 60.) / 2.));
+  }
+
+  fn envelope_width(ship: Ship, bodies: &[CelestialBodyReference]) -> f32 {
+    let params = SimParams::default();
+    let ship = Rc::new(RefCell::new(ship));
+    let (inner, outer) = prediction_envelope(&ship, bodies, ENVELOPE_RUNS, SIMULATION_STEP, 200, params.config.g, 2., 0., &[], params.integrator);
+    assert!(!inner.is_empty());
+    inner.iter().zip(&outer).map(|(i, o)| i.distance(*o)).fold(0., f32::max)
+  }
+
+  #[test]
+  fn envelope_is_tight_on_a_stable_orbit_and_wide_on_a_chaotic_one() {
+    let g = SimConfig::default().g;
+    let planet = test_body(Vec2::ZERO);
+    let r = 200.;
+    let mut ship = Ship::new(vec2(r, 0.), vec2(0., (g * 1000. / r).sqrt()), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.in_hill_radius_of = vec![planet.clone()];
+    let stable = envelope_width(ship, &[planet]);
+
+    // two equal planets with the ship falling slowly into the gap between them, just off centre
+    let a = test_body(vec2(-150., 0.));
+    let b = test_body(vec2(150., 0.));
+    let mut ship = Ship::new(vec2(5., 300.), vec2(0., -20.), &ShipConfig::default(), "Test".to_owned(), WHITE);
+    ship.in_hill_radius_of = vec![a.clone(), b.clone()];
+    let chaotic = envelope_width(ship, &[a, b]);
+
+    assert!(stable < 1., "{}", stable);
+    assert!(chaotic > 50. * stable, "{} vs {}", chaotic, stable);
+  }
+}
@@ -0,0 +1,47 @@
+// a burn planned for a moment in sim time; the cue fires once when the countdown runs out
+pub struct PlannedBurn {
+  pub label: String,
+  pub burn_time: f32,
+  cued: bool,
+}
+
+impl PlannedBurn {
+  pub fn new(label: String, burn_time: f32) -> Self {
+    Self { label, burn_time, cued: false }
+  }
+
+  pub fn countdown(&self, now: f32) -> f32 {
+    (self.burn_time - now).max(0.)
+  }
+
+  // true only on the update that reaches the burn time
+  pub fn update(&mut self, now: f32) -> bool {
+    if self.cued || now < self.burn_time {
+      return false;
+    }
+    self.cued = true;
+    true
+  }
+
+  pub fn is_due(&self) -> bool {
+    self.cued
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counts_down_and_cues_once_at_zero() {
+    let mut burn = PlannedBurn::new("Transfer".to_owned(), 10.);
+    assert_eq!(burn.countdown(4.), 6.);
+    assert!(!burn.update(9.9));
+    assert!(!burn.is_due());
+    assert!(burn.update(10.));
+    assert_eq!(burn.countdown(10.5), 0.);
+    assert!(!burn.update(10.5));
+    assert!(!burn.update(20.));
+    assert!(burn.is_due());
+  }
+}
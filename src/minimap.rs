@@ -0,0 +1,53 @@
+use macroquad::prelude::*;
+
+use crate::physics::rotate_vec2_by_rad;
+use crate::simulation::Simulation;
+use crate::CelestialBodyType;
+
+// fraction of the half-size the outermost body reaches, so it is not drawn on the border
+const MINIMAP_FILL: f32 = 0.9;
+const MINIMAP_STAR_RADIUS: f32 = 4.;
+const MINIMAP_BODY_RADIUS: f32 = 2.;
+const MINIMAP_SHIP_SIZE: f32 = 3.;
+// the main view's outline never shrinks below this many pixels, or it would vanish when zoomed in
+const MINIMAP_MIN_VIEW: f32 = 3.;
+
+// the whole system in a size x size square with its top left at origin, centred on the star
+// and scaled to the outermost major body; the main view's extent is outlined on top
+pub fn draw_minimap(sim: &Simulation, origin: Vec2, size: f32, focus: Vec2, view_size: Vec2, frame_angle: f32) {
+  let center = sim.cb_parent.borrow().mov.pos;
+  let extent = sim.major_celestial_bodies.iter()
+    .map(|cb| cb.borrow().mov.pos.distance(center))
+    .fold(0., f32::max);
+  if extent <= 0. {
+    return;
+  }
+  let map_scale = size / 2. * MINIMAP_FILL / extent;
+  let middle = origin + Vec2::splat(size / 2.);
+  let to_map = |p: Vec2| middle + (p - center) * map_scale;
+
+  draw_rectangle(origin.x, origin.y, size, size, Color::new(0., 0., 0., 0.6));
+  draw_rectangle_lines(origin.x, origin.y, size, size, 1., DARKGRAY);
+  for cb in &sim.major_celestial_bodies {
+    let cb = cb.borrow();
+    let p = to_map(cb.mov.pos);
+    let radius = if let CelestialBodyType::Star = cb.cb_type { MINIMAP_STAR_RADIUS } else { MINIMAP_BODY_RADIUS };
+    draw_circle(p.x, p.y, radius, cb.color);
+  }
+  for s in &sim.ships {
+    let s = s.borrow();
+    let p = to_map(s.mov.pos);
+    draw_rectangle(p.x - MINIMAP_SHIP_SIZE / 2., p.y - MINIMAP_SHIP_SIZE / 2., MINIMAP_SHIP_SIZE, MINIMAP_SHIP_SIZE, s.color);
+  }
+  let active = to_map(sim.ship.borrow().mov.pos);
+  draw_circle_lines(active.x, active.y, MINIMAP_SHIP_SIZE * 2., 1., WHITE);
+
+  // corners of the main view, turned with the frame and kept inside the map
+  let half = (view_size / 2.).max(Vec2::splat(MINIMAP_MIN_VIEW / map_scale));
+  let corners = [vec2(-1., -1.), vec2(1., -1.), vec2(1., 1.), vec2(-1., 1.)]
+    .map(|c| to_map(focus + rotate_vec2_by_rad(&(c * half), frame_angle)).clamp(origin, origin + Vec2::splat(size)));
+  for i in 0..corners.len() {
+    let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+    draw_line(a.x, a.y, b.x, b.y, 1., LIGHTGRAY);
+  }
+}
@@ -1,28 +1,82 @@
 use macroquad::prelude::*;
 
+use crate::integrator::{advance, complete, IntegratorKind, StageState};
+use crate::physics::normalize_angle;
+
 #[derive(Clone)]
 pub struct Movable {
   pub pos: Vec2,
   pub vel: Vec2,
   pub mass: f32,
   pub rot: f32,
-  pub store: (Vec2, Vec2, f32)
+  pub rot_vel: f32,
+  // gathered by the force passes, consumed by update and complete_step
+  pub acc: Vec2,
+  stage: StageState,
+  pub store: (Vec2, Vec2, f32, f32, Vec2, f32),
+  // state before the latest update, for drawing between physics steps
+  pub prev_pos: Vec2,
+  pub prev_rot: f32,
 }
 
 impl Movable {
   pub fn new(pos: Vec2, vel: Vec2, mass: f32, rot: f32) -> Self {
-    Self { pos, vel, mass, rot, store: (pos, vel, rot) }
+    Self { pos, vel, mass, rot, rot_vel: 0., acc: Vec2::ZERO, stage: StageState::default(), store: (pos, vel, rot, 0., pos, rot), prev_pos: pos, prev_rot: rot }
   }
 
   pub fn save(&mut self) {
-    self.store = (self.pos, self.vel, self.rot);
+    self.store = (self.pos, self.vel, self.rot, self.rot_vel, self.prev_pos, self.prev_rot);
   }
 
   pub fn load(&mut self) {
-    (self.pos, self.vel, self.rot) = self.store;
+    (self.pos, self.vel, self.rot, self.rot_vel, self.prev_pos, self.prev_rot) = self.store;
+    self.acc = Vec2::ZERO;
+  }
+
+  pub fn update(&mut self, integrator: IntegratorKind, dt: f32) {
+    self.prev_pos = self.pos;
+    self.prev_rot = self.rot;
+    (self.pos, self.vel) = advance(integrator, &mut self.stage, self.pos, self.vel, self.acc, dt);
+    self.acc = Vec2::ZERO;
+    self.rot += self.rot_vel * dt;
+    self.wrap_rot();
+  }
+
+  // keeps rot from growing without bound; prev_rot moves by the same turns so drawing
+  // between steps does not spin the long way round
+  pub fn wrap_rot(&mut self) {
+    let wrapped = normalize_angle(self.rot);
+    self.prev_rot += wrapped - self.rot;
+    self.rot = wrapped;
+  }
+
+  // acc has to be gathered again at the new position first, once per stage after the first
+  pub fn complete_step(&mut self, integrator: IntegratorKind, dt: f32) {
+    (self.pos, self.vel) = complete(integrator, &mut self.stage, self.pos, self.vel, self.acc, dt);
+    self.acc = Vec2::ZERO;
   }
 
-  pub fn update(&mut self, dt: f32) {
-    self.pos += self.vel * dt;
+  // alpha is the fraction of a physics step elapsed since the latest update
+  pub fn render_pos(&self, alpha: f32) -> Vec2 {
+    self.prev_pos.lerp(self.pos, alpha)
   }
-}
\ No newline at end of file
+
+  pub fn render_rot(&self, alpha: f32) -> f32 {
+    self.prev_rot + (self.rot - self.prev_rot) * alpha
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_state_runs_from_the_previous_step_to_the_current_one() {
+    let mut mov = Movable::new(vec2(10., 20.), vec2(30., -40.), 1., 0.5);
+    mov.rot_vel = 1.;
+    mov.update(IntegratorKind::SemiImplicitEuler, 0.1);
+    assert_eq!((mov.render_pos(0.), mov.render_rot(0.)), (vec2(10., 20.), 0.5));
+    assert_eq!((mov.render_pos(1.), mov.render_rot(1.)), (mov.pos, mov.rot));
+    assert_ne!(mov.pos, mov.prev_pos);
+  }
+}
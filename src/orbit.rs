@@ -0,0 +1,540 @@
+use macroquad::prelude::*;
+use std::f64::consts::PI;
+
+use crate::physics::rotate_vec2_by_rad;
+
+const LAMBERT_ITERATIONS: usize = 200;
+// radians off a half turn where the f and g coefficients lose too much precision
+const LAMBERT_COLLINEAR_ANGLE: f64 = 1e-3;
+const ARC_SUBSTEPS: usize = 10;
+const CIRCULAR_ECCENTRICITY: f32 = 0.01;
+const KEPLER_ITERATIONS: usize = 20;
+const KEPLER_MIN_ECCENTRICITY: f32 = 1e-4;
+
+fn stumpff_c(z: f64) -> f64 {
+  if z > 0. {
+    (1. - z.sqrt().cos()) / z
+  } else if z < 0. {
+    ((-z).sqrt().cosh() - 1.) / -z
+  } else {
+    0.5
+  }
+}
+
+fn stumpff_s(z: f64) -> f64 {
+  if z > 0. {
+    let sz = z.sqrt();
+    (sz - sz.sin()) / sz.powi(3)
+  } else if z < 0. {
+    let sz = (-z).sqrt();
+    (sz.sinh() - sz) / sz.powi(3)
+  } else {
+    1. / 6.
+  }
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+  a.x * b.y - a.y * b.x
+}
+
+// single revolution, prograde (counter-clockwise in world coordinates, same as
+// get_initial_position_and_velocity) transfer using universal variables;
+// returns departure velocity relative to the central body
+pub fn lambert_2d(r1: Vec2, r2: Vec2, tof: f32, mu: f32) -> Option<Vec2> {
+  let r1n = r1.length() as f64;
+  let r2n = r2.length() as f64;
+  if tof <= 0. || mu <= 0. || r1n == 0. || r2n == 0. {
+    return None;
+  }
+
+  let cos_dtheta = ((r1.dot(r2) as f64) / (r1n * r2n)).clamp(-1., 1.);
+  let dtheta = if cross(r1, r2) >= 0. { cos_dtheta.acos() } else { 2. * PI - cos_dtheta.acos() };
+  if (1. - cos_dtheta).abs() < 1e-9 {
+    return None;
+  }
+  let a = dtheta.sin() * (r1n * r2n / (1. - cos_dtheta)).sqrt();
+  let collinear = (dtheta - PI).abs() < LAMBERT_COLLINEAR_ANGLE;
+  if a.abs() < 1e-9 && !collinear {
+    return None;
+  }
+
+  let mu = mu as f64;
+  let tof = tof as f64;
+  let y = |z: f64| r1n + r2n + a * (z * stumpff_s(z) - 1.) / stumpff_c(z).sqrt();
+  let time_of_flight = |z: f64| {
+    let yz = y(z);
+    if yz < 0. {
+      return None;
+    }
+    Some(((yz / stumpff_c(z)).powf(1.5) * stumpff_s(z) + a * yz.sqrt()) / mu.sqrt())
+  };
+
+  let mut lo = -4. * PI * PI * 25.;
+  let mut hi = 4. * PI * PI - 1e-6;
+  match time_of_flight(hi) {
+    Some(t) if t >= tof => {},
+    _ => return None,
+  }
+  for _ in 0..LAMBERT_ITERATIONS {
+    let mid = (lo + hi) / 2.;
+    match time_of_flight(mid) {
+      Some(t) if t >= tof => hi = mid,
+      _ => lo = mid,
+    }
+  }
+
+  let yz = y(hi);
+  // across the star f and g both vanish, so the velocity is built from its radial part, out of
+  // Kepler's time equation, and its tangential part, out of the transfer's angular momentum
+  if collinear {
+    let (c, s) = (stumpff_c(hi), stumpff_s(hi));
+    let chi = (yz / c).sqrt();
+    let sigma = (mu.sqrt() * tof - chi.powi(3) * s - r1n * chi * (1. - hi * s)) / yz;
+    let p = r1n * r2n * (1. - cos_dtheta) / yz;
+    let radial = r1.normalize();
+    let v1 = radial * (mu.sqrt() * sigma / r1n) as f32 + radial.perp() * ((mu * p).sqrt() / r1n) as f32;
+    return if v1.is_finite() { Some(v1) } else { None };
+  }
+  let f = 1. - yz / r1n;
+  let g = a * (yz / mu).sqrt();
+  if g == 0. || !f.is_finite() || !g.is_finite() {
+    return None;
+  }
+  let v1 = (r2 - r1 * f as f32) / g as f32;
+  if v1.is_finite() { Some(v1) } else { None }
+}
+
+// numerically propagates a two-body trajectory for drawing
+pub fn transfer_arc(r1: Vec2, v1: Vec2, tof: f32, mu: f32, samples: usize) -> Vec<Vec2> {
+  let dt = tof / (samples * ARC_SUBSTEPS) as f32;
+  let mut pos = r1;
+  let mut vel = v1;
+  let mut arc = vec![pos];
+  for _ in 0..samples {
+    for _ in 0..ARC_SUBSTEPS {
+      vel += -pos.normalize() * mu / pos.length_squared() * dt;
+      pos += vel * dt;
+    }
+    arc.push(pos);
+  }
+  arc
+}
+
+// advances a body along a circle around its parent, keeping its current angular velocity
+pub fn position_on_rail(rel_pos: Vec2, rel_vel: Vec2, t: f32) -> Vec2 {
+  let r_sq = rel_pos.length_squared();
+  if r_sq == 0. {
+    return rel_pos;
+  }
+  let omega = cross(rel_pos, rel_vel) / r_sq;
+  rotate_vec2_by_rad(&rel_pos, omega * t)
+}
+
+// two-body position after t by solving Kepler's equation; near-circular and unbound
+// orbits fall back to the circular rail
+pub fn kepler_position(rel_pos: Vec2, rel_vel: Vec2, mu: f32, t: f32) -> Vec2 {
+  let elements = osculating_elements(rel_pos, rel_vel, mu);
+  let e = elements.eccentricity;
+  if !(KEPLER_MIN_ECCENTRICITY..1.).contains(&e) {
+    return position_on_rail(rel_pos, rel_vel, t);
+  }
+  let a = elements.semi_major_axis;
+  let e_cos = 1. - rel_pos.length() / a;
+  let e_sin = rel_pos.dot(rel_vel) / (mu * a).sqrt();
+  let anomaly = e_sin.atan2(e_cos);
+  let mean_anomaly = anomaly - e_sin + (mu / a.powi(3)).sqrt() * t;
+  let mut anomaly = mean_anomaly;
+  for _ in 0..KEPLER_ITERATIONS {
+    anomaly -= (anomaly - e * anomaly.sin() - mean_anomaly) / (1. - e * anomaly.cos());
+  }
+  let direction = cross(rel_pos, rel_vel).signum();
+  let perifocal = vec2(a * (anomaly.cos() - e), direction * a * (1. - e * e).sqrt() * anomaly.sin());
+  rotate_vec2_by_rad(&perifocal, elements.argument_of_periapsis)
+}
+
+// sample of a timed ship prediction that comes nearest to the target's two-body position at
+// the same time: (time, ship position, target position), all relative to the target's parent
+pub fn closest_intercept(ship_pred: &[(f32, Vec2)], target: (Vec2, Vec2), mu: f32) -> Option<(f32, Vec2, Vec2)> {
+  ship_pred.iter()
+    .map(|(t, p)| (*t, *p, kepler_position(target.0, target.1, mu, *t)))
+    .min_by(|a, b| a.1.distance(a.2).total_cmp(&b.1.distance(b.2)))
+}
+
+pub fn intercept_miss(ship_pred: &[(f32, Vec2)], target: (Vec2, Vec2), mu: f32) -> f32 {
+  closest_intercept(ship_pred, target, mu).map_or(f32::INFINITY, |(_, ship, target)| ship.distance(target))
+}
+
+// burn needed to turn the current velocity into a circular orbit at this radius,
+// keeping the current direction of travel around the body
+pub fn circularization_dv(rel_pos: Vec2, rel_vel: Vec2, mu: f32) -> Vec2 {
+  let r = rel_pos.length();
+  let direction = if cross(rel_pos, rel_vel) >= 0. { rel_pos.perp() } else { -rel_pos.perp() };
+  direction.normalize_or_zero() * (mu / r).sqrt() - rel_vel
+}
+
+// extra prograde speed that brings the orbit up to parabolic escape, zero once unbound
+pub fn escape_dv(rel_pos: Vec2, rel_vel: Vec2, mu: f32) -> f32 {
+  ((2. * mu / rel_pos.length()).sqrt() - rel_vel.length()).max(0.)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrojanPoint {
+  L4,
+  L5,
+}
+
+// L4 leads and L5 trails the body by 60 degrees along its orbit, sharing its distance and
+// speed around the parent; returns position and velocity relative to the parent
+pub fn lagrange_point(rel_pos: Vec2, rel_vel: Vec2, point: TrojanPoint) -> (Vec2, Vec2) {
+  let direction = cross(rel_pos, rel_vel).signum();
+  let angle = match point {
+    TrojanPoint::L4 => 60_f32,
+    TrojanPoint::L5 => -60_f32,
+  }.to_radians() * direction;
+  (rotate_vec2_by_rad(&rel_pos, angle), rotate_vec2_by_rad(&rel_vel, angle))
+}
+
+pub struct OrbitElements {
+  pub eccentricity: f32,
+  pub semi_major_axis: f32,
+  pub argument_of_periapsis: f32,
+  // measured from periapsis in the direction of motion
+  pub true_anomaly: f32,
+  // signed, positive for counter-clockwise motion
+  pub angular_momentum: f32,
+  pub periapsis: f32,
+  pub apoapsis: Option<f32>,
+  pub period: Option<f32>,
+}
+
+impl OrbitElements {
+  pub fn class(&self) -> &'static str {
+    if self.eccentricity < CIRCULAR_ECCENTRICITY {
+      "circular"
+    } else if self.eccentricity < 1. {
+      "elliptical"
+    } else if self.eccentricity == 1. {
+      "parabolic"
+    } else {
+      "hyperbolic"
+    }
+  }
+
+  // periapsis and apoapsis relative to the focus, None once the orbit is unbound
+  pub fn apsis_points(&self) -> Option<(Vec2, Vec2)> {
+    let apoapsis = self.apoapsis?;
+    let direction = rotate_vec2_by_rad(&vec2(1., 0.), self.argument_of_periapsis);
+    Some((direction * self.periapsis, -direction * apoapsis))
+  }
+}
+
+pub fn osculating_elements(rel_pos: Vec2, rel_vel: Vec2, mu: f32) -> OrbitElements {
+  let r = rel_pos.length();
+  let h = cross(rel_pos, rel_vel);
+  let energy = rel_vel.length_squared() / 2. - mu / r;
+  let e_vec = ((rel_vel.length_squared() - mu / r) * rel_pos - rel_pos.dot(rel_vel) * rel_vel) / mu;
+  let eccentricity = e_vec.length();
+  let semi_latus_rectum = h * h / mu;
+  let bound = eccentricity < 1.;
+  let semi_major_axis = -mu / (2. * energy);
+
+  let argument_of_periapsis = e_vec.y.atan2(e_vec.x);
+  let direction = if h < 0. { -1. } else { 1. };
+
+  OrbitElements {
+    eccentricity,
+    semi_major_axis,
+    argument_of_periapsis,
+    true_anomaly: direction * (rel_pos.y.atan2(rel_pos.x) - argument_of_periapsis),
+    angular_momentum: h,
+    periapsis: semi_latus_rectum / (1. + eccentricity),
+    apoapsis: if bound { Some(semi_latus_rectum / (1. - eccentricity)) } else { None },
+    period: if bound { Some(2. * std::f32::consts::PI * (semi_major_axis.powi(3) / mu).sqrt()) } else { None },
+  }
+}
+
+// inverse of osculating_elements: position and velocity relative to the focus
+pub fn state_from_elements(elements: &OrbitElements, mu: f32) -> (Vec2, Vec2) {
+  let (e, nu, h) = (elements.eccentricity, elements.true_anomaly, elements.angular_momentum);
+  let direction = if h < 0. { -1. } else { 1. };
+  let r = h * h / mu / (1. + e * nu.cos());
+  let radial = rotate_vec2_by_rad(&vec2(1., 0.), elements.argument_of_periapsis + direction * nu);
+  let speed_scale = mu / h.abs();
+  let vel = radial * speed_scale * e * nu.sin() + radial.perp() * direction * speed_scale * (1. + e * nu.cos());
+  (radial * r, vel)
+}
+
+// bound orbit through rel_pos with the given apsides, keeping the sense of motion and whether
+// the ship is climbing or falling; the apsides are clamped so rel_pos lies between them
+pub fn elements_with_apsides(rel_pos: Vec2, rel_vel: Vec2, periapsis: f32, apoapsis: f32, mu: f32) -> OrbitElements {
+  let r = rel_pos.length();
+  let (periapsis, apoapsis) = (periapsis.min(r), apoapsis.max(r));
+  let eccentricity = (apoapsis - periapsis) / (apoapsis + periapsis);
+  let semi_latus_rectum = 2. * periapsis * apoapsis / (periapsis + apoapsis);
+  let direction = if cross(rel_pos, rel_vel) < 0. { -1. } else { 1. };
+  let cos_anomaly = if eccentricity > 0. { ((semi_latus_rectum / r - 1.) / eccentricity).clamp(-1., 1.) } else { 1. };
+  let true_anomaly = cos_anomaly.acos() * if rel_pos.dot(rel_vel) < 0. { -1. } else { 1. };
+  let semi_major_axis = (periapsis + apoapsis) / 2.;
+
+  OrbitElements {
+    eccentricity,
+    semi_major_axis,
+    argument_of_periapsis: rel_pos.y.atan2(rel_pos.x) - direction * true_anomaly,
+    true_anomaly,
+    angular_momentum: direction * (mu * semi_latus_rectum).sqrt(),
+    periapsis,
+    apoapsis: Some(apoapsis),
+    period: Some(2. * std::f32::consts::PI * (semi_major_axis.powi(3) / mu).sqrt()),
+  }
+}
+
+// radial screening only: true if the ship's distance from the central body ever
+// passes within margin of the body's orbital radius
+pub fn orbits_can_intersect(ship_peri: f32, ship_apo: f32, body_orbit_r: f32, margin: f32) -> bool {
+  ship_peri - margin <= body_orbit_r && body_orbit_r <= ship_apo + margin
+}
+
+// time until the distance from the body first reaches soi_radius, None if the orbit
+// never gets that far (or falls straight in)
+pub fn time_to_soi_exit(rel_pos: Vec2, rel_vel: Vec2, mu: f32, soi_radius: f32) -> Option<f32> {
+  let r = rel_pos.length() as f64;
+  let soi = soi_radius as f64;
+  if r >= soi {
+    return Some(0.);
+  }
+  let mu64 = mu as f64;
+  let h = cross(rel_pos, rel_vel) as f64;
+  let energy = rel_vel.length_squared() as f64 / 2. - mu64 / r;
+
+  // radial trajectory, integrate dt = dr / v(r) with Simpson's rule
+  if h.abs() < 1e-6 * r * (rel_vel.length() as f64).max(1e-9) {
+    if rel_pos.dot(rel_vel) <= 0. || energy < -mu64 / soi {
+      return None;
+    }
+    let steps = 200;
+    let dr = (soi - r) / steps as f64;
+    let inv_speed = |x: f64| 1. / (2. * (energy + mu64 / x)).sqrt();
+    let sum: f64 = (0..=steps).map(|i| {
+      let weight = if i == 0 || i == steps { 1. } else if i % 2 == 1 { 4. } else { 2. };
+      weight * inv_speed(r + i as f64 * dr)
+    }).sum();
+    return Some((sum * dr / 3.) as f32);
+  }
+
+  let e_vec = ((rel_vel.length_squared() - mu / r as f32) * rel_pos - rel_pos.dot(rel_vel) * rel_vel) / mu;
+  let e = e_vec.length() as f64;
+  let p = h * h / mu64;
+  if e < 1. && p / (1. - e) < soi {
+    return None;
+  }
+  let mut nu = (cross(e_vec, rel_pos) as f64).atan2(e_vec.dot(rel_pos) as f64);
+  if e < 1e-9 {
+    nu = 0.;
+  }
+  if h < 0. {
+    nu = -nu;
+  }
+  let nu_exit = ((p / soi - 1.) / e).clamp(-1., 1.).acos();
+
+  let time_since_periapsis = |nu: f64| -> f64 {
+    if (e - 1.).abs() < 1e-4 {
+      let d = (nu / 2.).tan();
+      (p.powi(3) / mu64).sqrt() / 2. * (d + d.powi(3) / 3.)
+    } else if e < 1. {
+      let a = p / (1. - e * e);
+      let ecc_anomaly = 2. * (((1. - e) / (1. + e)).sqrt() * (nu / 2.).tan()).atan();
+      (ecc_anomaly - e * ecc_anomaly.sin()) * (a.powi(3) / mu64).sqrt()
+    } else {
+      let a = p / (e * e - 1.);
+      let hyp_anomaly = 2. * (((e - 1.) / (e + 1.)).sqrt() * (nu / 2.).tan()).atanh();
+      (e * hyp_anomaly.sinh() - hyp_anomaly) * (a.powi(3) / mu64).sqrt()
+    }
+  };
+  let t = time_since_periapsis(nu_exit) - time_since_periapsis(nu);
+  if t.is_finite() { Some(t.max(0.) as f32) } else { None }
+}
+
+pub fn orbit_summary(rel_pos: Vec2, rel_vel: Vec2, mu: f32, body_name: &str, day_length: f32) -> String {
+  let elements = osculating_elements(rel_pos, rel_vel, mu);
+  match (elements.apoapsis, elements.period) {
+    (Some(apoapsis), Some(period)) => format!(
+      "{}: {} orbit, pe {:.0}, ap {:.0}, period {:.1} days",
+      body_name, elements.class(), elements.periapsis, apoapsis, period / day_length
+    ),
+    _ => format!(
+      "{}: escape trajectory, no apoapsis, pe {:.0}",
+      body_name, elements.periapsis
+    ),
+  }
+}
+
+pub fn elements_report(elements: &OrbitElements, body_name: &str, day_length: f32) -> String {
+  [
+    format!("Orbit around {} ({})", body_name, elements.class()),
+    format!("  semi-major axis    {:.1}", elements.semi_major_axis),
+    format!("  eccentricity       {:.4}", elements.eccentricity),
+    format!("  period             {}", elements.period.map_or("none (unbound)".to_owned(), |p| format!("{:.2} days", p / day_length))),
+    format!("  periapsis          {:.1}", elements.periapsis),
+    format!("  apoapsis           {}", elements.apoapsis.map_or("none (unbound)".to_owned(), |a| format!("{:.1}", a))),
+    format!("  arg. of periapsis  {:.1} deg", elements.argument_of_periapsis.to_degrees()),
+    format!("  true anomaly       {:.1} deg", elements.true_anomaly.to_degrees()),
+  ].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MU: f32 = 50000.;
+
+  fn hohmann_time(r1: f32, r2: f32) -> f32 {
+    std::f32::consts::PI * ((r1 + r2) / 2.).powi(3).sqrt() / MU.sqrt()
+  }
+
+  fn arrival_error(r1: Vec2, r2: Vec2, tof: f32) -> f32 {
+    let v1 = lambert_2d(r1, r2, tof, MU).unwrap();
+    transfer_arc(r1, v1, tof, MU, 2000).last().unwrap().distance(r2) / r2.length()
+  }
+
+  #[test]
+  fn hohmann_transfer_at_exactly_half_turn() {
+    let (r1, r2) = (vec2(100., 0.), vec2(-200., 0.));
+    let v1 = lambert_2d(r1, r2, hohmann_time(100., 200.), MU).unwrap();
+    let periapsis_speed = (MU * (2. / 100. - 1. / 150.)).sqrt();
+    assert!(v1.x.abs() < periapsis_speed * 1e-3);
+    assert!((v1.y - periapsis_speed).abs() < periapsis_speed * 1e-3);
+  }
+
+  #[test]
+  fn half_turn_off_the_hohmann_time_reaches_the_target() {
+    let (r1, r2) = (vec2(100., 0.), vec2(-200., 0.));
+    for factor in [0.7, 1.3] {
+      assert!(arrival_error(r1, r2, hohmann_time(100., 200.) * factor) < 1e-2);
+    }
+  }
+
+  #[test]
+  fn quarter_turn_reaches_the_target() {
+    assert!(arrival_error(vec2(100., 0.), vec2(0., 150.), hohmann_time(100., 150.) * 0.6) < 1e-2);
+  }
+
+  #[test]
+  fn velocity_is_continuous_into_the_half_turn_band() {
+    let r1 = vec2(100., 0.);
+    let tof = hohmann_time(100., 200.);
+    let outside = lambert_2d(r1, rotate_vec2_by_rad(&vec2(200., 0.), std::f32::consts::PI - 2e-3), tof, MU).unwrap();
+    let inside = lambert_2d(r1, rotate_vec2_by_rad(&vec2(200., 0.), std::f32::consts::PI - 5e-4), tof, MU).unwrap();
+    assert!(outside.distance(inside) < outside.length() * 1e-2);
+  }
+
+  #[test]
+  fn circular_orbit_report() {
+    let r = 1000.;
+    let elements = osculating_elements(vec2(r, 0.), vec2(0., (MU / r).sqrt()), MU);
+    let report = elements_report(&elements, "Io", 100.);
+    assert_eq!(report.lines().collect::<Vec<_>>(), [
+      "Orbit around Io (circular)",
+      "  semi-major axis    1000.0",
+      "  eccentricity       0.0000",
+      "  period             8.89 days",
+      "  periapsis          1000.0",
+      "  apoapsis           1000.0",
+      "  arg. of periapsis  0.0 deg",
+      "  true anomaly       0.0 deg",
+    ]);
+  }
+
+  #[test]
+  fn summary_for_bound_and_unbound_orbits() {
+    let r = 1000.;
+    let circular = (MU / r).sqrt();
+    assert_eq!(
+      orbit_summary(vec2(r, 0.), vec2(0., circular), MU, "Io", 100.),
+      "Io: circular orbit, pe 1000, ap 1000, period 8.9 days"
+    );
+    assert_eq!(
+      orbit_summary(vec2(r, 0.), vec2(0., 2. * circular), MU, "Io", 100.),
+      "Io: escape trajectory, no apoapsis, pe 1000"
+    );
+  }
+
+  #[test]
+  fn orbit_bands_intersect_when_nested_or_overlapping() {
+    // the planet's band inside the ship's range, and the ship's range inside the band
+    assert!(orbits_can_intersect(800., 1500., 1000., 50.));
+    assert!(orbits_can_intersect(990., 1010., 1000., 50.));
+    // overlapping at either edge of the band
+    assert!(orbits_can_intersect(1040., 2000., 1000., 50.));
+    assert!(orbits_can_intersect(500., 960., 1000., 50.));
+    // disjoint on either side
+    assert!(!orbits_can_intersect(1060., 2000., 1000., 50.));
+    assert!(!orbits_can_intersect(500., 940., 1000., 50.));
+  }
+
+  #[test]
+  fn radial_escape_at_escape_speed_leaves_at_the_parabolic_time() {
+    let (r, soi) = (1000_f32, 9000_f32);
+    let speed = (2. * MU / r).sqrt();
+    // r(t)^(3/2) grows linearly at 3/2 sqrt(2 mu) on a radial parabola
+    let expected = (soi.powf(1.5) - r.powf(1.5)) * 2. / 3. / (2. * MU).sqrt();
+    let t = time_to_soi_exit(vec2(r, 0.), vec2(speed, 0.), MU, soi).unwrap();
+    assert!((t - expected).abs() < 1e-3 * expected, "{} vs {}", t, expected);
+    assert_eq!(time_to_soi_exit(vec2(r, 0.), vec2(-speed, 0.), MU, soi), None);
+  }
+
+  #[test]
+  fn escape_from_a_circular_orbit_needs_root_two_minus_one_of_its_speed() {
+    let r = 1000.;
+    let circular = (MU / r).sqrt();
+    let dv = escape_dv(vec2(r, 0.), vec2(0., circular), MU);
+    assert!((dv - (2_f32.sqrt() - 1.) * circular).abs() < 1e-4);
+  }
+
+  #[test]
+  fn a_path_through_the_targets_future_position_misses_by_nothing() {
+    // an eccentric target orbit, so the intercept point comes from Kepler's equation
+    let target = (vec2(1000., 0.), vec2(0., 1.2 * (MU / 1000_f32).sqrt()));
+    let meet = kepler_position(target.0, target.1, MU, 50.);
+    let start = vec2(-3000., 500.);
+    let ship_pred: Vec<(f32, Vec2)> = (0..=10).map(|k| (k as f32 * 10., start.lerp(meet, k as f32 / 5.))).collect();
+    assert!(intercept_miss(&ship_pred, target, MU) < 1e-3);
+    let (t, ship, _) = closest_intercept(&ship_pred, target, MU).unwrap();
+    assert_eq!(t, 50.);
+    assert!(ship.distance(meet) < 1e-3);
+  }
+
+  #[test]
+  fn apsis_line_ends_at_the_periapsis_and_apoapsis() {
+    // starting at periapsis, so half a period later the orbit is at apoapsis
+    let r = 1000.;
+    let angle = 0.7;
+    let (pos, vel) = (rotate_vec2_by_rad(&vec2(r, 0.), angle), rotate_vec2_by_rad(&vec2(0., 1.2 * (MU / r).sqrt()), angle));
+    let elements = osculating_elements(pos, vel, MU);
+    let (periapsis, apoapsis) = elements.apsis_points().unwrap();
+    assert!(periapsis.distance(pos) < 1e-2);
+    let half_period = elements.period.unwrap() / 2.;
+    assert!(apoapsis.distance(kepler_position(pos, vel, MU, half_period)) < 1.);
+    assert!((apoapsis.length() - elements.apoapsis.unwrap()).abs() < 1e-2);
+
+    let escaping = osculating_elements(pos, vel * 2., MU);
+    assert!(escaping.apsis_points().is_none());
+  }
+
+  #[test]
+  fn state_to_elements_and_back_round_trips() {
+    let mu = 1000. * 1000.;
+    // prograde and retrograde, climbing and falling, near circular to strongly eccentric
+    let states = [
+      (vec2(1000., 0.), vec2(0., 31.6)),
+      (vec2(-600., 800.), vec2(-30., -25.)),
+      (vec2(300., -1200.), vec2(-18., 12.)),
+      (vec2(0., 1500.), vec2(35., -10.)),
+    ];
+    for (pos, vel) in states {
+      let elements = osculating_elements(pos, vel, mu);
+      let (back_pos, back_vel) = state_from_elements(&elements, mu);
+      assert!((back_pos - pos).length() < pos.length() * 1e-3, "{} vs {}", back_pos, pos);
+      assert!((back_vel - vel).length() < vel.length() * 1e-3, "{} vs {}", back_vel, vel);
+    }
+  }
+}
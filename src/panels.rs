@@ -0,0 +1,92 @@
+use macroquad::prelude::*;
+use std::fs;
+use std::io;
+
+use crate::library::parse_objects;
+
+// a HUD block in screen space (origin top left) that can be dragged with the left mouse button
+pub struct Panel {
+  pub name: &'static str,
+  pub rect: Rect,
+  pub visible: bool,
+  grab: Option<Vec2>,
+}
+
+impl Panel {
+  pub fn new(name: &'static str, rect: Rect) -> Self {
+    Self { name, rect, visible: true, grab: None }
+  }
+
+  // returns true when a drag has just been let go of
+  pub fn drag(&mut self, mouse: Vec2, pressed: bool, down: bool) -> bool {
+    if pressed && self.visible && self.rect.contains(mouse) {
+      self.grab = Some(mouse - self.rect.point());
+    }
+    let Some(grab) = self.grab else { return false };
+    if !down {
+      self.grab = None;
+      return true;
+    }
+    self.rect.move_to(mouse - grab);
+    false
+  }
+
+  pub fn is_dragged(&self) -> bool {
+    self.grab.is_some()
+  }
+
+  // top left corner in the centred screen camera
+  pub fn origin(&self, screen: Vec2) -> Vec2 {
+    self.rect.point() - screen / 2.
+  }
+}
+
+pub fn save_panels(path: &str, panels: &[&Panel]) -> io::Result<()> {
+  let objects: Vec<String> = panels.iter()
+    .map(|p| format!("  {{\"name\": \"{}\", \"x\": {}, \"y\": {}, \"visible\": {}}}", p.name, p.rect.x, p.rect.y, p.visible))
+    .collect();
+  fs::write(path, format!("[\n{}\n]\n", objects.join(",\n")))
+}
+
+// panels missing from the file, or a missing file, keep their defaults
+pub fn load_panels(path: &str, panels: &mut [&mut Panel]) {
+  let Some(objects) = fs::read_to_string(path).ok().and_then(|json| parse_objects(&json)) else { return };
+  for fields in objects {
+    let field = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let Some(panel) = panels.iter_mut().find(|p| field("name") == Some(p.name)) else { continue };
+    if let (Some(x), Some(y)) = (field("x").and_then(|v| v.parse().ok()), field("y").and_then(|v| v.parse().ok())) {
+      panel.rect.move_to(vec2(x, y));
+    }
+    if let Some(visible) = field("visible").and_then(|v| v.parse().ok()) {
+      panel.visible = visible;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn a_saved_layout_loads_back_onto_the_same_panels() {
+    let path = std::env::temp_dir().join(format!("solsys_panels_{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut info = Panel::new("info", Rect::new(10., 20., 200., 100.));
+    let mut help = Panel::new("help", Rect::new(300., 20., 150., 80.));
+    info.drag(vec2(15., 25.), true, true);
+    info.drag(vec2(115., 225.), false, true);
+    assert!(info.drag(vec2(115., 225.), false, false));
+    help.visible = false;
+    save_panels(path, &[&info, &help]).unwrap();
+
+    let mut loaded_info = Panel::new("info", Rect::new(0., 0., 200., 100.));
+    let mut loaded_help = Panel::new("help", Rect::new(0., 0., 150., 80.));
+    let mut unsaved = Panel::new("other", Rect::new(5., 5., 10., 10.));
+    load_panels(path, &mut [&mut loaded_info, &mut loaded_help, &mut unsaved]);
+    assert_eq!(loaded_info.rect, Rect::new(110., 220., 200., 100.));
+    assert!(loaded_info.visible);
+    assert_eq!((loaded_help.rect.point(), loaded_help.visible), (vec2(300., 20.), false));
+    assert_eq!((unsaved.rect, unsaved.visible), (Rect::new(5., 5., 10., 10.), true));
+    fs::remove_file(path).unwrap();
+  }
+}
@@ -0,0 +1,426 @@
+use macroquad::prelude::*;
+
+use crate::config::SimConfig;
+use crate::integrator::IntegratorKind;
+use crate::{
+  CelestialBodyType, DAYS_PER_YEAR, DAY_TIME, LANDING_STANDOFF, MAX_ASTEROIDS, PHYSICS_STEP, SHIP_ACCELERATION, SHIP_FUEL, SHIP_ROT_SPEED,
+};
+
+pub const BODY_TAGS: [&str; 3] = ["depot", "target", "hazard"];
+const TAG_DIM_ALPHA: f32 = 0.2;
+// keeps the large steps of the fast preset from flinging close asteroid pairs apart
+const FAST_SOFTENING: f32 = 20.;
+const CLEAR_COLORS: [Color; 3] = [BLACK, Color::new(0.02, 0.03, 0.08, 1.), Color::new(0.1, 0.1, 0.1, 1.)];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TurnModel {
+  Instant,
+  Inertial,
+}
+
+impl TurnModel {
+  pub fn next(&self) -> Self {
+    match self {
+      Self::Instant => Self::Inertial,
+      Self::Inertial => Self::Instant,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccuracyPreset {
+  Fast,
+  Balanced,
+  Precise,
+}
+
+impl AccuracyPreset {
+  pub fn next(&self) -> Self {
+    match self {
+      Self::Fast => Self::Balanced,
+      Self::Balanced => Self::Precise,
+      Self::Precise => Self::Fast,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThrottleResponse {
+  Linear,
+  SpoolUp,
+}
+
+impl ThrottleResponse {
+  pub fn next(&self) -> Self {
+    match self {
+      Self::Linear => Self::SpoolUp,
+      Self::SpoolUp => Self::Linear,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DetailLevel {
+  Full,
+  Medium,
+  Low,
+}
+
+impl DetailLevel {
+  pub fn next(&self) -> Self {
+    match self {
+      Self::Full => Self::Medium,
+      Self::Medium => Self::Low,
+      Self::Low => Self::Full,
+    }
+  }
+
+  // names, velocity lines and the other text only come with the full detail
+  pub fn shows_labels(&self) -> bool {
+    *self == Self::Full
+  }
+
+  pub fn shows_trails(&self) -> bool {
+    *self != Self::Low
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShipConfig {
+  pub fuel: f32,
+  pub thrust: f32,
+  pub turn_rate: f32,
+  pub landing_standoff: f32,
+  // periapsis and apoapsis of the starting orbit, in radii of the spawn body
+  pub spawn_orbit: (f32, f32),
+}
+
+impl ShipConfig {
+  pub fn hard() -> Self {
+    Self { fuel: SHIP_FUEL / 4., thrust: SHIP_ACCELERATION / 2., turn_rate: SHIP_ROT_SPEED / 2., landing_standoff: LANDING_STANDOFF, spawn_orbit: (1.5, 4.) }
+  }
+}
+
+impl Default for ShipConfig {
+  fn default() -> Self {
+    Self { fuel: SHIP_FUEL, thrust: SHIP_ACCELERATION, turn_rate: SHIP_ROT_SPEED, landing_standoff: LANDING_STANDOFF, spawn_orbit: (1.5, 1.5) }
+  }
+}
+
+// the asteroid belt is laid out in equal angular sectors, each stacking a few asteroids
+// outward from the inner edge; every range is drawn from the seeded generator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeltParams {
+  // distances from the star in AU; an asteroid that would land past the outer edge is left out
+  pub inner_radius: f32,
+  pub outer_radius: f32,
+  pub sectors: usize,
+  // fewest and most asteroids per sector, both included; swapped bounds are put in order
+  pub per_sector: (usize, usize),
+  // space left between neighbours in a sector, on top of the inner one's radius
+  pub gap: (f32, f32),
+  pub mass: (f32, f32),
+  pub radius: (f32, f32),
+}
+
+impl Default for BeltParams {
+  fn default() -> Self {
+    Self { inner_radius: 2.7, outer_radius: 2.75, sectors: 360, per_sector: (1, 4), gap: (500., 1000.), mass: (50., 100.), radius: (20., 50.) }
+  }
+}
+
+// smallest on-screen radius per body type, so tiny bodies stay visible when zoomed out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayRadii {
+  pub star: f32,
+  pub planet: f32,
+  pub moon: f32,
+  pub asteroid: f32,
+}
+
+impl DisplayRadii {
+  pub fn for_type(&self, cb_type: &CelestialBodyType) -> f32 {
+    match cb_type {
+      CelestialBodyType::Star => self.star,
+      CelestialBodyType::Planet => self.planet,
+      CelestialBodyType::Moon => self.moon,
+      CelestialBodyType::Asteroid => self.asteroid,
+    }
+  }
+
+  pub fn next_asteroid(&self) -> f32 {
+    if self.asteroid >= 4. { 1. } else { self.asteroid * 2. }
+  }
+}
+
+impl Default for DisplayRadii {
+  fn default() -> Self {
+    Self { star: 15., planet: 5., moon: 3., asteroid: 1. }
+  }
+}
+
+// gravity between two bodies, by id, multiplied by scale, for what-if experiments
+#[derive(Clone, Debug, PartialEq)]
+pub struct PairScale {
+  pub a: usize,
+  pub b: usize,
+  pub scale: f32,
+}
+
+pub fn pair_gravity_scale(scales: &[PairScale], a: usize, b: usize) -> f32 {
+  scales.iter()
+    .find(|p| (p.a == a && p.b == b) || (p.a == b && p.b == a))
+    .map_or(1., |p| p.scale)
+}
+
+pub fn next_pair_scale(scale: f32) -> f32 {
+  if scale == 1. { 0.5 } else if scale == 0.5 { 0. } else if scale == 0. { 2. } else { 1. }
+}
+
+// bodies carrying none of the active tags are dimmed, or hidden; no active tags shows everything
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagFilter {
+  pub tags: Vec<String>,
+  pub hide: bool,
+}
+
+impl TagFilter {
+  // None when the body is hidden, otherwise the alpha it is drawn with
+  pub fn alpha(&self, tags: &[String]) -> Option<f32> {
+    if self.tags.is_empty() || tags.iter().any(|t| self.tags.contains(t)) {
+      Some(1.)
+    } else if self.hide {
+      None
+    } else {
+      Some(TAG_DIM_ALPHA)
+    }
+  }
+
+  pub fn toggle(&mut self, tag: &str) {
+    toggle_tag(&mut self.tags, tag);
+  }
+}
+
+pub fn toggle_tag(tags: &mut Vec<String>, tag: &str) {
+  match tags.iter().position(|t| t == tag) {
+    Some(i) => {
+      tags.remove(i);
+    },
+    None => tags.push(tag.to_owned()),
+  }
+}
+
+#[derive(Clone)]
+pub struct SimParams {
+  pub config: SimConfig,
+  pub turn_model: TurnModel,
+  // physics halts while the window looks unfocused and picks up again on the next input.
+  // macroquad 0.3 has no focus signal, so a long frame stall stands in for focus loss; the
+  // app's own stalls from reseeds and loads are ignored
+  pub pause_on_focus_loss: bool,
+  pub accuracy: AccuracyPreset,
+  pub physics_step: f32,
+  pub substeps: usize,
+  pub integrator: IntegratorKind,
+  // splits each step further where gravity bends paths quickly, see Simulation::step_adaptive
+  pub adaptive_substeps: bool,
+  pub invert_turn: bool,
+  pub control_sensitivity: f32,
+  pub mutual_asteroid_gravity: bool,
+  // mutual asteroid gravity from a Barnes-Hut tree instead of the time-sliced pair sweep
+  pub barnes_hut: bool,
+  pub barnes_hut_theta: f32,
+  pub asteroid_budget_ms: f64,
+  pub day_length: f32,
+  pub days_per_year: u32,
+  pub gravity_exponent: f32,
+  // Plummer length, see gravity_acc
+  pub softening: f32,
+  pub max_asteroids: usize,
+  pub ship: ShipConfig,
+  pub belt: BeltParams,
+  pub tracer_mass_threshold: f32,
+  pub throttle_response: ThrottleResponse,
+  // body gravity is recomputed every this many steps and held constant in between; the held
+  // acceleration is stale by up to gravity_every - 1 steps, so the error is first order in
+  // that lag and grows fastest for close, quickly moving pairs. Ships always get fresh gravity
+  pub gravity_every: usize,
+  pub despawn_escaped: bool,
+  // overlapping bodies combine into one instead of passing through each other
+  pub merge_bodies: bool,
+  // overlapping asteroids bounce off each other and hard impacts throw off debris
+  pub asteroid_collisions: bool,
+  // tangential speed decay rate of a skidding ship, 0 turns skids off
+  pub skid_friction: f32,
+  // worker threads for the belt and Barnes-Hut gravity passes, 1 keeps them on the main thread
+  pub physics_threads: usize,
+  pub min_display_radius: DisplayRadii,
+  pub star_glow: bool,
+  pub clear_color: Color,
+  pub gravity_scales: Vec<PairScale>,
+  // seconds between auto-saves, 0 turns them off
+  pub autosave_interval: f32,
+  pub autosave_keep: usize,
+  pub tag_filter: TagFilter,
+}
+
+impl SimParams {
+  pub fn step_dt(&self) -> f32 {
+    self.physics_step / self.substeps as f32
+  }
+
+  pub fn next_sensitivity(&self) -> f32 {
+    if self.control_sensitivity <= 0.25 { 1. } else { self.control_sensitivity / 2. }
+  }
+
+  pub fn next_day_length(&self) -> f32 {
+    if self.day_length >= DAY_TIME * 2. { DAY_TIME / 2. } else { self.day_length * 2. }
+  }
+
+  pub fn next_gravity_exponent(&self) -> f32 {
+    if self.gravity_exponent >= 3. { 1. } else { self.gravity_exponent + 1. }
+  }
+
+  pub fn next_max_asteroids(&self) -> usize {
+    if self.max_asteroids <= MAX_ASTEROIDS / 8 { MAX_ASTEROIDS } else { self.max_asteroids / 2 }
+  }
+
+  pub fn next_gravity_every(&self) -> usize {
+    if self.gravity_every >= 8 { 1 } else { self.gravity_every * 2 }
+  }
+
+  pub fn next_physics_threads(&self) -> usize {
+    if self.physics_threads >= 8 { 1 } else { self.physics_threads * 2 }
+  }
+
+  pub fn next_clear_color(&self) -> Color {
+    let i = CLEAR_COLORS.iter().position(|c| *c == self.clear_color).map_or(0, |i| i + 1);
+    CLEAR_COLORS[i % CLEAR_COLORS.len()]
+  }
+
+  // a scale of 1 drops the override
+  pub fn set_pair_scale(&mut self, a: usize, b: usize, scale: f32) {
+    self.gravity_scales.retain(|p| !((p.a == a && p.b == b) || (p.a == b && p.b == a)));
+    if scale != 1. {
+      self.gravity_scales.push(PairScale { a, b, scale });
+    }
+  }
+
+  pub fn with_accuracy(&self, preset: AccuracyPreset) -> Self {
+    let p = accuracy_preset(preset);
+    Self {
+      accuracy: p.accuracy,
+      physics_step: p.physics_step,
+      substeps: p.substeps,
+      integrator: p.integrator,
+      adaptive_substeps: p.adaptive_substeps,
+      softening: p.softening,
+      ..self.clone()
+    }
+  }
+}
+
+impl Default for SimParams {
+  fn default() -> Self {
+    Self {
+      config: SimConfig::default(),
+      turn_model: TurnModel::Instant,
+      pause_on_focus_loss: true,
+      accuracy: AccuracyPreset::Balanced,
+      physics_step: PHYSICS_STEP,
+      substeps: 1,
+      integrator: IntegratorKind::VelocityVerlet,
+      adaptive_substeps: false,
+      invert_turn: false,
+      control_sensitivity: 1.,
+      mutual_asteroid_gravity: false,
+      barnes_hut: false,
+      barnes_hut_theta: 0.5,
+      asteroid_budget_ms: 4.,
+      day_length: DAY_TIME,
+      days_per_year: DAYS_PER_YEAR,
+      gravity_exponent: 2.,
+      softening: 0.,
+      max_asteroids: MAX_ASTEROIDS,
+      ship: ShipConfig::default(),
+      belt: BeltParams::default(),
+      tracer_mass_threshold: 0.,
+      throttle_response: ThrottleResponse::Linear,
+      gravity_every: 1,
+      despawn_escaped: false,
+      merge_bodies: false,
+      asteroid_collisions: false,
+      skid_friction: 0.,
+      physics_threads: 1,
+      min_display_radius: DisplayRadii::default(),
+      star_glow: true,
+      clear_color: CLEAR_COLORS[0],
+      gravity_scales: vec![],
+      autosave_interval: 300.,
+      autosave_keep: 5,
+      tag_filter: TagFilter::default(),
+    }
+  }
+}
+
+pub fn accuracy_preset(preset: AccuracyPreset) -> SimParams {
+  let (physics_step, substeps, integrator, adaptive_substeps, softening) = match preset {
+    AccuracyPreset::Fast => (PHYSICS_STEP * 2.5, 1, IntegratorKind::SemiImplicitEuler, false, FAST_SOFTENING),
+    AccuracyPreset::Balanced => (PHYSICS_STEP, 1, IntegratorKind::VelocityVerlet, false, 0.),
+    AccuracyPreset::Precise => (PHYSICS_STEP, 4, IntegratorKind::Rk4, true, 0.),
+  };
+  SimParams {
+    accuracy: preset,
+    physics_step,
+    substeps,
+    integrator,
+    adaptive_substeps,
+    softening,
+    ..SimParams::default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn presets_are_valid_and_distinct() {
+    let presets = [AccuracyPreset::Fast, AccuracyPreset::Balanced, AccuracyPreset::Precise].map(accuracy_preset);
+    for p in &presets {
+      assert!(p.step_dt() > 0. && p.step_dt().is_finite());
+      assert!(p.softening >= 0.);
+    }
+    for i in 0..presets.len() {
+      for j in (i + 1)..presets.len() {
+        let (a, b) = (&presets[i], &presets[j]);
+        assert!((a.physics_step, a.substeps, a.integrator, a.adaptive_substeps, a.softening) != (b.physics_step, b.substeps, b.integrator, b.adaptive_substeps, b.softening));
+      }
+    }
+    let precise = &presets[2];
+    assert_eq!((precise.integrator, precise.adaptive_substeps), (IntegratorKind::Rk4, true));
+    assert!(precise.step_dt() < presets[0].step_dt() && precise.step_dt() < presets[1].step_dt());
+  }
+
+  #[test]
+  fn switching_preset_sets_integrator_and_softening() {
+    let params = SimParams::default().with_accuracy(AccuracyPreset::Fast);
+    assert_eq!(params.integrator, accuracy_preset(AccuracyPreset::Fast).integrator);
+    assert_eq!(params.softening, FAST_SOFTENING);
+    let params = params.with_accuracy(AccuracyPreset::Balanced);
+    assert_eq!((params.integrator, params.softening), (SimParams::default().integrator, SimParams::default().softening));
+  }
+
+  #[test]
+  fn tag_filter_hides_or_dims_untagged_bodies() {
+    let (tagged, other, untagged) = (vec!["mission".to_owned()], vec!["fuel".to_owned()], vec![]);
+    let mut filter = TagFilter::default();
+    assert_eq!([&tagged, &other, &untagged].map(|t| filter.alpha(t)), [Some(1.); 3]);
+    filter.toggle("mission");
+    assert_eq!([&tagged, &other, &untagged].map(|t| filter.alpha(t)), [Some(1.), Some(TAG_DIM_ALPHA), Some(TAG_DIM_ALPHA)]);
+    filter.hide = true;
+    assert_eq!([&tagged, &other, &untagged].map(|t| filter.alpha(t)), [Some(1.), None, None]);
+    filter.toggle("mission");
+    assert_eq!(filter.alpha(&untagged), Some(1.));
+  }
+}
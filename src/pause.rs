@@ -0,0 +1,110 @@
+// macroquad 0.3 has no window focus query. As a fallback a long frame stall, which the OS or
+// browser causes by throttling a hidden or minimized window, counts as lost focus, and the
+// next key or mouse press as focus coming back
+const FOCUS_LOSS_FRAME_TIME: f32 = 0.5;
+// a frame's time is only reported on the next one, so an expected stall covers both
+const EXPECTED_STALL_FRAMES: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusEvent {
+  Lost,
+  Gained,
+}
+
+pub fn focus_event(frame_time: f32, input_seen: bool) -> Option<FocusEvent> {
+  if frame_time >= FOCUS_LOSS_FRAME_TIME {
+    Some(FocusEvent::Lost)
+  } else if input_seen {
+    Some(FocusEvent::Gained)
+  } else {
+    None
+  }
+}
+
+// halts physics from a focus loss until focus returns; separate from the player's own pause,
+// which focus coming back leaves alone
+pub struct FocusPause {
+  paused: bool,
+  // frames left whose stall comes from the app's own work, a reseed or a load, not the window
+  stall_frames: u8,
+}
+
+impl FocusPause {
+  pub fn new() -> Self {
+    Self { paused: false, stall_frames: 0 }
+  }
+
+  pub fn expect_stall(&mut self) {
+    self.stall_frames = EXPECTED_STALL_FRAMES;
+  }
+
+  pub fn update(&mut self, event: Option<FocusEvent>, enabled: bool) {
+    let mut event = event;
+    if self.stall_frames > 0 {
+      self.stall_frames -= 1;
+      if event == Some(FocusEvent::Lost) {
+        event = None;
+      }
+    }
+    match event {
+      Some(FocusEvent::Lost) => self.paused = true,
+      Some(FocusEvent::Gained) => self.paused = false,
+      None => {},
+    }
+    if !enabled {
+      self.paused = false;
+    }
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pauses_on_focus_loss_until_focus_returns() {
+    let mut pause = FocusPause::new();
+    pause.update(None, true);
+    assert!(!pause.is_paused());
+    pause.update(Some(FocusEvent::Lost), true);
+    assert!(pause.is_paused());
+    // quiet frames keep it paused
+    pause.update(None, true);
+    assert!(pause.is_paused());
+    pause.update(Some(FocusEvent::Gained), true);
+    assert!(!pause.is_paused());
+  }
+
+  #[test]
+  fn disabled_never_pauses() {
+    let mut pause = FocusPause::new();
+    pause.update(Some(FocusEvent::Lost), false);
+    assert!(!pause.is_paused());
+    pause.update(Some(FocusEvent::Lost), true);
+    pause.update(None, false);
+    assert!(!pause.is_paused());
+  }
+
+  #[test]
+  fn an_expected_stall_is_not_a_focus_loss() {
+    let mut pause = FocusPause::new();
+    pause.expect_stall();
+    pause.update(None, true);
+    pause.update(Some(FocusEvent::Lost), true);
+    assert!(!pause.is_paused());
+    pause.update(Some(FocusEvent::Lost), true);
+    assert!(pause.is_paused());
+  }
+
+  #[test]
+  fn stalls_and_input_read_as_focus_events() {
+    assert_eq!(focus_event(1. / 60., false), None);
+    assert_eq!(focus_event(2., false), Some(FocusEvent::Lost));
+    assert_eq!(focus_event(2., true), Some(FocusEvent::Lost));
+    assert_eq!(focus_event(1. / 60., true), Some(FocusEvent::Gained));
+  }
+}
@@ -0,0 +1,129 @@
+use macroquad::math::{vec2, Vec2};
+
+use crate::config::SimConfig;
+
+pub fn rotate_vec2_by_rad(v: &Vec2, rad: f32) -> Vec2 {
+  let c = rad.cos();
+  let s = rad.sin();
+  vec2(c*v.x - s*v.y, s*v.x + c*v.y)
+}
+
+// into [-pi, pi]
+pub fn normalize_angle(rad: f32) -> f32 {
+  let full = 360_f32.to_radians();
+  let a = rad.rem_euclid(full);
+  if a > full / 2. { a - full } else { a }
+}
+
+// accelerations of a towards b and of b towards a; softening is added to the separation in
+// quadrature (Plummer), capping the pull of close pairs, and 0 leaves the plain power law
+pub fn gravity_acc(a_pos: Vec2, a_mass: f32, b_pos: Vec2, b_mass: f32, g: f32, exponent: f32, softening: f32) -> (Vec2, Vec2) {
+  let distance_vector = a_pos - b_pos;
+  let softened = distance_vector.length_squared() + softening * softening;
+  let force_vec = distance_vector * softened.sqrt().recip();
+  let distance_length = softened.powf(exponent / 2.);
+
+  (
+    -force_vec * b_mass * g / distance_length,
+    force_vec * a_mass * g / distance_length,
+  )
+}
+
+pub fn calculate_hill_radius(parent_pos: Vec2, parent_mass: f32, child_pos: Vec2, child_mass: f32) -> f32 {
+  let a = (child_pos - parent_pos).length();
+  a * (child_mass / (3. * parent_mass)).cbrt()
+}
+
+// position at periapsis and the vis-viva speed there, perpendicular to the radius;
+// eccentricity 0 is a circular orbit
+pub fn orbital_state(parent_mass: f32, periapsis: f32, eccentricity: f32, angle: f32, g: f32) -> (Vec2, Vec2) {
+  let delta_vector = rotate_vec2_by_rad(&vec2(periapsis, 0.), angle.to_radians());
+  let speed = (parent_mass / periapsis * g * (1. + eccentricity)).sqrt();
+  (delta_vector, delta_vector.perp().normalize() * speed)
+}
+
+// orbits are set up for attracting gravity, also while it is flipped
+pub fn get_initial_position_and_velocity(parent_mass: f32, distance: f32, angle: f32, config: &SimConfig) -> (Vec2, Vec2) {
+  orbital_state(parent_mass, distance, 0., angle, config.g.abs())
+}
+
+pub fn get_elliptical_position_and_velocity(parent_mass: f32, periapsis: f32, apoapsis: f32, angle: f32, config: &SimConfig) -> (Vec2, Vec2) {
+  orbital_state(parent_mass, periapsis, (apoapsis - periapsis) / (apoapsis + periapsis), angle, config.g.abs())
+}
+
+pub fn point_in_circle(point: &Vec2, circle: &Vec2, radius: f32) -> bool {
+  circle.distance_squared(*point) < (radius).powi(2)
+}
+
+// whether a point moving at vel, relative to the circle, passes inside it during the next dt;
+// the whole swept segment is tested so a fast point cannot skip over a small circle. A point
+// already touching the circle, like a ship resting on or sliding along a surface, only
+// collides if it ends the step inside
+pub fn collides_after(pos: Vec2, vel: Vec2, dt: f32, circle: Vec2, radius: f32) -> bool {
+  let start = pos - circle;
+  let sweep = vel * dt;
+  let length_squared = sweep.length_squared();
+  if start.length_squared() < radius.powi(2) || length_squared == 0. {
+    return (start + sweep).length_squared() < radius.powi(2);
+  }
+  let t = (-start.dot(sweep) / length_squared).clamp(0., 1.);
+  (start + sweep * t).length_squared() < radius.powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::PI;
+
+  #[test]
+  fn normalize_angle_wraps_into_half_turn() {
+    assert!((normalize_angle(2. * PI + 0.1) - 0.1).abs() < 1e-5);
+    assert!((normalize_angle(-2. * PI - 0.1) + 0.1).abs() < 1e-5);
+    assert!((normalize_angle(1.5 * PI) + 0.5 * PI).abs() < 1e-5);
+    assert!((normalize_angle(-1.5 * PI) - 0.5 * PI).abs() < 1e-5);
+    assert!((normalize_angle(20. * PI + 0.3) - 0.3).abs() < 1e-4);
+  }
+
+  #[test]
+  fn softening_caps_the_pull_at_zero_separation() {
+    let g = SimConfig::default().g;
+    let (a, b) = gravity_acc(Vec2::ZERO, 1., Vec2::ZERO, 1., g, 2., 10.);
+    assert_eq!((a, b), (Vec2::ZERO, Vec2::ZERO));
+    let (plain, _) = gravity_acc(vec2(3., 4.), 1., Vec2::ZERO, 100., g, 2., 0.);
+    assert!((plain.length() - g * 100. / 25.).abs() < 1e-4);
+    let (softened, _) = gravity_acc(vec2(3., 4.), 1., Vec2::ZERO, 100., g, 2., 5.);
+    assert!((softened.length() - g * 100. * 5. / 50_f32.powf(1.5)).abs() < 1e-4);
+  }
+
+  #[test]
+  fn circular_orbital_state_matches_the_old_circular_formula() {
+    let config = SimConfig::default();
+    for (mass, distance, angle) in [(30000000., 150000., 0_f32), (30000., 2500., 73.), (1000., 60., -140.)] {
+      let delta_vector = rotate_vec2_by_rad(&vec2(distance, 0.), angle.to_radians());
+      let old = (delta_vector, delta_vector.perp().normalize() * (mass / distance * config.g).sqrt());
+      assert_eq!(orbital_state(mass, distance, 0., angle, config.g), old);
+      assert_eq!(get_initial_position_and_velocity(mass, distance, angle, &config), old);
+    }
+  }
+
+  #[test]
+  fn a_step_that_jumps_past_a_body_still_collides() {
+    // both ends of the step are well outside the body
+    assert!(collides_after(vec2(-100., 0.), vec2(10000., 0.), 0.1, Vec2::ZERO, 10.));
+    assert!(!collides_after(vec2(-100., 20.), vec2(10000., 0.), 0.1, Vec2::ZERO, 10.));
+    // relative to a moving body the same step is a pass-through
+    assert!(collides_after(vec2(-100., 0.), vec2(5000., 0.) - vec2(-5000., 0.), 0.1, Vec2::ZERO, 10.));
+    // sliding along the surface is not a collision, sinking into it is
+    assert!(!collides_after(vec2(10., 0.), vec2(0., 50.), 0.1, Vec2::ZERO, 10.));
+    assert!(collides_after(vec2(10., 0.), vec2(-50., 0.), 0.1, Vec2::ZERO, 10.));
+  }
+
+  #[test]
+  fn exponent_two_is_the_inverse_square_and_others_scale_with_distance() {
+    let g = SimConfig::default().g;
+    let at = |exponent: f32| gravity_acc(vec2(10., 0.), 1., Vec2::ZERO, 100., g, exponent, 0.).0;
+    assert_eq!(at(2.), vec2(-g * 100. / 100., 0.));
+    assert!((at(1.).length() - g * 100. / 10.).abs() < 1e-3);
+    assert!((at(3.).length() - g * 100. / 1000.).abs() < 1e-5);
+  }
+}
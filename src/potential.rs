@@ -0,0 +1,74 @@
+use macroquad::prelude::*;
+
+use crate::CelestialBodyReference;
+
+const HEATMAP_COLUMNS: usize = 48;
+
+// distance is clamped to the body radius so the well has a finite floor
+pub fn potential_at(pos: Vec2, celestial_bodies: &[CelestialBodyReference], g: f32) -> f32 {
+  celestial_bodies.iter()
+    .map(|cb| {
+      let cb = cb.borrow();
+      -g * cb.mov.mass / (pos - cb.mov.pos).length().max(cb.radius)
+    })
+    .sum()
+}
+
+// coarse grid in world coordinates, so it stays in place between resamples
+pub struct PotentialHeatmap {
+  origin: Vec2,
+  cell_size: f32,
+  columns: usize,
+  colors: Vec<Color>,
+}
+
+impl PotentialHeatmap {
+  pub fn sample(center: Vec2, half_extent: Vec2, celestial_bodies: &[CelestialBodyReference], g: f32) -> Self {
+    let cell_size = half_extent.x * 2. / HEATMAP_COLUMNS as f32;
+    let rows = (half_extent.y * 2. / cell_size).ceil() as usize;
+    let origin = center - half_extent;
+    let depths: Vec<f32> = (0..rows * HEATMAP_COLUMNS)
+      .map(|i| {
+        let cell = vec2((i % HEATMAP_COLUMNS) as f32 + 0.5, (i / HEATMAP_COLUMNS) as f32 + 0.5);
+        potential_at(origin + cell * cell_size, celestial_bodies, g).abs().max(f32::MIN_POSITIVE).ln()
+      })
+      .collect();
+    let min = depths.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = depths.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(f32::EPSILON);
+    let colors = depths.iter()
+      .map(|d| {
+        let t = (d - min) / range;
+        Color::new(t, 0.2 * t, 1. - t, 0.35)
+      })
+      .collect();
+
+    Self { origin, cell_size, columns: HEATMAP_COLUMNS, colors }
+  }
+
+  pub fn draw(&self, focus: Vec2, scale: f32) {
+    let size = self.cell_size / scale;
+    for (i, color) in self.colors.iter().enumerate() {
+      let cell = vec2((i % self.columns) as f32, (i / self.columns) as f32);
+      let p = (self.origin + cell * self.cell_size - focus) / scale;
+      draw_rectangle(p.x, p.y, size, size, *color);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::SimParams;
+  use crate::{wrap_object, CelestialBody, CelestialBodyType};
+
+  #[test]
+  fn potential_is_deeper_near_a_massive_body() {
+    let params = SimParams::default();
+    let star = wrap_object(CelestialBody::new(Vec2::ZERO, 30000000., 7000., CelestialBodyType::Star, ORANGE, "Star".to_owned(), &params));
+    let bodies = [star];
+    let near = potential_at(vec2(10000., 0.), &bodies, params.config.g);
+    let far = potential_at(vec2(1000000., 0.), &bodies, params.config.g);
+    assert!(near < far && far < 0.);
+  }
+}
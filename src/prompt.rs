@@ -0,0 +1,71 @@
+use macroquad::prelude::*;
+
+const PROMPT_MAX_LEN: usize = 40;
+
+pub enum PromptOutcome {
+  Editing,
+  Submitted(String),
+  Cancelled,
+}
+
+// a one-line text field filled from the typed characters; the main loop skips its hotkeys
+// while one is open so the letters only go into the text
+pub struct TextPrompt {
+  pub text: String,
+}
+
+impl TextPrompt {
+  pub fn new(text: String) -> Self {
+    Self { text }
+  }
+
+  // an empty or blank text cannot be submitted, the prompt stays open
+  pub fn update(&mut self, typed: &[char], backspace: bool, submit: bool, cancel: bool) -> PromptOutcome {
+    if cancel {
+      return PromptOutcome::Cancelled;
+    }
+    for c in typed.iter().filter(|c| !c.is_control()) {
+      if self.text.chars().count() < PROMPT_MAX_LEN {
+        self.text.push(*c);
+      }
+    }
+    if backspace {
+      self.text.pop();
+    }
+    if submit && !self.text.trim().is_empty() {
+      return PromptOutcome::Submitted(self.text.trim().to_owned());
+    }
+    PromptOutcome::Editing
+  }
+}
+
+// macroquad queues every typed character and hands them back newest first; drained each
+// frame so the queue does not grow and the characters come out in typing order
+pub fn typed_chars() -> Vec<char> {
+  let mut typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+  typed.reverse();
+  typed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn typing_edits_and_submits_the_trimmed_text() {
+    let mut prompt = TextPrompt::new("Seed 1".to_owned());
+    assert!(matches!(prompt.update(&['x', '\r'], true, false, false), PromptOutcome::Editing));
+    assert_eq!(prompt.text, "Seed 1");
+    prompt.update(&[' ', '!', ' '], false, false, false);
+    assert!(matches!(prompt.update(&[], false, true, false), PromptOutcome::Submitted(name) if name == "Seed 1 !"));
+  }
+
+  #[test]
+  fn blank_text_is_not_submitted_and_escape_cancels() {
+    let mut prompt = TextPrompt::new(String::new());
+    assert!(matches!(prompt.update(&[' '], false, true, false), PromptOutcome::Editing));
+    assert!(matches!(prompt.update(&['a'], false, true, true), PromptOutcome::Cancelled));
+    prompt.update(&['a'; PROMPT_MAX_LEN + 5], false, false, false);
+    assert_eq!(prompt.text.chars().count(), PROMPT_MAX_LEN);
+  }
+}
@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+const RECORDER_CAPACITY: usize = 4096;
+const RECORD_EVERY: f32 = 0.1;
+const REPLAY_WINDOW: f32 = 60.;
+
+// rolling record of the active ship's path in sim time
+pub struct TrajectoryRecorder {
+  samples: VecDeque<(f32, Vec2)>,
+  elapsed: f32,
+}
+
+impl TrajectoryRecorder {
+  pub fn new() -> Self {
+    Self { samples: VecDeque::new(), elapsed: 0. }
+  }
+
+  pub fn record(&mut self, pos: Vec2, sim_dt: f32) {
+    if sim_dt <= 0. {
+      return;
+    }
+    self.elapsed += sim_dt;
+    if self.samples.back().is_some_and(|(t, _)| self.elapsed - t < RECORD_EVERY) {
+      return;
+    }
+    if self.samples.len() == RECORDER_CAPACITY {
+      self.samples.pop_front();
+    }
+    self.samples.push_back((self.elapsed, pos));
+  }
+
+  pub fn start_time(&self) -> f32 {
+    self.samples.front().map_or(0., |(t, _)| *t)
+  }
+
+  pub fn end_time(&self) -> f32 {
+    self.samples.back().map_or(0., |(t, _)| *t)
+  }
+
+  pub fn position_at(&self, time: f32) -> Option<Vec2> {
+    let after = self.samples.iter().position(|(t, _)| *t >= time)?;
+    if after == 0 {
+      return Some(self.samples[0].1);
+    }
+    let (t0, p0) = self.samples[after - 1];
+    let (t1, p1) = self.samples[after];
+    Some(p0.lerp(p1, (time - t0) / (t1 - t0)))
+  }
+
+  pub fn path(&self) -> impl Iterator<Item = Vec2> + '_ {
+    self.samples.iter().map(|(_, p)| *p)
+  }
+}
+
+pub struct Replay {
+  pub time: f32,
+}
+
+impl Replay {
+  // starts shortly before the latest recorded moment
+  pub fn new(recorder: &TrajectoryRecorder) -> Option<Self> {
+    if recorder.samples.len() < 2 {
+      return None;
+    }
+    Some(Self { time: (recorder.end_time() - REPLAY_WINDOW).max(recorder.start_time()) })
+  }
+
+  pub fn advance(&mut self, recorder: &TrajectoryRecorder, dt: f32) {
+    self.time = (self.time + dt).clamp(recorder.start_time(), recorder.end_time());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn playback_reproduces_the_recorded_positions_at_their_times() {
+    let mut recorder = TrajectoryRecorder::new();
+    let path = |t: f32| vec2(t * 10., (t * 0.5).sin() * 100.);
+    let mut time = 0.;
+    for _ in 0..400 {
+      time += 0.05;
+      recorder.record(path(time), 0.05);
+    }
+    let samples: Vec<(f32, Vec2)> = recorder.samples.iter().copied().collect();
+    let mut replay = Replay::new(&recorder).unwrap();
+    assert_eq!(replay.time, recorder.start_time());
+    for (t, pos) in samples {
+      // frames of uneven length that land on the sample
+      let gap = t - replay.time;
+      replay.advance(&recorder, gap * 0.3);
+      replay.advance(&recorder, gap * 0.7);
+      assert!((replay.time - t).abs() < 1e-3);
+      assert!(recorder.position_at(replay.time).unwrap().distance(pos) < 1e-2);
+    }
+    // playback stops at the end of the recording
+    replay.advance(&recorder, 10.);
+    assert_eq!(replay.time, recorder.end_time());
+  }
+}
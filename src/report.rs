@@ -0,0 +1,79 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+
+use crate::orbit::osculating_elements;
+use crate::simulation::Simulation;
+use crate::{orbital_parent, CelestialBodyType};
+
+pub fn system_report(sim: &Simulation, g: f32, day_length: f32, au: f32) -> String {
+  let star = sim.cb_parent.borrow();
+  let mut report = String::new();
+  let _ = writeln!(report, "Solar system {} facts, day {}", sim.seed, sim.day_count);
+  let _ = writeln!(report, "Star: {}, mass {:.0}", star.name, star.mov.mass);
+  let _ = writeln!(report, "Bodies: {} ({} asteroids)", sim.all_celestial_bodies.len(), sim.minor_celestial_bodies.len());
+
+  let planets: Vec<_> = sim.major_celestial_bodies.iter()
+    .filter(|cb| matches!(cb.borrow().cb_type, CelestialBodyType::Planet))
+    .collect();
+  let _ = writeln!(report, "Planets: {}", planets.len());
+  for planet in planets {
+    let p = planet.borrow();
+    let elements = osculating_elements(p.mov.pos - star.mov.pos, p.mov.vel - star.mov.vel, g * star.mov.mass);
+    let period = match elements.period {
+      Some(period) => format!("{:.1} days", period / day_length),
+      None => "unbound".to_string(),
+    };
+    let _ = writeln!(report, "  {}: {:.2} AU, period {}", p.name, (p.mov.pos - star.mov.pos).length() / au, period);
+  }
+
+  let largest_moon = sim.major_celestial_bodies.iter()
+    .filter(|cb| matches!(cb.borrow().cb_type, CelestialBodyType::Moon))
+    .max_by(|a, b| a.borrow().radius.total_cmp(&b.borrow().radius));
+  match largest_moon {
+    Some(moon) => {
+      let parent = orbital_parent(moon, &sim.major_celestial_bodies)
+        .map(|p| p.borrow().name.clone())
+        .unwrap_or_else(|| "nothing".to_string());
+      let moon = moon.borrow();
+      let _ = writeln!(report, "Largest moon: {} (radius {:.0}) around {}", moon.name, moon.radius, parent);
+    },
+    None => { let _ = writeln!(report, "Largest moon: none"); },
+  }
+
+  let distances: Vec<f32> = sim.minor_celestial_bodies.iter()
+    .map(|cb| (cb.borrow().mov.pos - star.mov.pos).length())
+    .collect();
+  if distances.is_empty() {
+    let _ = writeln!(report, "Belt: empty");
+  } else {
+    let inner = distances.iter().copied().fold(f32::INFINITY, f32::min);
+    let outer = distances.iter().copied().fold(0., f32::max);
+    let _ = writeln!(report, "Belt: {:.2} - {:.2} AU", inner / au, outer / au);
+  }
+
+  let total_mass: f32 = sim.all_celestial_bodies.iter().map(|cb| cb.borrow().mov.mass).sum();
+  let _ = writeln!(report, "Total system mass: {:.0} ({:.4}% outside the star)", total_mass, (1. - star.mov.mass / total_mass) * 100.);
+  report
+}
+
+pub fn export_report(report: &str, path: &str) -> io::Result<()> {
+  fs::write(path, report)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::SimParams;
+
+  #[test]
+  fn report_names_the_star_and_counts_the_planets() {
+    let params = SimParams::default();
+    let sim = Simulation::new(2, &params);
+    let planets = sim.major_celestial_bodies.iter().filter(|cb| matches!(cb.borrow().cb_type, CelestialBodyType::Planet)).count();
+    let report = system_report(&sim, params.config.g, params.day_length, params.config.au);
+    assert!(report.lines().any(|line| line.starts_with(&format!("Star: {},", sim.cb_parent.borrow().name))));
+    assert!(report.lines().any(|line| line == format!("Planets: {}", planets)));
+    assert!(planets > 0);
+  }
+}
@@ -0,0 +1,417 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::library::SystemConfig;
+use crate::movable::Movable;
+use crate::params::SimParams;
+use crate::simulation::{add_body, add_debris, add_ship, remove_body, Simulation};
+use crate::timer::Timer;
+use crate::{wrap_object, CelestialBody, CelestialBodyType, ShipState};
+
+const AUTOSAVE_PREFIX: &str = "autosave_";
+const AUTOSAVE_EXTENSION: &str = ".json";
+
+#[derive(Serialize, Deserialize)]
+struct SavedMovable {
+  pos: [f32; 2],
+  vel: [f32; 2],
+  rot: f32,
+  rot_vel: f32,
+}
+
+impl SavedMovable {
+  fn new(mov: &Movable) -> Self {
+    Self { pos: [mov.pos.x, mov.pos.y], vel: [mov.vel.x, mov.vel.y], rot: mov.rot, rot_vel: mov.rot_vel }
+  }
+
+  fn restore(&self, mass: f32) -> Movable {
+    let mut mov = Movable::new(vec2(self.pos[0], self.pos[1]), vec2(self.vel[0], self.vel[1]), mass, self.rot);
+    mov.rot_vel = self.rot_vel;
+    mov
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedSystem {
+  config: SystemConfig,
+  day_count: u32,
+  elapsed_seconds: f64,
+  step_count: u64,
+  active_ship: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedBody {
+  name: String,
+  cb_type: String,
+  tags: Vec<String>,
+  mov: SavedMovable,
+  mass: f32,
+  radius: f32,
+  // None for the star's unbounded one, JSON has no infinity
+  hill_radius: Option<f32>,
+  color: [f32; 3],
+  // seconds left for a debris piece
+  lifetime: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SavedShipState {
+  // on is an index into the saved bodies, names are not unique
+  Landed { on: usize, offset: [f32; 2] },
+  InSpace,
+  Destroyed,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedShip {
+  name: String,
+  mov: SavedMovable,
+  fuel: f32,
+  max_fuel: f32,
+  total_dv_used: f32,
+  cargo_mass: f32,
+  color: [f32; 3],
+  state: SavedShipState,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+  system: SavedSystem,
+  bodies: Vec<SavedBody>,
+  ships: Vec<SavedShip>,
+}
+
+fn encode_body_type(cb_type: &CelestialBodyType) -> &'static str {
+  match cb_type {
+    CelestialBodyType::Star => "star",
+    CelestialBodyType::Planet => "planet",
+    CelestialBodyType::Moon => "moon",
+    CelestialBodyType::Asteroid => "asteroid",
+  }
+}
+
+fn decode_body_type(name: &str) -> Option<CelestialBodyType> {
+  match name {
+    "star" => Some(CelestialBodyType::Star),
+    "planet" => Some(CelestialBodyType::Planet),
+    "moon" => Some(CelestialBodyType::Moon),
+    "asteroid" => Some(CelestialBodyType::Asteroid),
+    _ => None,
+  }
+}
+
+fn snapshot(sim: &Simulation, params: &SimParams) -> SavedState {
+  let system = SavedSystem {
+    config: SystemConfig::new(sim.seed, params),
+    day_count: sim.day_count,
+    elapsed_seconds: sim.elapsed_seconds,
+    step_count: sim.step_count,
+    active_ship: sim.ships.iter().position(|s| Rc::ptr_eq(s, &sim.ship)).unwrap_or(0),
+  };
+  let bodies = sim.all_celestial_bodies.iter().map(|body| {
+    let cb = body.borrow();
+    SavedBody {
+      name: cb.name.clone(),
+      cb_type: encode_body_type(&cb.cb_type).to_owned(),
+      tags: cb.tags.clone(),
+      mov: SavedMovable::new(&cb.mov),
+      mass: cb.mov.mass,
+      radius: cb.radius,
+      hill_radius: Some(cb.hill_radius).filter(|r| r.is_finite()),
+      color: [cb.color.r, cb.color.g, cb.color.b],
+      lifetime: sim.debris_lifetime(body),
+    }
+  }).collect();
+  let ships = sim.ships.iter().map(|s| {
+    let s = s.borrow();
+    let state = match &s.state {
+      ShipState::Landed(cb, _, offset) => match sim.all_celestial_bodies.iter().position(|b| Rc::ptr_eq(b, cb)) {
+        Some(on) => SavedShipState::Landed { on, offset: [offset.x, offset.y] },
+        None => SavedShipState::InSpace,
+      },
+      ShipState::InSpace => SavedShipState::InSpace,
+      ShipState::Destroyed => SavedShipState::Destroyed,
+    };
+    SavedShip {
+      name: s.name.clone(),
+      mov: SavedMovable::new(&s.mov),
+      fuel: s.fuel,
+      max_fuel: s.max_fuel,
+      total_dv_used: s.total_dv_used,
+      cargo_mass: s.cargo_mass,
+      color: [s.color.r, s.color.g, s.color.b],
+      state,
+    }
+  }).collect();
+  SavedState { system, bodies, ships }
+}
+
+// the system is regenerated from its config, then every body and ship is moved to its saved state
+pub fn encode_state(sim: &Simulation, params: &SimParams) -> String {
+  serde_json::to_string_pretty(&snapshot(sim, params)).expect("the snapshot is plain data")
+}
+
+pub fn restore_state(json: &str, params: &SimParams) -> Option<(SimParams, Simulation)> {
+  let state: SavedState = serde_json::from_str(json).ok()?;
+  let params = state.system.config.apply(params);
+  let mut sim = Simulation::new(state.system.config.seed, &params);
+  sim.day_count = state.system.day_count;
+  sim.elapsed_seconds = state.system.elapsed_seconds;
+  sim.step_count = state.system.step_count;
+
+  // saved bodies claim regenerated ones by name in order; the rest left the system before the save,
+  // and saved bodies without a regenerated counterpart were placed by hand or are debris and are rebuilt
+  let mut unclaimed = sim.all_celestial_bodies.clone();
+  let mut placed = vec![];
+  // in the saved order, which is what landed ships index into
+  let mut bodies = vec![];
+  for saved in &state.bodies {
+    let mov = saved.mov.restore(saved.mass);
+    let cb = match unclaimed.iter().position(|cb| cb.borrow().name == saved.name) {
+      Some(i) if saved.lifetime.is_none() => unclaimed.remove(i),
+      _ => {
+        let color = Color::new(saved.color[0], saved.color[1], saved.color[2], 1.);
+        let cb_type = decode_body_type(&saved.cb_type)?;
+        let cb = wrap_object(CelestialBody::new(mov.pos, saved.mass, saved.radius, cb_type, color, saved.name.clone(), &params));
+        placed.push((cb.clone(), saved.lifetime));
+        cb
+      },
+    };
+    {
+      let mut cb = cb.borrow_mut();
+      cb.radius = saved.radius;
+      cb.hill_radius = saved.hill_radius.unwrap_or(f32::INFINITY);
+      cb.color = Color::new(saved.color[0], saved.color[1], saved.color[2], cb.color.a);
+      cb.last_good = (mov.pos, mov.vel);
+      cb.mov = mov;
+      cb.tags = saved.tags.clone();
+    }
+    bodies.push(cb);
+  }
+  for cb in unclaimed {
+    remove_body(&mut sim, &cb);
+  }
+  for (cb, lifetime) in placed {
+    match lifetime {
+      Some(lifetime) => add_debris(&mut sim, cb, lifetime),
+      None => add_body(&mut sim, cb),
+    }
+  }
+
+  for (i, saved) in state.ships.iter().enumerate() {
+    if i >= sim.ships.len() {
+      let copy = sim.ship.borrow().clone();
+      add_ship(&mut sim, wrap_object(copy));
+    }
+    let mut s = sim.ships[i].borrow_mut();
+    s.mov = saved.mov.restore(s.mov.mass);
+    s.last_vel = s.mov.vel;
+    s.name = saved.name.clone();
+    s.color = Color::new(saved.color[0], saved.color[1], saved.color[2], 1.);
+    s.fuel = saved.fuel;
+    s.max_fuel = saved.max_fuel;
+    s.total_dv_used = saved.total_dv_used;
+    s.cargo_mass = saved.cargo_mass;
+    s.state = match &saved.state {
+      SavedShipState::Landed { on, offset } => ShipState::Landed(bodies.get(*on)?.clone(), Vec2::ZERO, vec2(offset[0], offset[1])),
+      SavedShipState::InSpace => ShipState::InSpace,
+      SavedShipState::Destroyed => ShipState::Destroyed,
+    };
+    s.in_hill_radius_of.clear();
+  }
+  // regenerated ships beyond the saved ones were gone by the save
+  for extra in sim.ships.split_off(state.ships.len().min(sim.ships.len())) {
+    sim.game_objects.retain(|go| !std::ptr::addr_eq(Rc::as_ptr(go), Rc::as_ptr(&extra)));
+  }
+  match sim.ships.get(state.system.active_ship).or(sim.ships.first()).cloned() {
+    Some(ship) => sim.ship = ship,
+    // as after remove_dead_ships took the last one, the active ship stays behind as a wreck
+    None => sim.ship.borrow_mut().state = ShipState::Destroyed,
+  }
+  Some((params, sim))
+}
+
+pub fn save_state(path: &Path, sim: &Simulation, params: &SimParams) -> io::Result<()> {
+  fs::write(path, encode_state(sim, params))
+}
+
+pub fn load_state(path: &Path, params: &SimParams) -> io::Result<(SimParams, Simulation)> {
+  restore_state(&fs::read_to_string(path)?, params)
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a readable save", path.display())))
+}
+
+fn autosave_number(path: &Path) -> Option<u64> {
+  path.file_name()?.to_str()?.strip_prefix(AUTOSAVE_PREFIX)?.strip_suffix(AUTOSAVE_EXTENSION)?.parse().ok()
+}
+
+// oldest first
+fn autosaves(dir: &str) -> Vec<(u64, PathBuf)> {
+  let mut saves: Vec<(u64, PathBuf)> = fs::read_dir(dir).into_iter().flatten()
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| autosave_number(&entry.path()).map(|n| (n, entry.path())))
+    .collect();
+  saves.sort_by_key(|(n, _)| *n);
+  saves
+}
+
+pub fn newest_autosave(dir: &str) -> Option<PathBuf> {
+  autosaves(dir).pop().map(|(_, path)| path)
+}
+
+// writes the state every interval seconds of dt, keeping at most keep files in dir
+pub struct AutoSave {
+  dir: String,
+  keep: usize,
+  timer: Timer,
+}
+
+impl AutoSave {
+  pub fn new(dir: &str, interval: f32, keep: usize) -> Self {
+    Self { dir: dir.to_owned(), keep: keep.max(1), timer: Timer::new(interval) }
+  }
+
+  // Some with the written path (or the error) whenever the interval elapsed
+  pub fn update(&mut self, dt: f32, sim: &Simulation, params: &SimParams) -> Option<io::Result<PathBuf>> {
+    self.timer.update(dt);
+    if !self.timer.is_just_over() {
+      return None;
+    }
+    Some(self.write(sim, params))
+  }
+
+  fn write(&self, sim: &Simulation, params: &SimParams) -> io::Result<PathBuf> {
+    let mut saves = autosaves(&self.dir);
+    let next = saves.last().map_or(1, |(n, _)| n + 1);
+    let path = Path::new(&self.dir).join(format!("{}{}{}", AUTOSAVE_PREFIX, next, AUTOSAVE_EXTENSION));
+    fs::write(&path, encode_state(sim, params))?;
+    saves.push((next, path.clone()));
+    while saves.len() > self.keep {
+      fs::remove_file(saves.remove(0).1)?;
+    }
+    Ok(path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("solsys_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn autosave_writes_after_the_interval_and_keeps_the_newest() {
+    let dir = scratch_dir("autosave");
+    let params = SimParams::default();
+    let sim = Simulation::new(1, &params);
+    let mut autosave = AutoSave::new(dir.to_str().unwrap(), 1., 2);
+
+    assert!(autosave.update(0.6, &sim, &params).is_none());
+    assert!(autosaves(dir.to_str().unwrap()).is_empty());
+    for _ in 0..3 {
+      assert!(autosave.update(0.6, &sim, &params).is_some_and(|written| written.is_ok()));
+      assert!(autosave.update(0.6, &sim, &params).is_none());
+    }
+    let kept: Vec<u64> = autosaves(dir.to_str().unwrap()).into_iter().map(|(n, _)| n).collect();
+    assert_eq!(kept, vec![2, 3]);
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn a_saved_state_restores_bodies_and_the_landed_ship() {
+    let params = SimParams::default();
+    let mut sim = Simulation::new(1, &params);
+    for _ in 0..50 {
+      sim.step(&params, params.step_dt());
+    }
+    let body = sim.major_celestial_bodies[2].clone();
+    {
+      let mut ship = sim.ship.borrow_mut();
+      ship.state = ShipState::Landed(body.clone(), Vec2::ZERO, vec2(0., body.borrow().radius));
+      ship.fuel = 12.5;
+    }
+    let saved = encode_state(&sim, &params);
+
+    let (_, restored) = restore_state(&saved, &params).unwrap();
+    assert_eq!(encode_state(&restored, &params), saved);
+    assert_eq!((restored.day_count, restored.elapsed_seconds, restored.step_count), (sim.day_count, sim.elapsed_seconds, sim.step_count));
+    for (a, b) in sim.all_celestial_bodies.iter().zip(&restored.all_celestial_bodies) {
+      let (a, b) = (a.borrow(), b.borrow());
+      assert_eq!((&a.name, a.mov.pos, a.mov.vel, a.mov.mass), (&b.name, b.mov.pos, b.mov.vel, b.mov.mass));
+    }
+    let ship = restored.ship.borrow();
+    assert_eq!(ship.fuel, 12.5);
+    match &ship.state {
+      ShipState::Landed(on, _, _) => assert!(restored.all_celestial_bodies.iter().any(|cb| Rc::ptr_eq(cb, on)) && on.borrow().name == body.borrow().name),
+      _ => panic!("the ship is no longer landed"),
+    }
+  }
+
+  #[test]
+  fn debris_keeps_its_lifetime_and_a_landed_ship_its_body_when_names_repeat() {
+    let params = SimParams::default();
+    let mut sim = Simulation::new(1, &params);
+    let pieces: Vec<_> = [7.5, 20.].into_iter().map(|lifetime| {
+      let piece = wrap_object(CelestialBody::new(vec2(5000., lifetime * 100.), 1., 5., CelestialBodyType::Asteroid, GRAY, "Debris of Test".to_owned(), &params));
+      add_debris(&mut sim, piece.clone(), lifetime);
+      piece
+    }).collect();
+    sim.ship.borrow_mut().state = ShipState::Landed(pieces[1].clone(), Vec2::ZERO, vec2(0., 5.));
+
+    let (_, restored) = restore_state(&encode_state(&sim, &params), &params).unwrap();
+    let restored_pieces: Vec<_> = restored.all_celestial_bodies.iter().filter(|cb| cb.borrow().name == "Debris of Test").cloned().collect();
+    let lifetimes: Vec<Option<f32>> = restored_pieces.iter().map(|cb| restored.debris_lifetime(cb)).collect();
+    assert_eq!(lifetimes, [Some(7.5), Some(20.)]);
+    let ship = restored.ship.borrow();
+    match &ship.state {
+      ShipState::Landed(on, _, _) => assert!(Rc::ptr_eq(on, &restored_pieces[1])),
+      _ => panic!("the ship is no longer landed"),
+    }
+  }
+
+  #[test]
+  fn a_save_without_ships_restores_none_and_leaves_the_active_one_a_wreck() {
+    let params = SimParams::default();
+    let mut sim = Simulation::new(1, &params);
+    sim.ship.borrow_mut().state = ShipState::Destroyed;
+    sim.remove_dead_ships();
+    let saved = encode_state(&sim, &params);
+
+    let (_, restored) = restore_state(&saved, &params).unwrap();
+    assert!(restored.ships.is_empty());
+    assert!(matches!(restored.ship.borrow().state, ShipState::Destroyed));
+    assert!(!restored.game_objects.iter().any(|go| std::ptr::addr_eq(Rc::as_ptr(go), Rc::as_ptr(&restored.ship))));
+    assert_eq!(encode_state(&restored, &params), saved);
+  }
+
+  #[test]
+  fn two_saved_ships_restore_with_the_active_one() {
+    let params = SimParams::default();
+    let mut sim = Simulation::new(1, &params);
+    let second = {
+      let mut s = sim.ship.borrow().clone();
+      s.name = "Second".to_owned();
+      s.mov.pos += vec2(100., 0.);
+      wrap_object(s)
+    };
+    add_ship(&mut sim, second.clone());
+    sim.ship = second;
+    let saved = encode_state(&sim, &params);
+
+    let (_, restored) = restore_state(&saved, &params).unwrap();
+    assert_eq!(restored.ships.len(), 2);
+    assert!(Rc::ptr_eq(&restored.ship, &restored.ships[1]));
+    assert_eq!(restored.ship.borrow().name, "Second");
+    assert_eq!(restored.game_objects.len(), sim.game_objects.len());
+    assert_eq!(encode_state(&restored, &params), saved);
+  }
+}
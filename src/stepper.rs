@@ -0,0 +1,38 @@
+// a frame longer than this (a stall, a dragged window) only advances the simulation by this
+// much real time, so a slow frame cannot ask for ever more steps in the next one
+const MAX_FRAME_TIME: f32 = 0.05;
+
+// real frame time turned into whole physics steps; the remainder carries over to the next frame
+pub struct StepAccumulator {
+  pending: f32,
+}
+
+impl StepAccumulator {
+  pub fn new() -> Self {
+    Self { pending: 0. }
+  }
+
+  // steps of step_dt that speed times the frame's real time covers
+  pub fn drain(&mut self, frame_time: f32, speed: f32, step_dt: f32) -> usize {
+    if step_dt <= 0. {
+      return 0;
+    }
+    self.pending += frame_time.clamp(0., MAX_FRAME_TIME) * speed;
+    let steps = (self.pending / step_dt) as usize;
+    self.pending -= steps as f32 * step_dt;
+    steps
+  }
+
+  // time that does not run, like a pause, is not made up for afterwards
+  pub fn clear(&mut self) {
+    self.pending = 0.;
+  }
+
+  // fraction of a step the simulation is owed, for drawing between its last two states
+  pub fn alpha(&self, step_dt: f32) -> f32 {
+    if step_dt <= 0. {
+      return 1.;
+    }
+    (self.pending / step_dt).clamp(0., 1.)
+  }
+}
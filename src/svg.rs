@@ -0,0 +1,143 @@
+use macroquad::prelude::*;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+
+use crate::params::DisplayRadii;
+use crate::physics::rotate_vec2_by_rad;
+use crate::simulation::Simulation;
+use crate::{CelestialBodyType, SHIP_SIZE};
+
+pub struct Viewport {
+  pub focus: Vec2,
+  pub scale: f32,
+  pub size: Vec2,
+  pub labels: bool,
+  pub radii: DisplayRadii,
+}
+
+impl Viewport {
+  fn to_screen(&self, pos: Vec2) -> Vec2 {
+    (pos - self.focus) / self.scale + self.size / 2.
+  }
+}
+
+fn svg_color(color: Color) -> String {
+  let [r, g, b, _]: [u8; 4] = color.into();
+  format!("rgb({},{},{})", r, g, b)
+}
+
+// names are free text, so markup characters in them are escaped before going into the document
+fn xml_escape(text: &str) -> String {
+  text.chars().fold(String::with_capacity(text.len()), |mut out, c| {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&apos;"),
+      c => out.push(c),
+    }
+    out
+  })
+}
+
+pub fn export_svg(sim: &Simulation, trails: &[Vec<(Vec2, Color)>], path: &str, viewport: &Viewport) -> io::Result<()> {
+  let mut svg = String::new();
+  let _ = writeln!(
+    svg,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+    w = viewport.size.x, h = viewport.size.y
+  );
+  let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="black"/>"#);
+
+  // each trail is one polyline, stroked in the colour of its first point
+  for trail in trails.iter().filter(|trail| trail.len() > 1) {
+    let color = trail[0].1;
+    let points: Vec<String> = trail.iter()
+      .map(|(p, _)| viewport.to_screen(*p))
+      .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+      .collect();
+    let _ = writeln!(svg, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1"/>"#, points.join(" "), svg_color(color));
+  }
+
+  for cb in &sim.all_celestial_bodies {
+    let cb = cb.borrow();
+    let p = viewport.to_screen(cb.mov.pos);
+    let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}"/>"#, p.x, p.y, cb.display_radius(viewport.scale, &viewport.radii), svg_color(cb.color));
+    if viewport.labels && !matches!(cb.cb_type, CelestialBodyType::Asteroid) {
+      let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" fill="{}" font-size="12">{}</text>"#, p.x, p.y - cb.display_radius(viewport.scale, &viewport.radii) - 4., svg_color(cb.color), xml_escape(&cb.name));
+    }
+  }
+
+  for s in &sim.ships {
+    let s = s.borrow();
+    let p = viewport.to_screen(s.mov.pos);
+    let v = vec2((SHIP_SIZE / viewport.scale).max(3.), 0.);
+    let corners: Vec<String> = [0., 135., -135.].iter()
+      .map(|a: &f32| p + rotate_vec2_by_rad(&v, s.mov.rot + a.to_radians()))
+      .map(|c| format!("{:.1},{:.1}", c.x, c.y))
+      .collect();
+    let _ = writeln!(svg, r#"<polygon points="{}" fill="none" stroke="{}" stroke-width="2"/>"#, corners.join(" "), svg_color(s.color));
+    if viewport.labels {
+      let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" fill="{}" font-size="12">{}</text>"#, p.x, p.y - v.x - 4., svg_color(s.color), xml_escape(&s.name));
+    }
+  }
+
+  svg.push_str("</svg>\n");
+  fs::write(path, svg)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::params::{DetailLevel, SimParams};
+
+  #[test]
+  fn xml_escape_replaces_markup() {
+    assert_eq!(xml_escape("Ship <1> & \"Moon's\""), "Ship &lt;1&gt; &amp; &quot;Moon&apos;s&quot;");
+    assert_eq!(xml_escape("Io"), "Io");
+  }
+
+  #[test]
+  fn export_writes_one_shape_per_trail_body_and_ship() {
+    let params = SimParams::default();
+    let sim = Simulation::new(1, &params);
+    let trails = vec![
+      vec![(Vec2::ZERO, WHITE), (vec2(10., 0.), WHITE)],
+      vec![(Vec2::ZERO, RED)],
+      vec![(Vec2::ZERO, BLUE), (vec2(0., 10.), BLUE), (vec2(10., 10.), BLUE)],
+    ];
+    let viewport = Viewport { focus: Vec2::ZERO, scale: 100., size: vec2(800., 600.), labels: true, radii: params.min_display_radius };
+    let path = std::env::temp_dir().join(format!("solsys_svg_{}.svg", std::process::id()));
+    export_svg(&sim, &trails, path.to_str().unwrap(), &viewport).unwrap();
+    let svg = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let named = sim.all_celestial_bodies.iter().filter(|cb| !matches!(cb.borrow().cb_type, CelestialBodyType::Asteroid)).count();
+    assert_eq!(svg.matches("<polyline").count(), 2);
+    assert_eq!(svg.matches("<circle").count(), sim.all_celestial_bodies.len());
+    assert_eq!(svg.matches("<polygon").count(), sim.ships.len());
+    assert_eq!(svg.matches("<text").count(), named + sim.ships.len());
+  }
+
+  #[test]
+  fn low_detail_exports_no_labels_or_trails() {
+    let params = SimParams::default();
+    let sim = Simulation::new(1, &params);
+    let count = |detail: DetailLevel| {
+      let trails = if detail.shows_trails() { vec![vec![(Vec2::ZERO, WHITE), (vec2(10., 0.), WHITE)]] } else { vec![] };
+      let viewport = Viewport { focus: Vec2::ZERO, scale: 100., size: vec2(800., 600.), labels: detail.shows_labels(), radii: params.min_display_radius };
+      let path = std::env::temp_dir().join(format!("solsys_svg_{:?}_{}.svg", detail, std::process::id()));
+      export_svg(&sim, &trails, path.to_str().unwrap(), &viewport).unwrap();
+      let svg = fs::read_to_string(&path).unwrap();
+      fs::remove_file(&path).unwrap();
+      (svg.matches("<text").count(), svg.matches("<polyline").count())
+    };
+    assert_eq!(count(DetailLevel::Low), (0, 0));
+    assert_eq!(count(DetailLevel::Medium).0, 0);
+    assert_ne!(count(DetailLevel::Medium).1, 0);
+    let (labels, trails) = count(DetailLevel::Full);
+    assert!(labels > 0 && trails > 0);
+  }
+}
@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use crate::params::SimParams;
+use crate::savegame::{encode_state, restore_state};
+use crate::simulation::Simulation;
+
+const SNAPSHOT_INTERVAL: f64 = 5.;
+const SNAPSHOT_LIMIT: usize = 64;
+
+// encoded states every few sim seconds, oldest first
+pub struct Timeline {
+  snapshots: VecDeque<(f64, String)>,
+}
+
+impl Timeline {
+  pub fn new() -> Self {
+    Self { snapshots: VecDeque::new() }
+  }
+
+  pub fn record(&mut self, sim: &Simulation, params: &SimParams) {
+    if self.snapshots.back().is_some_and(|(t, _)| sim.elapsed_seconds - t < SNAPSHOT_INTERVAL) {
+      return;
+    }
+    if self.snapshots.len() == SNAPSHOT_LIMIT {
+      self.snapshots.pop_front();
+    }
+    self.snapshots.push_back((sim.elapsed_seconds, encode_state(sim, params)));
+  }
+
+  pub fn start_time(&self) -> Option<f64> {
+    self.snapshots.front().map(|(t, _)| *t)
+  }
+
+  // restores the latest snapshot at or before target and steps it forward to target;
+  // snapshots after it belong to the abandoned future and are dropped
+  pub fn rewind(&mut self, target: f64, params: &SimParams) -> Option<(SimParams, Simulation)> {
+    let i = self.snapshots.iter().rposition(|(t, _)| *t <= target)?;
+    self.snapshots.truncate(i + 1);
+    let (params, mut sim) = restore_state(&self.snapshots[i].1, params)?;
+    let dt = params.step_dt();
+    while sim.elapsed_seconds + (dt as f64) <= target {
+      sim.step(&params, dt);
+    }
+    Some((params, sim))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rewinding_to_a_snapshot_restores_its_state() {
+    let params = SimParams::default();
+    let mut sim = Simulation::new(1, &params);
+    let mut timeline = Timeline::new();
+    let mut captured = vec![];
+    while timeline.snapshots.len() < 3 {
+      let before = timeline.snapshots.len();
+      timeline.record(&sim, &params);
+      if timeline.snapshots.len() > before {
+        captured.push((sim.elapsed_seconds, encode_state(&sim, &params)));
+      }
+      sim.step(&params, params.step_dt());
+    }
+
+    let (time, state) = &captured[1];
+    let (_, rewound) = timeline.rewind(*time, &params).unwrap();
+    assert_eq!(rewound.elapsed_seconds, *time);
+    assert_eq!(&encode_state(&rewound, &params), state);
+    assert_eq!(timeline.snapshots.len(), 2);
+    assert_eq!(timeline.start_time(), Some(captured[0].0));
+  }
+}
@@ -12,14 +12,32 @@ impl Timer {
     Self { act: 0., threshold, repeat: true, just_over: false }
   }
 
-  // pub fn new_timeout(threshold: f32) -> Self {
-  //   Self { act: 0., threshold, repeat: false, just_over: false }
-  // }
+  // fires once and then stays over until reset
+  pub fn new_timeout(threshold: f32) -> Self {
+    Self { act: 0., threshold, repeat: false, just_over: false }
+  }
+
+  pub fn reset(&mut self) {
+    self.act = 0.;
+    self.just_over = false;
+  }
+
+  // keeps the accumulated time, so shortening the threshold below it fires on the next update
+  pub fn set_threshold(&mut self, threshold: f32) {
+    self.threshold = threshold;
+  }
 
-  // pub fn reset(&mut self) {
-  //   self.act = 0.;
-  //   self.just_over = false;
-  // }
+  // fraction of the threshold elapsed, 1 once over
+  pub fn progress(&self) -> f32 {
+    if self.threshold <= 0. {
+      return 1.;
+    }
+    (self.act / self.threshold).min(1.)
+  }
+
+  pub fn remaining(&self) -> f32 {
+    (self.threshold - self.act).max(0.)
+  }
 
   pub fn is_over(&self) -> bool {
     self.act > self.threshold
@@ -31,6 +49,7 @@ impl Timer {
 
   pub fn update(&mut self, dt: f32) {
     if self.is_over() && !self.repeat {
+      self.just_over = false;
       return;
     }
     let updated_time = self.act + dt;
@@ -43,4 +62,38 @@ impl Timer {
     }
     self.act = if over_threshold && self.repeat { 0. } else { updated_time };
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn a_timeout_fires_once_and_stays_over() {
+    let mut timer = Timer::new_timeout(1.);
+    timer.update(0.6);
+    assert!(!timer.is_just_over() && !timer.is_over());
+    assert!((timer.progress() - 0.6).abs() < 1e-6 && (timer.remaining() - 0.4).abs() < 1e-6);
+    timer.update(0.6);
+    assert!(timer.is_just_over() && timer.is_over());
+    assert_eq!((timer.progress(), timer.remaining()), (1., 0.));
+    for _ in 0..5 {
+      timer.update(0.6);
+      assert!(!timer.is_just_over() && timer.is_over());
+    }
+    timer.reset();
+    assert!(!timer.is_over() && timer.progress() == 0.);
+    timer.update(1.1);
+    assert!(timer.is_just_over());
+  }
+
+  #[test]
+  fn a_repeating_timer_rearms() {
+    let mut timer = Timer::new(1.);
+    let fired: Vec<bool> = (0..6).map(|_| {
+      timer.update(0.6);
+      timer.is_just_over()
+    }).collect();
+    assert_eq!(fired, [false, true, false, true, false, true]);
+  }
+}